@@ -1,7 +1,7 @@
 use notify_debouncer_full::{new_debouncer, notify::*, DebounceEventResult, Debouncer, RecommendedCache};
 use serde::{Deserialize, Serialize};
-use std::{path::PathBuf, time::Duration};
-use titan_core::{error, info, ArcLock, Channels, Result};
+use std::{collections::{HashMap, HashSet}, path::{Path, PathBuf}, time::{Duration, SystemTime}};
+use titan_core::{anyhow, error, info, runtime::runtime::Handle, ArcLock, Channels, Result};
 
 pub struct DiskResourceDef {
     extensions: &'static [&'static str],
@@ -15,6 +15,18 @@ pub enum DiskResourceType {
     Data(DiskResourceDef),
 }
 
+impl DiskResourceType {
+    pub fn extensions(&self) -> &'static [&'static str] {
+        match self {
+            DiskResourceType::Model(def)
+            | DiskResourceType::Texture(def)
+            | DiskResourceType::Shader(def)
+            | DiskResourceType::Script(def)
+            | DiskResourceType::Data(def) => def.extensions,
+        }
+    }
+}
+
 const DISK_RESOURCE_TYPES: &[&DiskResourceType] = &[
     &DiskResourceType::Model(DiskResourceDef {
         extensions: &["fbx", "obj", "gltf", "glb"],
@@ -33,6 +45,13 @@ const DISK_RESOURCE_TYPES: &[&DiskResourceType] = &[
     }),
 ];
 
+#[derive(Clone, Debug)]
+pub enum ResourceChange {
+    Created,
+    Modified,
+    Removed,
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct AssetsConfig {
     pub assets_dir: String,
@@ -41,8 +60,9 @@ pub struct AssetsConfig {
 pub struct ResourceSubsystem {
     pub channels: Channels,
     pub assets_dir: PathBuf,
-    pub watcher: ArcLock<Option<Debouncer<RecommendedWatcher, RecommendedCache>>>
-    // pub resources: DashMap<String, Resource>,
+    pub watcher: ArcLock<Option<Debouncer<RecommendedWatcher, RecommendedCache>>>,
+    /// Known resource files and their last modified time, as of the last scan.
+    pub resources: ArcLock<HashMap<PathBuf, SystemTime>>,
 }
 
 #[titan_core::subsystem]
@@ -50,16 +70,22 @@ impl ResourceSubsystem {
     
     #[titan_core::task]
     pub async fn init(&self) -> Result<()> {
+
+        // The debouncer calls back on its own thread, so publishing needs the runtime handle.
+        let runtime = Handle::current();
+        let channels = self.channels.clone();
     
         let watcher = new_debouncer(
             Duration::from_secs(2),
             None,
-            |res: DebounceEventResult| {
+            move |res: DebounceEventResult| {
+                let _runtime_guard = runtime.enter();
+
                 match res {
                     Ok(events) => {
                         events.into_iter()
                             .for_each(|event| {
-                                Self::watcher_event(&event);
+                                Self::watcher_event(&channels, &event);
                             });
                     },
                     Err(errors) => {
@@ -77,8 +103,7 @@ impl ResourceSubsystem {
             .await;
 
         {
-            let watch_dir = std::env::current_dir()?
-                .join(&self.assets_dir);
+            let watch_dir = self.resources_dir()?;
             
             let mut watcher_lock = self.watcher.lock()
                 .await;
@@ -89,28 +114,410 @@ impl ResourceSubsystem {
                 .watch(&watch_dir, RecursiveMode::Recursive)
                 .unwrap_or_else(|err| error!("Failed to start watching: {:?}", err));
         }
+
+        // Scanned after the watcher is up so files added in between aren't missed.
+        // Dispatched rather than called so the directory walk runs on the blocking pool.
+        self.channels
+            .get::<ResourceSubsystem>()
+            .send(Scan)
+            .await??;
+
+        let resource_count = self.resources
+            .read()
+            .await
+            .len();
+
+        self.channels
+            .publish(Ready { resource_count })
+            .await;
         
         Ok(())
     }
 
-    fn watcher_event(event: &Event) {
-        match event.kind {
+    /// Published once `init` has populated the registry with the files already on disk.
+    #[titan_core::task]
+    pub fn ready(&self, resource_count: usize) {
+        info!("Resources ready: {}", resource_count);
+    }
+
+    /// Stops the file watcher, whose thread publishes through the runtime and
+    /// so has to be gone before the runtime is dropped.
+    #[titan_core::task]
+    pub async fn shutdown(&self) {
+        if let Some(watcher) = self.watcher.lock().await.take() {
+            watcher.stop();
+        }
+    }
+
+    fn watcher_event(channels: &Channels, event: &Event) {
+        let change = match event.kind {
             EventKind::Create(_) => {
                 info!("Created files: {:?}", event.paths);
+                ResourceChange::Created
             },
             EventKind::Modify(_) => {
                 info!("Modified files: {:?}", event.paths);
+                ResourceChange::Modified
             },
             EventKind::Remove(_) => {
                 info!("Removed files: {:?}", event.paths);
+                ResourceChange::Removed
             },
-            _ => {}
+            _ => return,
+        };
+
+        let publish_result = channels.publish_sync(ResourcesChanged {
+            change,
+            paths: event.paths.clone(),
+        });
+
+        if let Err(err) = publish_result {
+            error!("Failed to publish resource change: {}", err);
         }
     }
 
+    /// Published by the file watcher; subscribe to it to react to resource changes.
     #[titan_core::task]
+    pub fn resources_changed(&self, change: ResourceChange, paths: Vec<PathBuf>) {
+        info!("Resources {:?}: {:?}", change, paths);
+    }
+
+    fn resources_dir(&self) -> Result<PathBuf> {
+        Ok(std::env::current_dir()?.join(&self.assets_dir))
+    }
+
+    /// On-disk path of the asset at `rel_path` under the assets dir. The asset has to
+    /// exist, and paths that resolve outside the assets dir, e.g. through `..`, are rejected.
+    #[titan_core::task(io)]
+    pub fn resolve(&self, rel_path: String) -> Result<PathBuf> {
+        let root = self.resources_dir()?
+            .canonicalize()
+            .map_err(|err| anyhow!("Assets: Failed to resolve the assets dir: {}", err))?;
+
+        let path = root
+            .join(&rel_path)
+            .canonicalize()
+            .map_err(|err| anyhow!("Assets: Failed to resolve `{}`: {}", rel_path, err))?;
+
+        match path.starts_with(&root) {
+            true => Ok(path),
+            false => Err(anyhow!("Assets: `{}` resolves outside the assets dir", rel_path)),
+        }
+    }
+
+    /// Records every resource file under the assets dir in the registry.
+    #[titan_core::task(io)]
     pub async fn scan(&self) -> Result<()> {
-        
+        let resources = Self::scan_dir(&self.resources_dir()?)?;
+
+        info!("Scanned {} resources", resources.len());
+
+        self.resources.write(resources)
+            .await;
+
         Ok(())
     }
+
+    /// Rescans the assets dir, publishes `ResourcesChanged` for new, modified and
+    /// removed files and returns how many were affected.
+    #[titan_core::task(io)]
+    pub async fn reload_all(&self) -> Result<usize> {
+        let scanned = Self::scan_dir(&self.resources_dir()?)?;
+
+        let mut resources = self.resources
+            .lock()
+            .await;
+
+        let mut created = Vec::new();
+        let mut modified = Vec::new();
+
+        for (path, modified_time) in scanned.iter() {
+            match resources.get(path) {
+                None => created.push(path.clone()),
+                Some(known_time) if known_time != modified_time => modified.push(path.clone()),
+                Some(_) => {},
+            }
+        }
+
+        let removed = resources
+            .keys()
+            .filter(|path| !scanned.contains_key(*path))
+            .cloned()
+            .collect::<Vec<_>>();
+
+        *resources = scanned;
+
+        drop(resources);
+
+        let affected = created.len() + modified.len() + removed.len();
+
+        let changes = [
+            (ResourceChange::Created, created),
+            (ResourceChange::Modified, modified),
+            (ResourceChange::Removed, removed),
+        ];
+
+        for (change, paths) in changes {
+            if paths.is_empty() {
+                continue;
+            }
+
+            self.channels
+                .publish(ResourcesChanged { change, paths })
+                .await;
+        }
+
+        info!("Reloaded {} resources", affected);
+
+        Ok(affected)
+    }
+
+    fn scan_dir(dir: &Path) -> Result<HashMap<PathBuf, SystemTime>> {
+        let mut resources = HashMap::new();
+        let mut visited = HashSet::new();
+
+        Self::scan_dir_into(dir, &mut visited, &mut resources)?;
+
+        Ok(resources)
+    }
+
+    fn scan_dir_into(dir: &Path, visited: &mut HashSet<PathBuf>, resources: &mut HashMap<PathBuf, SystemTime>) -> Result<()> {
+        if !dir.is_dir() {
+            return Ok(());
+        }
+
+        // Symlinked directories can loop back on themselves, so each real directory is walked once.
+        if !visited.insert(dir.canonicalize()?) {
+            return Ok(());
+        }
+
+        for entry in std::fs::read_dir(dir)? {
+            let path = entry?.path();
+
+            if path.is_dir() {
+                Self::scan_dir_into(&path, visited, resources)?;
+                continue;
+            }
+
+            if Self::is_resource(&path) {
+                let modified_time = std::fs::metadata(&path)?.modified()?;
+                resources.insert(path, modified_time);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn is_resource(path: &Path) -> bool {
+        let Some(extension) = path.extension().and_then(|extension| extension.to_str()) else {
+            return false;
+        };
+
+        DISK_RESOURCE_TYPES
+            .iter()
+            .any(|resource_type| resource_type.extensions().contains(&extension))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{fs, sync::{Arc, Mutex}};
+    use titan_core::{Subsystem, SubsystemRef};
+
+    /// Starts a `ResourceSubsystem` over `assets_dir`, which is absolute so the
+    /// working directory doesn't matter.
+    async fn start_resources(assets_dir: &Path) -> Channels {
+        let (resources_ref, resources_receiver) = SubsystemRef::<ResourceSubsystem>::new();
+        let mut channels = Channels::default();
+
+        channels.add(resources_ref)
+            .await;
+
+        ResourceSubsystem::start_quiet(
+            ResourceSubsystem {
+                channels: channels.clone(),
+                assets_dir: assets_dir.to_path_buf(),
+                watcher: ArcLock::new(None),
+                resources: ArcLock::new(HashMap::new()),
+            },
+            resources_receiver,
+        );
+
+        channels
+    }
+
+    #[tokio::test]
+    async fn reload_all_picks_up_changes_since_the_scan() {
+        let assets_dir = tempfile::tempdir()
+            .unwrap();
+        let path = |name| assets_dir.path().join(name);
+
+        fs::write(path("removed.png"), "")
+            .unwrap();
+        fs::write(path("modified.json"), "{}")
+            .unwrap();
+        fs::write(path("unchanged.lua"), "")
+            .unwrap();
+
+        let channels = start_resources(assets_dir.path())
+            .await;
+
+        channels
+            .get::<ResourceSubsystem>()
+            .send(Scan)
+            .await
+            .unwrap()
+            .unwrap();
+
+        let changes = Arc::new(Mutex::new(Vec::new()));
+        let published = changes.clone();
+
+        channels.subscribe_fn::<ResourcesChanged>(move |(change, paths)| {
+            published.lock().unwrap().push(format!("{:?} {:?}", change, paths));
+        })
+        .await;
+
+        fs::remove_file(path("removed.png"))
+            .unwrap();
+        fs::write(path("created.toml"), "")
+            .unwrap();
+        fs::File::options()
+            .write(true)
+            .open(path("modified.json"))
+            .unwrap()
+            .set_modified(SystemTime::now() + Duration::from_secs(60))
+            .unwrap();
+
+        let affected = channels
+            .get::<ResourceSubsystem>()
+            .send(ReloadAll)
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(affected, 3);
+        assert_eq!(*changes.lock().unwrap(), [
+            format!("Created {:?}", [path("created.toml")]),
+            format!("Modified {:?}", [path("modified.json")]),
+            format!("Removed {:?}", [path("removed.png")]),
+        ]);
+    }
+
+    #[tokio::test]
+    async fn shutdown_stops_the_watcher() {
+        let assets_dir = tempfile::tempdir()
+            .unwrap();
+        let (resources_ref, resources_receiver) = SubsystemRef::<ResourceSubsystem>::new();
+        let watcher = ArcLock::new(None);
+        let mut channels = Channels::default();
+
+        channels.add(resources_ref)
+            .await;
+
+        ResourceSubsystem::start_quiet(
+            ResourceSubsystem {
+                channels: channels.clone(),
+                assets_dir: assets_dir.path().to_path_buf(),
+                watcher: watcher.clone(),
+                resources: ArcLock::new(HashMap::new()),
+            },
+            resources_receiver,
+        );
+
+        let resources = channels.get::<ResourceSubsystem>();
+
+        resources.send(Init)
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert!(watcher.read().await.is_some());
+
+        resources.send(Shutdown)
+            .await
+            .unwrap();
+
+        assert!(watcher.read().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn init_scans_then_publishes_ready() {
+        let assets_dir = tempfile::tempdir()
+            .unwrap();
+
+        fs::write(assets_dir.path().join("player.png"), "")
+            .unwrap();
+        fs::write(assets_dir.path().join("level.json"), "{}")
+            .unwrap();
+        fs::write(assets_dir.path().join("notes.txt"), "")
+            .unwrap();
+
+        let channels = start_resources(assets_dir.path())
+            .await;
+
+        let ready = Arc::new(Mutex::new(Vec::new()));
+        let published = ready.clone();
+
+        channels.subscribe_fn::<Ready>(move |resource_count| {
+            published.lock().unwrap().push(resource_count);
+        })
+        .await;
+
+        let resources = channels.get::<ResourceSubsystem>();
+
+        resources.send(Init)
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(*ready.lock().unwrap(), [2]);
+
+        resources.send(Shutdown)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn resolve_stays_inside_the_assets_dir() {
+        let root_dir = tempfile::tempdir()
+            .unwrap();
+        let assets_dir = root_dir.path().join("assets");
+
+        fs::create_dir_all(assets_dir.join("sprites"))
+            .unwrap();
+        fs::write(assets_dir.join("sprites/player.png"), "")
+            .unwrap();
+        fs::write(root_dir.path().join("secret.txt"), "")
+            .unwrap();
+
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(root_dir.path().join("secret.txt"), assets_dir.join("linked.txt"))
+            .unwrap();
+
+        let channels = start_resources(&assets_dir)
+            .await;
+
+        let resolve = |rel_path: &str| channels
+            .get::<ResourceSubsystem>()
+            .send(Resolve { rel_path: rel_path.to_string() });
+
+        let player = resolve("sprites/player.png")
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(player, assets_dir.join("sprites/player.png").canonicalize().unwrap());
+
+        let escaped = resolve("../secret.txt")
+            .await
+            .unwrap()
+            .unwrap_err();
+
+        assert!(escaped.to_string().contains("outside the assets dir"), "{}", escaped);
+        assert!(resolve("sprites/missing.png").await.unwrap().is_err());
+
+        #[cfg(unix)]
+        assert!(resolve("linked.txt").await.unwrap().is_err());
+    }
 }
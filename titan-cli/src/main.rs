@@ -1,4 +1,4 @@
-use clap::{Parser, Subcommand};
+use clap::{Args, Parser, Subcommand};
 use colored::Colorize;
 use include_dir::{include_dir, Dir, DirEntry};
 use std::{
@@ -14,6 +14,7 @@ const APP_BIN_TEMPLATE: &str = include_str!("../template/app/app_bin_template.tx
 const APP_LIB_TEMPLATE: &str = include_str!("../template/app/app_lib_template.rs");
 const APP_CARGO_TEMPLATE: &str = include_str!("../template/app/app_cargo_template.toml");
 const APP_BUILD_TEMPLATE: &str = include_str!("../template/app/app_build_template.rs");
+const WORKSPACE_CARGO_TEMPLATE: &str = include_str!("../template/app/workspace_cargo_template.toml");
 const LSP_TEMPLATE: &str = include_str!("../template/lsp/lsp_template.rs");
 const HELIX_IGNORE_TEMPLATE: &str = include_str!("../template/helix/helix_.ignore_template");
 const HELIX_LANGUAGES_TEMPLATE: &str =
@@ -21,6 +22,7 @@ const HELIX_LANGUAGES_TEMPLATE: &str =
 const HELIX_ADASTRA_GRAMMARS_DIR: Dir<'_> =
     include_dir!("$CARGO_MANIFEST_DIR/template/scripting");
 const HELIX_ADASTRA_QUERIES_TEMPLATE: &str = include_str!("../template/scripting/highlights.scm");
+const NVIM_LSP_TEMPLATE: &str = include_str!("../template/nvim/nvim_lsp_template.lua");
 
 #[derive(Parser)]
 #[command(version, about, long_about = None)]
@@ -38,22 +40,118 @@ enum Commands {
         /// Enable Helix integration
         #[arg(short, long)]
         helix: bool,
+        /// Enable Neovim integration
+        #[arg(long)]
+        nvim: bool,
+        /// Generate a cargo workspace with the app and titan-lsp as members
+        #[arg(short, long)]
+        workspace: bool,
+        /// Print the files and commands init would create and run, without doing either
+        #[arg(long)]
+        dry_run: bool,
+    },
+    Clean {
+        /// Also remove generated editor integration
+        #[arg(short, long)]
+        editors: bool,
+    },
+    /// Build the project with cargo
+    Build {
+        #[command(flatten)]
+        cargo: CargoArgs,
+        /// Extra flags passed to cargo as is, after `--`
+        #[arg(last = true)]
+        cargo_flags: Vec<String>,
+    },
+    /// Build and run the project with cargo
+    Run {
+        #[command(flatten)]
+        cargo: CargoArgs,
+        /// Arguments passed to the app, after `--`
+        #[arg(last = true)]
+        app_args: Vec<String>,
     },
 }
 
+#[derive(Args)]
+struct CargoArgs {
+    /// Use the release profile instead of dev
+    #[arg(short, long)]
+    release: bool,
+    /// Comma separated list of features to enable
+    #[arg(short = 'F', long)]
+    features: Option<String>,
+}
+
 fn main() -> std::io::Result<()> {
     let cli = Cli::parse();
 
     match &cli.command {
-        Commands::Init { name, helix } => {
-            let init_result = init(name, *helix);
+        Commands::Init { name, helix, nvim, workspace, dry_run } => {
+            let init_result = init(name, *helix, *nvim, *workspace, *dry_run);
 
-            if init_result.is_err() {
+            // A dry run creates nothing, so there's nothing to clean up.
+            if init_result.is_err() && !dry_run {
                 deinit(name)?;
             }
 
             init_result
         }
+        Commands::Clean { editors } => clean(&env::current_dir()?, *editors),
+        Commands::Build { cargo, cargo_flags } => {
+            cargo_project(cargo_command("build", cargo, cargo_flags, &[]))
+        }
+        Commands::Run { cargo, app_args } => {
+            cargo_project(cargo_command("run", cargo, &[], app_args))
+        }
+    }
+}
+
+/// Builds the cargo invocation for `build`/`run`: profile and features first, then the
+/// raw cargo flags, then `--` and the app's own arguments.
+fn cargo_command(subcommand: &str, cargo: &CargoArgs, cargo_flags: &[String], app_args: &[String]) -> Command {
+    let mut command = Command::new("cargo");
+
+    command.arg(subcommand);
+
+    if cargo.release {
+        command.arg("--release");
+    }
+
+    if let Some(features) = &cargo.features {
+        command.arg("--features").arg(features);
+    }
+
+    command.args(cargo_flags);
+
+    if !app_args.is_empty() {
+        command.arg("--").args(app_args);
+    }
+
+    command
+}
+
+fn cargo_project(mut command: Command) -> std::io::Result<()> {
+    let app_dir = env::current_dir()?;
+
+    if !is_titan_project(&app_dir) {
+        eprintln!(
+            "{}",
+            "Not a titan project: missing Cargo.toml or tools/titan-lsp".red()
+        );
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            "Not a titan project",
+        ));
+    }
+
+    let status = command
+        .current_dir(&app_dir)
+        .status()?;
+
+    match status.success() {
+        true => Ok(()),
+        false => Err(std::io::Error::other(format!("cargo exited with {}", status))),
     }
 }
 
@@ -68,75 +166,228 @@ fn deinit(app_name: &str) -> std::io::Result<()> {
     Ok(())
 }
 
-fn init(name: &str, helix: bool) -> std::io::Result<()> {
+fn clean(app_dir: &Path, editors: bool) -> std::io::Result<()> {
+    if !is_titan_project(app_dir) {
+        eprintln!(
+            "{}",
+            "Not a titan project: missing Cargo.toml or tools/titan-lsp".red()
+        );
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            "Not a titan project",
+        ));
+    }
+
+    let lsp_dir = app_dir.join("tools/titan-lsp");
+
+    remove_path(&lsp_dir.join(".lsp_build_trigger"))?;
+    remove_path(&lsp_dir.join("target"))?;
+
+    if editors {
+        remove_path(&app_dir.join(".helix"))?;
+        remove_path(&app_dir.join(".vscode"))?;
+        remove_path(&app_dir.join(".nvim.lua"))?;
+    }
+
+    println!("{}", "Project cleaned".green());
+    Ok(())
+}
+
+fn is_titan_project(app_dir: &Path) -> bool {
+    app_dir.join("Cargo.toml").is_file() && app_dir.join("tools/titan-lsp").is_dir()
+}
+
+fn remove_path(path: &Path) -> std::io::Result<()> {
+    if path.is_dir() {
+        fs::remove_dir_all(path)?;
+    } else if path.exists() {
+        fs::remove_file(path)?;
+    } else {
+        return Ok(());
+    }
+
+    println!("Removed: {}", path.display());
+    Ok(())
+}
+
+/// The filesystem and command steps `init` takes. In a dry run each step is printed
+/// instead of performed, so both share one list of actions.
+struct Actions {
+    dry_run: bool,
+}
+
+impl Actions {
+    fn create_dir(&self, path: &Path) -> std::io::Result<()> {
+        match self.dry_run {
+            true => {
+                println!("Would create directory: {}", path.display());
+                Ok(())
+            }
+            false => fs::create_dir_all(path),
+        }
+    }
+
+    fn write(&self, path: &Path, contents: &[u8]) -> std::io::Result<()> {
+        match self.dry_run {
+            true => {
+                println!("Would write: {}", path.display());
+                Ok(())
+            }
+            false => File::create(path)?.write_all(contents),
+        }
+    }
+
+    fn append(&self, path: &Path, contents: &str) -> std::io::Result<()> {
+        match self.dry_run {
+            true => {
+                println!("Would append to: {}", path.display());
+                Ok(())
+            }
+            false => {
+                let mut file = fs::read_to_string(path)?;
+
+                file.push('\n');
+                file.push_str(contents);
+
+                fs::write(path, file)
+            }
+        }
+    }
+
+    fn run(&self, command: &mut Command) -> std::io::Result<()> {
+        match self.dry_run {
+            true => {
+                // Debug includes the `cd` into the command's working directory
+                println!("Would run: {:?}", command);
+                Ok(())
+            }
+            false => {
+                command
+                    .stdout(Stdio::piped())
+                    .spawn()?
+                    .wait()?;
+
+                Ok(())
+            }
+        }
+    }
+
+    /// Nothing exists yet in a dry run, so the path can only be made absolute, not canonical.
+    fn absolute_path(&self, path: &Path) -> std::io::Result<String> {
+        let path = match self.dry_run {
+            true => std::path::absolute(path)?,
+            false => path.canonicalize()?,
+        };
+
+        Ok(path.to_str().unwrap().to_string())
+    }
+
+    /// Progress messages describe finished steps, so a dry run leaves them out.
+    fn report(&self, message: impl std::fmt::Display) {
+        if !self.dry_run {
+            println!("{}", message);
+        }
+    }
+}
+
+fn init(name: &str, helix: bool, nvim: bool, workspace: bool, dry_run: bool) -> std::io::Result<()> {
     // Get the directory from which the command was called
     let current_dir = env::current_dir()?;
+
+    init_in(&current_dir, name, helix, nvim, workspace, &Actions { dry_run })
+}
+
+fn init_in(current_dir: &Path, name: &str, helix: bool, nvim: bool, workspace: bool, actions: &Actions) -> std::io::Result<()> {
     println!("Current directory: {}", current_dir.display());
 
     // Create the path to the new project based on the calling directory
     let name = name.replace("-", "_");
-    let app_dir = current_dir.join(&name);
+    let root_dir = current_dir.join(&name);
+
+    // In a workspace the app is a member next to tools/, otherwise it is the root itself
+    let (app_dir, lsp_dir) = match workspace {
+        true => (root_dir.join(&name), "../tools/titan-lsp"),
+        false => (root_dir.clone(), "tools/titan-lsp"),
+    };
 
-    println!("Creating project in: {}", app_dir.display());
+    println!("Creating project in: {}", root_dir.display());
+
+    if workspace {
+        create_workspace(actions, &root_dir, &name)?;
+    }
 
     // Initialize a new cargo project in the current directory
-    Command::new("cargo")
-        .arg("init")
-        .arg("--bin")
-        .arg(&name)
-        .current_dir(&current_dir) // Ensure we're in the directory the command was called from
-        .stdout(Stdio::piped())
-        .spawn()?
-        .wait()?;
+    actions.run(
+        Command::new("cargo")
+            .arg("init")
+            .arg("--bin")
+            .arg(&app_dir)
+            .current_dir(current_dir) // Ensure we're in the directory the command was called from
+    )?;
 
-    println!("{}", "Project created".green());
+    actions.report("Project created".green());
 
     // Add dependencies
-    Command::new("cargo")
-        .arg("add")
-        .arg("--path")
-        .arg("/home/stormblessed/dev/titan/titan")
-        .current_dir(&app_dir) // Now run in the new project's directory
-        .stdout(Stdio::piped())
-        .spawn()?
-        .wait()?;
+    actions.run(
+        Command::new("cargo")
+            .arg("add")
+            .arg("--path")
+            .arg("/home/stormblessed/dev/titan/titan")
+            .current_dir(&app_dir) // Now run in the new project's directory
+    )?;
 
-    println!("{}", "Dependency added: titan".green());
+    actions.report("Dependency added: titan".green());
 
-    Command::new("cargo")
-        .arg("add")
-        .arg("ad-astra")
-        .current_dir(&app_dir) // Run in the new project's directory
-        .stdout(Stdio::piped())
-        .spawn()?
-        .wait()?;
+    actions.run(
+        Command::new("cargo")
+            .arg("add")
+            .arg("ad-astra")
+            .current_dir(&app_dir) // Run in the new project's directory
+    )?;
 
-    println!("{}", "Dependency added: ad-astra".green());
+    actions.report("Dependency added: ad-astra".green());
 
-    create_project_dirs(&app_dir, &name)?;
+    create_project_dirs(actions, &app_dir, &name)?;
 
-    replace_main_rs(&app_dir, &name)?;
+    replace_main_rs(actions, &app_dir, &name)?;
 
-    add_lib_rs(&app_dir, &name)?;
+    add_lib_rs(actions, &app_dir, &name)?;
 
-    replace_cargo_toml(&app_dir, &name)?;
+    replace_cargo_toml(actions, &app_dir, &name)?;
 
-    replace_build_rs(&app_dir, &name)?;
+    replace_build_rs(actions, &app_dir, &name, lsp_dir)?;
 
-    setup_lsp_tool(&app_dir, &name)?;
+    setup_lsp_tool(actions, &root_dir, &app_dir, &name)?;
 
     if helix {
         println!("Setting up Helix integration...");
-        setup_helix(&app_dir)?;
+        setup_helix(actions, &root_dir)?;
+    }
+
+    if nvim {
+        println!("Setting up Neovim integration...");
+        setup_nvim(actions, &root_dir)?;
     }
 
     Ok(())
 }
 
-fn create_project_dirs(app_dir: &Path, _app_name: &str) -> std::io::Result<()> {
+fn create_workspace(actions: &Actions, root_dir: &Path, app_name: &str) -> std::io::Result<()> {
+    actions.create_dir(root_dir)?;
+
+    let workspace_toml_path = root_dir.join("Cargo.toml");
+    let workspace_toml_content = WORKSPACE_CARGO_TEMPLATE.replace("{app_name}", app_name);
+
+    actions.write(&workspace_toml_path, workspace_toml_content.as_bytes())?;
+
+    actions.report(format!("Workspace Cargo.toml created at: {}", workspace_toml_path.display()));
+    Ok(())
+}
+
+fn create_project_dirs(actions: &Actions, app_dir: &Path, _app_name: &str) -> std::io::Result<()> {
     let content_dir = app_dir.join("content");
 
-    if let Err(e) = fs::create_dir_all(&content_dir) {
+    if let Err(e) = actions.create_dir(&content_dir) {
         eprintln!(
             "Failed to create content directory: {}",
             e.to_string().red()
@@ -144,212 +395,216 @@ fn create_project_dirs(app_dir: &Path, _app_name: &str) -> std::io::Result<()> {
         return Err(e);
     }
 
-    println!("Content directory created at: {}", content_dir.display());
+    actions.report(format!("Content directory created at: {}", content_dir.display()));
 
     let default_script = content_dir.join("welcome.aa");
 
-    if let Err(e) = File::create(&default_script) {
+    if let Err(e) = actions.write(&default_script, &[]) {
         eprintln!("Failed to create script file: {}", e.to_string().red());
         return Err(e);
     }
 
-    println!("Script file created at: {}", default_script.display());
+    actions.report(format!("Script file created at: {}", default_script.display()));
 
     Ok(())
 }
 
-fn replace_main_rs(app_dir: &Path, app_name: &str) -> std::io::Result<()> {
+fn replace_main_rs(actions: &Actions, app_dir: &Path, app_name: &str) -> std::io::Result<()> {
     let main_rs_path = app_dir.join("src/main.rs");
 
     let lib_name = app_name.replace("-", "_");
 
     let main_rs_content = APP_BIN_TEMPLATE.replace("{lib_name}", &lib_name);
 
-    let mut main_rs_file = File::create(&main_rs_path)?;
-    main_rs_file.write_all(main_rs_content.as_bytes())?;
+    actions.write(&main_rs_path, main_rs_content.as_bytes())?;
 
-    println!("Main app's main.rs created for: {}", app_name);
+    actions.report(format!("Main app's main.rs created for: {}", app_name));
     Ok(())
 }
-fn add_lib_rs(app_dir: &Path, app_name: &str) -> std::io::Result<()> {
+fn add_lib_rs(actions: &Actions, app_dir: &Path, app_name: &str) -> std::io::Result<()> {
     let librs_path = app_dir.join("src/lib.rs");
 
     let librs_content = APP_LIB_TEMPLATE.replace("{app_name}", &to_camel_case(app_name));
 
-    let mut librs_file = File::create(&librs_path)?;
-    librs_file.write_all(librs_content.as_bytes())?;
+    actions.write(&librs_path, librs_content.as_bytes())?;
 
-    println!("Main app's main.rs created for: {}", app_name);
+    actions.report(format!("Main app's main.rs created for: {}", app_name));
     Ok(())
 }
 
-fn replace_cargo_toml(app_dir: &Path, app_name: &str) -> std::io::Result<()> {
+fn replace_cargo_toml(actions: &Actions, app_dir: &Path, app_name: &str) -> std::io::Result<()> {
     let cargo_toml_path = app_dir.join("Cargo.toml");
 
-    let mut cargo_toml = fs::read_to_string(&cargo_toml_path).expect("Failed to read Cargo.toml");
-
-    cargo_toml.push('\n');
-    cargo_toml.push_str(APP_CARGO_TEMPLATE);
-
     let bin_name = format!("{}_bin", app_name.replace("-", "_"));
     let lib_name = app_name.replace("-", "_");
 
-    let cargo_toml = cargo_toml.replace("{bin_name}", &bin_name);
-    let cargo_toml = cargo_toml.replace("{lib_name}", &lib_name);
+    // Placeholders only appear in the template, so it can be filled in before appending.
+    let cargo_template = APP_CARGO_TEMPLATE
+        .replace("{bin_name}", &bin_name)
+        .replace("{lib_name}", &lib_name);
 
-    fs::write(cargo_toml_path, cargo_toml).expect("Failed to write updated Cargo.toml");
+    actions.append(&cargo_toml_path, &cargo_template)?;
 
-    println!("Cargo.toml updated with dependencies for: {}", app_name);
+    actions.report(format!("Cargo.toml updated with dependencies for: {}", app_name));
     Ok(())
 }
 
-fn replace_build_rs(app_dir: &Path, app_name: &str) -> std::io::Result<()> {
+fn replace_build_rs(actions: &Actions, app_dir: &Path, app_name: &str, lsp_dir: &str) -> std::io::Result<()> {
     let build_rs_path = app_dir.join("build.rs");
 
-    let build_rs_content = APP_BUILD_TEMPLATE.replace("{app_name}", &to_camel_case(app_name));
+    let build_rs_content = APP_BUILD_TEMPLATE
+        .replace("{app_name}", &to_camel_case(app_name))
+        .replace("{lsp_dir}", lsp_dir);
 
-    let mut build_rs_file = File::create(&build_rs_path)?;
-    build_rs_file.write_all(build_rs_content.as_bytes())?;
+    actions.write(&build_rs_path, build_rs_content.as_bytes())?;
 
-    println!("Main app's main.rs created for: {}", app_name);
+    actions.report(format!("Main app's main.rs created for: {}", app_name));
     Ok(())
 }
 
-fn setup_lsp_tool(app_dir: &Path, app_name: &str) -> std::io::Result<()> {
-    let lsp_dir = app_dir.join("tools/titan-lsp");
+fn setup_lsp_tool(actions: &Actions, root_dir: &Path, app_dir: &Path, app_name: &str) -> std::io::Result<()> {
+    let lsp_dir = root_dir.join("tools/titan-lsp");
+
+    actions.run(
+        Command::new("cargo")
+            .arg("init")
+            .arg("--bin")
+            .arg(&lsp_dir)
+    )?;
+
+    actions.report(format!("LSP tool binary project created in: {}", lsp_dir.display()));
+
+    actions.run(
+        Command::new("cargo")
+            .arg("add")
+            .arg("ad-astra")
+            .current_dir(&lsp_dir)
+    )?;
 
-    Command::new("cargo")
-        .arg("init")
-        .arg("--bin")
-        .arg(&lsp_dir)
-        .stdout(Stdio::piped())
-        .spawn()?
-        .wait()?;
-
-    println!("LSP tool binary project created in: {}", lsp_dir.display());
-
-    Command::new("cargo")
-        .arg("add")
-        .arg("ad-astra")
-        .current_dir(&lsp_dir)
-        .stdout(Stdio::piped())
-        .spawn()?
-        .wait()?;
-
-    println!("Added `ad-astra` to the LSP project.");
-
-    Command::new("cargo")
-        .arg("add")
-        .arg(app_name)
-        .arg("--path")
-        .arg(app_dir)
-        .current_dir(&lsp_dir)
-        .stdout(Stdio::piped())
-        .spawn()?
-        .wait()?;
-
-    println!("Linked the user app to the LSP project.");
+    actions.report("Added `ad-astra` to the LSP project.");
+
+    actions.run(
+        Command::new("cargo")
+            .arg("add")
+            .arg(app_name)
+            .arg("--path")
+            .arg(app_dir)
+            .current_dir(&lsp_dir)
+    )?;
+
+    actions.report("Linked the user app to the LSP project.");
 
     let lsp_main_rs_path = lsp_dir.join("src/main.rs");
 
     let lsp_main_rs_content = LSP_TEMPLATE.replace("{app_name}", &to_camel_case(app_name));
 
-    let mut lsp_main_rs_file = File::create(&lsp_main_rs_path)?;
-    lsp_main_rs_file.write_all(lsp_main_rs_content.as_bytes())?;
+    actions.write(&lsp_main_rs_path, lsp_main_rs_content.as_bytes())?;
 
-    println!("LSP tool's main.rs created for: {}", app_name);
+    actions.report(format!("LSP tool's main.rs created for: {}", app_name));
     Ok(())
 }
 
-fn setup_helix(app_dir: &Path) -> std::io::Result<()> {
+fn setup_helix(actions: &Actions, app_dir: &Path) -> std::io::Result<()> {
     let helix_config_dir = app_dir.join(".helix");
     let helix_grammar_dir = helix_config_dir.join("runtime/grammars/sources/adastra");
     let helix_queries_dir = helix_config_dir.join("runtime/queries/adastra");
 
-    let app_absolute_path = app_dir
-        .canonicalize()?
-        .to_str()
-        .unwrap()
-        .to_string();
+    let app_absolute_path = actions.absolute_path(app_dir)?;
 
     println!("Helix config dir: {}", helix_config_dir.display());
     println!("Helix grammar dir: {}", helix_grammar_dir.display());
     println!("Helix queries dir: {}", helix_queries_dir.display());
 
-    println!("Creating Helix directories...");
+    actions.report("Creating Helix directories...");
 
-    std::fs::create_dir_all(&helix_grammar_dir)?;
-    std::fs::create_dir_all(&helix_queries_dir)?;
+    actions.create_dir(&helix_grammar_dir)?;
+    actions.create_dir(&helix_queries_dir)?;
 
-    if !helix_grammar_dir.exists() {
+    if !actions.dry_run && !helix_grammar_dir.exists() {
         panic!("Helix grammar directory not created!");
     }
 
-    if !helix_queries_dir.exists() {
+    if !actions.dry_run && !helix_queries_dir.exists() {
         panic!("Helix queries directory not created!");
     }
 
     let helix_ignore_file = app_dir.join(".ignore");
-    let mut ignore_file = File::create(&helix_ignore_file)?;
-    ignore_file.write_all(HELIX_IGNORE_TEMPLATE.as_bytes())?;
+    actions.write(&helix_ignore_file, HELIX_IGNORE_TEMPLATE.as_bytes())?;
 
     let helix_languages_path = helix_config_dir.join("languages.toml");
-    let helix_languages_content =
-        HELIX_LANGUAGES_TEMPLATE.replace("{app_path}", &app_absolute_path);
-    let mut languages_file = File::create(&helix_languages_path)?;
-    languages_file.write_all(helix_languages_content.as_bytes())?;
+    let helix_languages_content = HELIX_LANGUAGES_TEMPLATE
+        .replace("{lsp_path}", &lsp_binary_path(&app_absolute_path))
+        .replace("{app_path}", &app_absolute_path);
+    actions.write(&helix_languages_path, helix_languages_content.as_bytes())?;
 
-    println!(
+    actions.report(format!(
         "languages.toml created at: {}",
         helix_languages_path.display()
-    );
+    ));
 
     let helix_queries_path = helix_queries_dir.join("highlights.scm");
-    let mut queries_file = File::create(&helix_queries_path)?;
-    queries_file.write_all(HELIX_ADASTRA_QUERIES_TEMPLATE.as_bytes())?;
+    actions.write(&helix_queries_path, HELIX_ADASTRA_QUERIES_TEMPLATE.as_bytes())?;
 
-    println!(
+    actions.report(format!(
         "highlights.scm created at: {}",
         helix_queries_path.display()
-    );
+    ));
 
     unpack_files(
+        actions,
         helix_grammar_dir.to_str().unwrap(),
         &HELIX_ADASTRA_GRAMMARS_DIR,
     )?;
 
-    println!("{}", "Grammars unpacked.".green());
+    actions.report("Grammars unpacked.".green());
+
+    actions.run(
+        Command::new("hx")
+            .arg("--grammar")
+            .arg("build")
+            .current_dir(app_dir)
+    )?;
+
+    actions.report("Helix grammars built successfully".green());
+    Ok(())
+}
+
+fn setup_nvim(actions: &Actions, app_dir: &Path) -> std::io::Result<()> {
+    let app_absolute_path = actions.absolute_path(app_dir)?;
+
+    let nvim_config_path = app_dir.join(".nvim.lua");
+    let nvim_config_content = NVIM_LSP_TEMPLATE
+        .replace("{lsp_path}", &lsp_binary_path(&app_absolute_path))
+        .replace("{app_path}", &app_absolute_path);
 
-    Command::new("hx")
-        .arg("--grammar")
-        .arg("build")
-        .current_dir(app_dir)
-        .stdout(Stdio::piped())
-        .spawn()?
-        .wait()?;
+    actions.write(&nvim_config_path, nvim_config_content.as_bytes())?;
 
-    println!("{}", "Helix grammars built successfully".green());
+    actions.report(format!(".nvim.lua created at: {}", nvim_config_path.display()));
+    println!("{}", "Enable `exrc` in Neovim to load the project config".yellow());
     Ok(())
 }
 
-fn unpack_files(target_directory: &str, unpack_directory: &Dir<'_>) -> std::io::Result<()> {
+fn lsp_binary_path(app_absolute_path: &str) -> String {
+    format!("{}/tools/titan-lsp/target/release/titan-lsp", app_absolute_path)
+}
+
+fn unpack_files(actions: &Actions, target_directory: &str, unpack_directory: &Dir<'_>) -> std::io::Result<()> {
     for entry in unpack_directory.entries() {
         let entry_path = entry.path();
         let target_path = Path::new(target_directory).join(entry_path.file_name().unwrap());
 
         match entry {
             DirEntry::Dir(dir) => {
-                fs::create_dir_all(&target_path)?;
+                actions.create_dir(&target_path)?;
 
-                unpack_files(target_path.to_str().unwrap(), dir)?;
+                unpack_files(actions, target_path.to_str().unwrap(), dir)?;
             }
             DirEntry::File(file) => {
                 if let Some(parent) = target_path.parent() {
-                    fs::create_dir_all(parent)?;
+                    actions.create_dir(parent)?;
                 }
 
-                let mut output_file = File::create(&target_path)?;
-                output_file.write_all(file.contents())?;
+                actions.write(&target_path, file.contents())?;
             }
         }
     }
@@ -367,3 +622,149 @@ fn to_camel_case(input: &str) -> String {
         })
         .collect::<String>()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn titan_project() -> tempfile::TempDir {
+        let app_dir = tempfile::tempdir()
+            .unwrap();
+
+        fs::write(app_dir.path().join("Cargo.toml"), "[package]")
+            .unwrap();
+
+        fs::create_dir_all(app_dir.path().join("tools/titan-lsp/target/release"))
+            .unwrap();
+
+        app_dir
+    }
+
+    #[test]
+    fn clean_removes_build_artifacts() {
+        let app_dir = titan_project();
+        let lsp_dir = app_dir.path().join("tools/titan-lsp");
+
+        fs::write(lsp_dir.join(".lsp_build_trigger"), "")
+            .unwrap();
+
+        fs::create_dir_all(app_dir.path().join(".helix"))
+            .unwrap();
+
+        clean(app_dir.path(), false)
+            .unwrap();
+
+        assert!(!lsp_dir.join(".lsp_build_trigger").exists());
+        assert!(!lsp_dir.join("target").exists());
+        assert!(lsp_dir.is_dir());
+        assert!(app_dir.path().join("Cargo.toml").is_file());
+        assert!(app_dir.path().join(".helix").is_dir());
+    }
+
+    #[test]
+    fn clean_with_editors_removes_editor_config() {
+        let app_dir = titan_project();
+
+        fs::create_dir_all(app_dir.path().join(".helix/runtime"))
+            .unwrap();
+
+        fs::write(app_dir.path().join(".nvim.lua"), "")
+            .unwrap();
+
+        clean(app_dir.path(), true)
+            .unwrap();
+
+        assert!(!app_dir.path().join(".helix").exists());
+        assert!(!app_dir.path().join(".nvim.lua").exists());
+    }
+
+    #[test]
+    fn clean_rejects_non_titan_projects() {
+        let dir = tempfile::tempdir()
+            .unwrap();
+
+        fs::write(dir.path().join("keep.txt"), "")
+            .unwrap();
+
+        assert!(clean(dir.path(), true).is_err());
+        assert!(dir.path().join("keep.txt").is_file());
+    }
+
+    #[test]
+    fn nvim_config_points_at_the_lsp_binary() {
+        let app_dir = titan_project();
+        let app_path = app_dir.path()
+            .canonicalize()
+            .unwrap();
+
+        setup_nvim(&Actions { dry_run: false }, app_dir.path())
+            .unwrap();
+
+        let nvim_config = fs::read_to_string(app_dir.path().join(".nvim.lua"))
+            .unwrap();
+
+        let lsp_path = format!("{}/tools/titan-lsp/target/release/titan-lsp", app_path.display());
+
+        assert!(nvim_config.contains(&format!("cmd = {{ \"{}\" }}", lsp_path)));
+        assert!(nvim_config.contains(&format!("root_dir = \"{}\"", app_path.display())));
+        assert!(!nvim_config.contains("{lsp_path}"));
+    }
+
+    #[test]
+    fn workspace_manifest_lists_the_app_and_lsp() {
+        let root_dir = tempfile::tempdir()
+            .unwrap();
+        let workspace_dir = root_dir.path().join("my_game");
+
+        create_workspace(&Actions { dry_run: false }, &workspace_dir, "my_game")
+            .unwrap();
+
+        let manifest = fs::read_to_string(workspace_dir.join("Cargo.toml"))
+            .unwrap();
+
+        assert!(manifest.starts_with("[workspace]"));
+        assert!(manifest.contains("\"my_game\""));
+        assert!(manifest.contains("\"tools/titan-lsp\""));
+        assert!(!manifest.contains("{app_name}"));
+    }
+
+    fn cargo_args(argv: &[&str]) -> Vec<String> {
+        let command = match Cli::try_parse_from(argv).unwrap().command {
+            Commands::Build { cargo, cargo_flags } => cargo_command("build", &cargo, &cargo_flags, &[]),
+            Commands::Run { cargo, app_args } => cargo_command("run", &cargo, &[], &app_args),
+            _ => unreachable!(),
+        };
+
+        assert_eq!(command.get_program(), "cargo");
+
+        command.get_args()
+            .map(|arg| arg.to_string_lossy().into_owned())
+            .collect()
+    }
+
+    #[test]
+    fn cargo_command_forwards_build_and_run_flags() {
+        assert_eq!(cargo_args(&["titan", "build"]), ["build"]);
+
+        assert_eq!(
+            cargo_args(&["titan", "build", "--release", "-F", "stats,metrics", "--", "--locked"]),
+            ["build", "--release", "--features", "stats,metrics", "--locked"],
+        );
+
+        assert_eq!(
+            cargo_args(&["titan", "run", "-r", "--", "--level", "2"]),
+            ["run", "--release", "--", "--level", "2"],
+        );
+    }
+
+    #[test]
+    fn dry_run_init_creates_nothing() {
+        let current_dir = tempfile::tempdir()
+            .unwrap();
+
+        init_in(current_dir.path(), "my-game", true, true, true, &Actions { dry_run: true })
+            .unwrap();
+
+        assert_eq!(fs::read_dir(current_dir.path()).unwrap().count(), 0);
+    }
+}
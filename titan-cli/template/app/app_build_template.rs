@@ -4,13 +4,13 @@ use std::process::Command;
 
 fn main() {
     // Use a trigger file to ensure the LSP build happens every time
-    let trigger_file = Path::new("tools/titan-lsp/.lsp_build_trigger");
+    let trigger_file = Path::new("{lsp_dir}/.lsp_build_trigger");
 
     // Always create or touch the trigger file to ensure rerun-if-changed gets triggered
     File::create(trigger_file).expect("Failed to create trigger file for LSP build");
 
     // Ensure the build.rs script always reruns if the trigger file is touched
-    println!("cargo:rerun-if-changed=tools/titan-lsp/.lsp_build_trigger");
+    println!("cargo:rerun-if-changed={lsp_dir}/.lsp_build_trigger");
 
     // Now, check if we are in a LSP build process to prevent an infinite loop
     if std::env::var("CARGO_BUILDING_LSP").is_ok() {
@@ -19,12 +19,15 @@ fn main() {
     }
 
     // Path to the LSP project
-    let lsp_path = "tools/titan-lsp";
+    let lsp_path = "{lsp_dir}";
 
     // Build the LSP after the app in release mode
     let status = Command::new("cargo")
         .arg("build")
         .arg("--release")
+        // Keep the LSP's own target dir so a workspace build doesn't block on its lock
+        .arg("--target-dir")
+        .arg("target")
         .env("CARGO_BUILDING_LSP", "1") // Set this to prevent loops
         .current_dir(lsp_path)
         .status()
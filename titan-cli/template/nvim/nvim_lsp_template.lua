@@ -0,0 +1,17 @@
+vim.filetype.add({
+  extension = {
+    aa = "adastra",
+    adastra = "adastra",
+  },
+})
+
+vim.api.nvim_create_autocmd("FileType", {
+  pattern = "adastra",
+  callback = function(args)
+    vim.lsp.start({
+      name = "adastra-lsp",
+      cmd = { "{lsp_path}" },
+      root_dir = "{app_path}",
+    }, { bufnr = args.buf })
+  end,
+})
@@ -1,7 +1,10 @@
-use tokio::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard};
+use tokio::{runtime::Handle, sync::{OwnedRwLockReadGuard, OwnedRwLockWriteGuard, RwLock, RwLockReadGuard, RwLockWriteGuard}};
 use std::{future::Future, sync::Arc};
 use crate::{Result, anyhow};
 
+/// Shared `tokio::sync::RwLock`. The lock is fair: waiters are served in FIFO order,
+/// so a writer waiting in `lock` is served before readers that arrive after it,
+/// and mutable tasks can't be starved by a steady stream of immutable ones.
 pub struct ArcLock<T>
 where
     T: Send + 'static
@@ -24,6 +27,12 @@ where
     pub async fn read(&self) -> RwLockReadGuard<'_, T> {
         self.data.read().await
     }
+
+    /// Like `read`, but the guard holds its own `Arc` to the lock, so it is `'static`
+    /// and can be moved into spawned tasks.
+    pub async fn read_owned(&self) -> OwnedRwLockReadGuard<T> {
+        self.data.clone().read_owned().await
+    }
     
     pub fn read_sync(&self) -> Result<RwLockReadGuard<'_, T>> {
         match self.data.try_read() {
@@ -31,11 +40,25 @@ where
             Err(err) => Err(anyhow!("Failed to lock channels: {}", err))
         }
     }
+
+    /// Like `read_sync`, but blocks the thread until the lock is free instead of failing.
+    /// Blocking would stall a runtime worker, so it errors when called from within one.
+    pub fn blocking_read(&self) -> Result<RwLockReadGuard<'_, T>> {
+        Self::ensure_blocking_allowed()?;
+
+        Ok(self.data.blocking_read())
+    }
         
+    /// Waits for exclusive access, queued ahead of any reads requested afterwards.
     pub async fn lock(&self) -> RwLockWriteGuard<'_, T> {
         self.data.write().await
     }
 
+    /// `'static` variant of `lock`, see `read_owned`.
+    pub async fn lock_owned(&self) -> OwnedRwLockWriteGuard<T> {
+        self.data.clone().write_owned().await
+    }
+
     pub fn lock_sync(&self) -> Result<RwLockWriteGuard<'_, T>> {
         match self.data.try_write() {
             Ok(guard) => Ok(guard),
@@ -43,6 +66,21 @@ where
         }
     }
 
+    /// Blocking variant of `lock`, see `blocking_read`.
+    pub fn blocking_lock(&self) -> Result<RwLockWriteGuard<'_, T>> {
+        Self::ensure_blocking_allowed()?;
+
+        Ok(self.data.blocking_write())
+    }
+
+    // tokio panics on blocking lock calls made inside a runtime, so fail gracefully first.
+    fn ensure_blocking_allowed() -> Result<()> {
+        match Handle::try_current() {
+            Ok(_) => Err(anyhow!("Blocking lock called from within an async runtime")),
+            Err(_) => Ok(()),
+        }
+    }
+
     pub async fn write(&self, val: T) {
         let mut lock = self.data.write().await;
         *lock = val;
@@ -68,3 +106,93 @@ where
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::ArcLock;
+    use tokio::task;
+
+    #[tokio::test]
+    async fn queued_writer_is_served_before_later_readers() {
+        let lock = ArcLock::new(0);
+        let held_read = lock.read_owned()
+            .await;
+
+        let writer_lock = lock.clone();
+        let writer = task::spawn(async move {
+            *writer_lock.lock().await = 1;
+        });
+
+        // Lets the writer queue up behind the held read.
+        task::yield_now()
+            .await;
+
+        let reader_lock = lock.clone();
+        let reader = task::spawn(async move {
+            *reader_lock.read().await
+        });
+
+        task::yield_now()
+            .await;
+
+        assert!(!writer.is_finished());
+        assert!(!reader.is_finished(), "read overtook the queued writer");
+
+        drop(held_read);
+
+        writer.await
+            .unwrap();
+
+        assert_eq!(reader.await.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn owned_guard_is_held_inside_a_spawned_task() {
+        let lock = ArcLock::new(0);
+        let mut guard = lock.lock_owned()
+            .await;
+
+        let writer = task::spawn(async move {
+            task::yield_now()
+                .await;
+
+            *guard = 1;
+        });
+
+        let read = *lock.read_owned()
+            .await;
+
+        assert_eq!(read, 1);
+
+        writer.await
+            .unwrap();
+    }
+
+    #[test]
+    fn blocking_read_waits_for_the_writer() {
+        let lock = ArcLock::new(0);
+        let mut held = lock.lock_sync()
+            .unwrap();
+
+        std::thread::scope(|scope| {
+            let reader = scope.spawn(|| *lock.blocking_read().unwrap());
+
+            std::thread::sleep(std::time::Duration::from_millis(20));
+
+            assert!(!reader.is_finished());
+
+            *held = 5;
+            drop(held);
+
+            assert_eq!(reader.join().unwrap(), 5);
+        });
+    }
+
+    #[tokio::test]
+    async fn blocking_calls_inside_a_runtime_error() {
+        let lock = ArcLock::new(0);
+
+        assert!(lock.blocking_read().is_err());
+        assert!(lock.blocking_lock().is_err());
+    }
+}
@@ -1,55 +1,267 @@
 use std::{
-    any::{Any, TypeId}, collections::HashMap, future::Future, pin::Pin, sync::Arc
+    any::{Any, TypeId}, collections::HashMap, future::Future, hash::Hash, panic::Location, pin::Pin, sync::Arc
 };
-use crate::{subsystem::ErasedSubsystemRef, ArcLock, Event, ImmutableTask, MutableTask, Subsystem, SubsystemRef, Task};
+use dashmap::DashSet;
+use futures::future;
+use tokio::runtime::Handle;
+use tracing::{debug, error, warn};
+use crate::{subsystem::{ErasedSubsystemRef, TaskHandle}, ArcLock, DashMap, Event, ImmutableTask, MutableTask, Subsystem, SubsystemRef, Task, TitanError, WeakSubsystemRef};
 
 
+/// Source and target task names of a subscription, for inspecting how subsystems are wired.
+#[derive(Clone, Debug, Hash)]
+pub struct SubscriptionInfo {
+    pub source: &'static str,
+    pub target: &'static str,
+}
+
+struct Subscriber {
+    info: SubscriptionInfo,
+    notify: SubscriberFn,
+}
+
+/// Resolves once a subscriber's task has finished, with its output discarded.
+type Completion = Pin<Box<dyn Future<Output = Result<(), TitanError>> + Send + 'static>>;
+
+/// Resolves to the subscriber task's completion, or why it couldn't be dispatched.
+type SubscriberFuture = Pin<Box<dyn Future<Output = Result<Completion, TitanError>> + Send + 'static>>;
+
 type SubscriberFn = Box<
-    dyn Fn(Box<dyn Any + Send + Sync + 'static>, Channels) -> Pin<Box<dyn Future<Output = ()> + Send + 'static>>
+    dyn Fn(Box<dyn Any + Send + Sync + 'static>, Channels) -> SubscriberFuture
         + Send
         + Sync,
 >;
 
+/// Called with the name of a task that was published with no subscribers.
+type DeadLetterFn = Arc<dyn Fn(&'static str) + Send + Sync>;
+
+/// Subsystem type and registration name, empty for the unnamed `add`/`get` registration.
+type ChannelKey = (TypeId, String);
+
+/// A `#[task(cache)]` task's outputs keyed by its inputs, as a `DashMap<T::Inputs, T::Output>`.
+type TaskCache = Arc<dyn Any + Send + Sync>;
+
 #[derive(Clone)]
 pub struct Channels {
-    channels: ArcLock<HashMap<TypeId, Arc<dyn ErasedSubsystemRef>>>,
-    subscriptions: ArcLock<HashMap<TypeId, Vec<SubscriberFn>>>,
+    channels: ArcLock<HashMap<ChannelKey, Arc<dyn ErasedSubsystemRef>>>,
+    subscriptions: ArcLock<HashMap<TypeId, Vec<Subscriber>>>,
+    dead_letter: ArcLock<Option<DeadLetterFn>>,
+    caches: Arc<DashMap<TypeId, TaskCache>>,
+    /// Tasks already logged as published with no subscribers, so each is logged once.
+    dead_letters: Arc<DashSet<&'static str>>,
 }
 
 impl Channels {
     /// Add a subsystem reference of any type `S` that implements `Subsystem`.
-    pub fn add<S: Subsystem>(&mut self, channel: SubsystemRef<S>) {
-        if let Ok(mut channels_lock) = self.channels.lock_sync() {
-            channels_lock.insert(TypeId::of::<S>(), Arc::new(channel));
+    /// Returns the registration it replaced, if `S` was already added and is still alive.
+    pub async fn add<S: Subsystem>(&mut self, channel: SubsystemRef<S>) -> Option<SubsystemRef<S>> {
+        self.insert::<S>("", Arc::new(channel))
+            .await
+    }
+
+    /// Like `add`, but registered under `name` so several subsystems of the same type
+    /// can coexist, e.g. one per window. Retrieve it with `get_named`.
+    pub async fn add_named<S: Subsystem>(&mut self, name: &str, channel: SubsystemRef<S>) -> Option<SubsystemRef<S>> {
+        self.insert::<S>(name, Arc::new(channel))
+            .await
+    }
+
+    /// Add a weak subsystem reference, so `Channels` alone doesn't keep the subsystem
+    /// loop alive. `get` upgrades it while a strong `SubsystemRef` exists elsewhere.
+    /// Returns the replaced registration like `add`.
+    pub async fn add_weak<S: Subsystem>(&mut self, channel: &SubsystemRef<S>) -> Option<SubsystemRef<S>> {
+        self.insert::<S>("", Arc::new(channel.downgrade()))
+            .await
+    }
+
+    // Waits for the lock rather than failing under contention, so the insert always happens.
+    async fn insert<S: Subsystem>(&self, name: &str, channel: Arc<dyn ErasedSubsystemRef>) -> Option<SubsystemRef<S>> {
+        let added = Self::upgrade_erased::<S>(channel.as_ref());
+
+        let previous = self.channels
+            .lock()
+            .await
+            .insert((TypeId::of::<S>(), name.to_string()), channel)?;
+
+        // A weak registration whose subsystem has stopped is free to take over.
+        let Some(previous) = Self::upgrade_erased::<S>(previous.as_ref()) else {
+            debug!("Channels: {} {:?} was registered again after it stopped", S::name(), name);
+            return None;
+        };
+
+        // Adding a ref that's already registered replaces nothing.
+        if added.as_ref() == Some(&previous) {
+            debug!("Channels: {} {:?} was registered again with the same ref", S::name(), name);
+            return None;
+        }
+
+        warn!("Channels: Replaced existing registration of {} {:?}", S::name(), name);
+
+        Some(previous)
+    }
+
+    fn upgrade_erased<S: Subsystem>(erased: &dyn ErasedSubsystemRef) -> Option<SubsystemRef<S>> {
+        let erased = erased.as_any();
+
+        if let Some(weak) = erased.downcast_ref::<WeakSubsystemRef<S>>() {
+            return weak.upgrade();
         }
+
+        erased
+            .downcast_ref::<SubsystemRef<S>>()
+            .cloned()
     }
 
     /// Retrieve a subsystem reference by its type `S`.
+    /// Panics with the caller's location if it isn't available.
+    #[track_caller]
     pub fn get<S: Subsystem>(&self) -> SubsystemRef<S> {
-        let type_id = TypeId::of::<S>();
+        match self.try_get::<S>() {
+            Ok(subsystem_ref) => subsystem_ref,
+            Err(err) => panic!("Get: {} (at {})", err, Location::caller()),
+        }
+    }
 
-        if let Ok(channels_lock) = self.channels.read_sync() {
-            let erased = channels_lock
-                .get(&type_id)
-                .unwrap_or_else(|| panic!("Get: No subsystem of type `{}` was registered!", std::any::type_name::<S>()));
+    /// Like `get`, but adds `context` to the panic message to say why the subsystem was needed.
+    #[track_caller]
+    pub fn expect_subsystem<S: Subsystem>(&self, context: &str) -> SubsystemRef<S> {
+        match self.try_get::<S>() {
+            Ok(subsystem_ref) => subsystem_ref,
+            Err(err) => panic!("{}: {} (at {})", context, err, Location::caller()),
+        }
+    }
 
-            erased
-                .as_any()
-                .downcast_ref::<SubsystemRef<S>>()
-                .expect("TypeId matched but downcast failed.")
-                .clone()
+    /// Retrieve a subsystem reference by its type `S`, without panicking.
+    pub fn try_get<S: Subsystem>(&self) -> Result<SubsystemRef<S>, TitanError> {
+        self.try_get_named::<S>("")
+    }
 
-        } else {
-            panic!("Failed to acquire read lock!");
+    /// Retrieve the subsystem reference added with `add_named` under `name`.
+    /// Panics with the caller's location if it isn't available.
+    #[track_caller]
+    pub fn get_named<S: Subsystem>(&self, name: &str) -> SubsystemRef<S> {
+        match self.try_get_named::<S>(name) {
+            Ok(subsystem_ref) => subsystem_ref,
+            Err(err) => panic!("Get {:?}: {} (at {})", name, err, Location::caller()),
         }
     }
 
-    pub async fn subscribe<T1, T2>(&self)
+    /// `get_named` without panicking.
+    pub fn try_get_named<S: Subsystem>(&self, name: &str) -> Result<SubsystemRef<S>, TitanError> {
+        let key = (TypeId::of::<S>(), name.to_string());
+        let type_name = std::any::type_name::<S>();
+
+        let channels_lock = self.channels
+            .read_sync()
+            .map_err(|_| TitanError::LockUnavailable)?;
+
+        let erased = channels_lock
+            .get(&key)
+            .ok_or(TitanError::NotRegistered { subsystem: type_name })?;
+
+        let erased = erased.as_any();
+
+        if let Some(weak) = erased.downcast_ref::<WeakSubsystemRef<S>>() {
+            return weak
+                .upgrade()
+                .ok_or(TitanError::SubsystemDropped { subsystem: type_name });
+        }
+
+        erased
+            .downcast_ref::<SubsystemRef<S>>()
+            .cloned()
+            .ok_or(TitanError::Downcast { type_name })
+    }
+
+    // A task subscribed to itself would re-trigger forever once published.
+    fn check_self_subscription<T2: Task>(sources: &[TypeId]) -> Result<(), TitanError> {
+        match sources.contains(&TypeId::of::<T2>()) {
+            true => Err(TitanError::SelfSubscription { task: T2::name() }),
+            false => Ok(()),
+        }
+    }
+
+    // `publish` only counts dispatch errors, so they're logged here.
+    fn dispatched<O>(source: &str, target: &str, dispatch: Result<TaskHandle<O>, TitanError>) -> Result<Completion, TitanError>
+    where
+        O: Send + 'static,
+    {
+        match dispatch {
+            Ok(handle) => Ok(Box::pin(async move {
+                handle
+                    .await
+                    .map(|_| ())
+            })),
+            Err(err) => {
+                error!("Subscription {} -> {}: {}", source, target, err);
+                Err(err)
+            },
+        }
+    }
+
+    /// Queued message count for each registered subsystem, sorted by name.
+    pub fn pending_lens(&self) -> Result<Vec<(&'static str, usize)>, TitanError> {
+        let channels_lock = self.channels
+            .read_sync()
+            .map_err(|_| TitanError::LockUnavailable)?;
+
+        let mut pending = channels_lock
+            .values()
+            .map(|channel| (channel.name(), channel.pending_len()))
+            .collect::<Vec<_>>();
+
+        pending.sort_by_key(|(name, _)| *name);
+
+        Ok(pending)
+    }
+
+    /// Names of the registered subsystems, sorted.
+    pub fn subsystem_names(&self) -> Result<Vec<&'static str>, TitanError> {
+        let mut names = self.channels
+            .read_sync()
+            .map_err(|_| TitanError::LockUnavailable)?
+            .values()
+            .map(|channel| channel.name())
+            .collect::<Vec<_>>();
+
+        names.sort();
+
+        Ok(names)
+    }
+
+    /// Whether publishing `T` would dispatch anything, for publishers that can skip the work.
+    pub async fn has_subscribers<T: Task>(&self) -> bool {
+        self.subscriptions
+            .read()
+            .await
+            .get(&TypeId::of::<T>())
+            .is_some_and(|subscriptions| !subscriptions.is_empty())
+    }
+
+    /// Every active subscription, sorted by source then target. Subscriptions made with
+    /// `subscribe_any` list their source as `any`, and `subscribe_fn` handlers their target as `fn`.
+    pub async fn subscriptions(&self) -> Vec<SubscriptionInfo> {
+        let mut subscriptions = self.subscriptions
+            .read()
+            .await
+            .values()
+            .flatten()
+            .map(|subscriber| subscriber.info.clone())
+            .collect::<Vec<_>>();
+
+        subscriptions.sort_by_key(|info| (info.source, info.target));
+
+        subscriptions
+    }
+
+    pub async fn subscribe<T1, T2>(&self) -> Result<(), TitanError>
     where
         T1: Task + 'static,
         T2: ImmutableTask + From<T1::Inputs> + 'static,
         T1::Inputs: Clone + Send + Sync + 'static,
     {
+        Self::check_self_subscription::<T2>(&[TypeId::of::<T1>()])?;
+
         // Create a subscriber function
         let subscriber: SubscriberFn = Box::new(move |inputs: Box<dyn Any + Send + Sync + 'static>, channels: Channels| {
             let cloned_inputs = match inputs.downcast::<T1::Inputs>() {
@@ -63,10 +275,12 @@ impl Channels {
                 
                 let t2_instance: T2 = T2::from(cloned_inputs);
 
-                channels.get::<T2::Subsystem>()
-                    .send(t2_instance);
+                let dispatch = channels.try_get::<T2::Subsystem>()
+                    .and_then(|subsystem| subsystem.try_send(t2_instance));
+
+                Self::dispatched(T1::name(), T2::name(), dispatch)
 
-            }) as Pin<Box<dyn Future<Output = ()> + Send + 'static>>
+            }) as SubscriberFuture
         });
 
         self.subscriptions
@@ -74,16 +288,23 @@ impl Channels {
             .await
             .entry(TypeId::of::<T1>())
             .or_insert_with(Vec::new)
-            .push(subscriber);
+            .push(Subscriber {
+                info: SubscriptionInfo { source: T1::name(), target: T2::name() },
+                notify: subscriber,
+            });
+
+        Ok(())
     }
 
     
-    pub async fn subscribe_mut<T1, T2>(&self)
+    pub async fn subscribe_mut<T1, T2>(&self) -> Result<(), TitanError>
     where
         T1: Task + 'static,
         T2: MutableTask + From<T1::Inputs> + 'static,
         T1::Inputs: Clone + Send + Sync + 'static,
     {
+        Self::check_self_subscription::<T2>(&[TypeId::of::<T1>()])?;
+
         let subscriber: SubscriberFn = Box::new(
             move |inputs: Box<dyn Any + Send + Sync + 'static>, channels: Channels| {
 
@@ -98,10 +319,12 @@ impl Channels {
 
                     let t2_instance: T2 = T2::from(cloned_inputs);
 
-                    channels.get::<T2::Subsystem>()
-                        .send_mut(t2_instance);
+                    let dispatch = channels.try_get::<T2::Subsystem>()
+                        .and_then(|subsystem| subsystem.try_send_mut(t2_instance));
+
+                    Self::dispatched(T1::name(), T2::name(), dispatch)
                     
-                }) as Pin<Box<dyn Future<Output = ()> + Send + 'static>>
+                }) as SubscriberFuture
             }
         );
 
@@ -110,38 +333,282 @@ impl Channels {
             .await
             .entry(TypeId::of::<T1>())
             .or_insert_with(Vec::new)
-            .push(subscriber);
+            .push(Subscriber {
+                info: SubscriptionInfo { source: T1::name(), target: T2::name() },
+                notify: subscriber,
+            });
+
+        Ok(())
+    }
+
+    /// Runs `handler` with the inputs of every published `T1`, bypassing the task
+    /// machinery. Meant for light hooks like logging; it runs inline in `publish`.
+    pub async fn subscribe_fn<T1>(&self, handler: impl Fn(T1::Inputs) + Send + Sync + 'static)
+    where
+        T1: Task + 'static,
+        T1::Inputs: Clone + Send + Sync + 'static,
+    {
+        let handler = Arc::new(handler);
+
+        let subscriber: SubscriberFn = Box::new(move |inputs: Box<dyn Any + Send + Sync + 'static>, _channels: Channels| {
+            let cloned_inputs = match inputs.downcast::<T1::Inputs>() {
+                Ok(boxed) => (*boxed).clone(),
+                Err(_) => {
+                    panic!("Failed to downcast subscription inputs!");
+                }
+            };
+
+            let handler = handler.clone();
+
+            Box::pin(async move {
+                handler(cloned_inputs);
+                Ok(Box::pin(future::ready(Ok(()))) as Completion)
+            }) as SubscriberFuture
+        });
+
+        self.subscriptions
+            .lock()
+            .await
+            .entry(TypeId::of::<T1>())
+            .or_insert_with(Vec::new)
+            .push(Subscriber {
+                info: SubscriptionInfo { source: T1::name(), target: "fn" },
+                notify: subscriber,
+            });
     }
 
-    pub async fn publish<T>(&self, task: T)
+    /// Subscribes `T2` to every source task type in `sources`, so any of them triggers it.
+    /// Sources have unrelated inputs, so `T2` is built from `()` rather than forwarded inputs.
+    pub async fn subscribe_any<T2>(&self, sources: &[TypeId]) -> Result<(), TitanError>
+    where
+        T2: ImmutableTask + From<()> + 'static,
+    {
+        Self::check_self_subscription::<T2>(sources)?;
+
+        let mut sub_lock = self.subscriptions
+            .lock()
+            .await;
+
+        for source in sources {
+            let subscriber: SubscriberFn = Box::new(move |_inputs, channels: Channels| {
+                Box::pin(async move {
+                    let dispatch = channels.try_get::<T2::Subsystem>()
+                        .and_then(|subsystem| subsystem.try_send(T2::from(())));
+
+                    Self::dispatched("any", T2::name(), dispatch)
+                }) as SubscriberFuture
+            });
+
+            sub_lock
+                .entry(*source)
+                .or_insert_with(Vec::new)
+                .push(Subscriber {
+                    info: SubscriptionInfo { source: "any", target: T2::name() },
+                    notify: subscriber,
+                });
+        }
+
+        Ok(())
+    }
+
+    /// Mutable variant of `subscribe_any`.
+    pub async fn subscribe_any_mut<T2>(&self, sources: &[TypeId]) -> Result<(), TitanError>
+    where
+        T2: MutableTask + From<()> + 'static,
+    {
+        Self::check_self_subscription::<T2>(sources)?;
+
+        let mut sub_lock = self.subscriptions
+            .lock()
+            .await;
+
+        for source in sources {
+            let subscriber: SubscriberFn = Box::new(move |_inputs, channels: Channels| {
+                Box::pin(async move {
+                    let dispatch = channels.try_get::<T2::Subsystem>()
+                        .and_then(|subsystem| subsystem.try_send_mut(T2::from(())));
+
+                    Self::dispatched("any", T2::name(), dispatch)
+                }) as SubscriberFuture
+            });
+
+            sub_lock
+                .entry(*source)
+                .or_insert_with(Vec::new)
+                .push(Subscriber {
+                    info: SubscriptionInfo { source: "any", target: T2::name() },
+                    notify: subscriber,
+                });
+        }
+
+        Ok(())
+    }
+
+    /// Notifies every subscriber of `T` concurrently, returning how many dispatched successfully.
+    pub async fn publish<T>(&self, task: T) -> usize
     where
         T: ImmutableTask,
         T::Inputs: Clone + Sync + 'static,
     {    
-        let type_id = TypeId::of::<T>();
-        let sub_lock = self.subscriptions.read().await;
-        if let Some(subscriptions) = sub_lock.get(&type_id) {
-            for subscription in subscriptions {
-                let inputs: Box<dyn Any + Send + Sync + 'static> = Box::new(task.inputs().clone());
-                subscription(inputs, self.clone())
-                    .await;
-            }
-        }
+        Self::dispatched_count(self.notify(TypeId::of::<T>(), T::name(), task.inputs()).await)
     }
 
-    
-    pub async fn publish_mut<T>(&self, task: T)
+    /// Like `publish`, but waits for every subscriber's task to finish, so reactions can be
+    /// ordered. Returns one result per subscriber: dispatch errors, or how its task ended.
+    pub async fn publish_await<T>(&self, task: T) -> Vec<Result<(), TitanError>>
+    where
+        T: ImmutableTask,
+        T::Inputs: Clone + Sync + 'static,
+    {
+        Self::completed(self.notify(TypeId::of::<T>(), T::name(), task.inputs()).await)
+            .await
+    }
+
+    /// Mutable variant of `publish_await`.
+    pub async fn publish_mut_await<T>(&self, task: T) -> Vec<Result<(), TitanError>>
+    where
+        T: MutableTask,
+        T::Inputs: Clone + Sync + 'static,
+    {
+        Self::completed(self.notify(TypeId::of::<T>(), T::name(), task.inputs()).await)
+            .await
+    }
+
+    /// Notifies every subscriber of `T` concurrently, returning how many dispatched successfully.
+    pub async fn publish_mut<T>(&self, task: T) -> usize
     where
         T: MutableTask,
         T::Inputs: Clone + Sync + 'static,
     {    
-        let type_id = TypeId::of::<T>();
+        Self::dispatched_count(self.notify(TypeId::of::<T>(), T::name(), task.inputs()).await)
+    }
+
+    /// Routes publishes that find no subscribers to `handler` instead of the default `debug!`.
+    pub async fn set_dead_letter(&self, handler: impl Fn(&'static str) + Send + Sync + 'static) {
+        self.dead_letter
+            .write(Some(Arc::new(handler)))
+            .await;
+    }
+
+    /// Memoized outputs of the `#[task(cache)]` task `T`, created empty on first use.
+    pub fn task_cache<T>(&self) -> Arc<DashMap<T::Inputs, T::Output>>
+    where
+        T: Task,
+        T::Inputs: Hash + Eq,
+        T::Output: Clone,
+    {
+        self.caches
+            .entry(TypeId::of::<T>())
+            .or_insert_with(|| Arc::new(DashMap::<T::Inputs, T::Output>::new()))
+            .clone()
+            .downcast::<DashMap<T::Inputs, T::Output>>()
+            .expect("Task caches are keyed by their task type")
+    }
+
+    /// Drops every output cached for `T`, so its next calls run the task again.
+    pub fn invalidate_cache<T: Task>(&self) {
+        self.caches
+            .remove(&TypeId::of::<T>());
+    }
+
+    /// Spawns `publish` onto the current runtime, for synchronous callbacks that can't `.await`.
+    /// Enter a runtime handle first when calling from a thread the runtime doesn't own.
+    pub fn publish_sync<T>(&self, task: T) -> Result<(), TitanError>
+    where
+        T: ImmutableTask,
+        T::Inputs: Clone + Sync + 'static,
+    {
+        let runtime = Handle::try_current()
+            .map_err(|_| TitanError::NoRuntime { task: T::name() })?;
+
+        let channels = self.clone();
+
+        runtime.spawn(async move {
+            channels.publish(task)
+                .await;
+        });
+
+        Ok(())
+    }
+
+    /// Mutable variant of `publish_sync`.
+    pub fn publish_mut_sync<T>(&self, task: T) -> Result<(), TitanError>
+    where
+        T: MutableTask,
+        T::Inputs: Clone + Sync + 'static,
+    {
+        let runtime = Handle::try_current()
+            .map_err(|_| TitanError::NoRuntime { task: T::name() })?;
+
+        let channels = self.clone();
+
+        runtime.spawn(async move {
+            channels.publish_mut(task)
+                .await;
+        });
+
+        Ok(())
+    }
+
+    // Subscribers only enqueue their task without awaiting it, so mutable subscribers
+    // contending for the same subsystem lock can't deadlock each other here.
+    async fn notify<I>(&self, type_id: TypeId, task: &'static str, inputs: I) -> Vec<Result<Completion, TitanError>>
+    where
+        I: Clone + Send + Sync + 'static,
+    {
         let sub_lock = self.subscriptions.read().await;
-        if let Some(subscriptions) = sub_lock.get(&type_id) {
-            for subscription in subscriptions {
-                let inputs: Box<dyn Any + Send + Sync + 'static> = Box::new(task.inputs().clone());
-                subscription(inputs, self.clone())
-                    .await;
+
+        let Some(subscriptions) = sub_lock.get(&type_id).filter(|subscriptions| !subscriptions.is_empty()) else {
+            drop(sub_lock);
+            self.dead_letter(task)
+                .await;
+            return Vec::new();
+        };
+
+        let notifications = subscriptions
+            .iter()
+            .map(|subscription| {
+                let inputs: Box<dyn Any + Send + Sync + 'static> = Box::new(inputs.clone());
+                (subscription.notify)(inputs, self.clone())
+            });
+
+        future::join_all(notifications)
+            .await
+    }
+
+    fn dispatched_count(dispatches: Vec<Result<Completion, TitanError>>) -> usize {
+        dispatches
+            .iter()
+            .filter(|dispatch| dispatch.is_ok())
+            .count()
+    }
+
+    async fn completed(dispatches: Vec<Result<Completion, TitanError>>) -> Vec<Result<(), TitanError>> {
+        let completions = dispatches
+            .into_iter()
+            .map(|dispatch| async move {
+                dispatch?
+                    .await
+            });
+
+        future::join_all(completions)
+            .await
+    }
+
+    // Published with nobody listening, usually a subscription that was never wired up.
+    // Some tasks publish on every run, so without a handler each is only logged the first time.
+    async fn dead_letter(&self, task: &'static str) {
+        let handler = self.dead_letter
+            .read()
+            .await
+            .clone();
+
+        match handler {
+            Some(handler) => handler(task),
+            None => {
+                if self.dead_letters.insert(task) {
+                    debug!("{}: Published with no subscribers", task);
+                }
             }
         }
     }
@@ -152,6 +619,349 @@ impl Default for Channels {
         Self {
             channels: ArcLock::new(HashMap::new()),
             subscriptions: ArcLock::new(HashMap::new()),
+            dead_letter: ArcLock::new(None),
+            caches: Arc::new(DashMap::new()),
+            dead_letters: Arc::new(DashSet::new()),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{logs::LogHistory, subsystem::tests::*};
+    use std::time::Duration;
+    use tokio::time::Instant;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    #[tokio::test(start_paused = true)]
+    async fn publish_runs_subscribers_concurrently() {
+        let channels = start_subsystems()
+            .await;
+
+        channels.subscribe::<Wake, Nap>()
+            .await
+            .unwrap();
+        channels.subscribe::<Wake, Doze>()
+            .await
+            .unwrap();
+
+        let start = Instant::now();
+
+        let results = channels.publish_await(Wake { millis: 100 })
+            .await;
+
+        assert_eq!(results, [Ok(()), Ok(())]);
+
+        // Run one after the other the naps would take 200ms.
+        assert!(start.elapsed() < Duration::from_millis(150), "{:?}", start.elapsed());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn publish_await_waits_for_the_subscribers_to_finish() {
+        let channels = start_subsystems()
+            .await;
+
+        channels.subscribe::<Wake, Nap>()
+            .await
+            .unwrap();
+        channels.subscribe_any::<Fail>(&[TypeId::of::<Wake>()])
+            .await
+            .unwrap();
+
+        let start = Instant::now();
+
+        assert_eq!(channels.publish(Wake { millis: 100 }).await, 2);
+        assert!(start.elapsed() < Duration::from_millis(100), "{:?}", start.elapsed());
+
+        let start = Instant::now();
+
+        let results = channels.publish_await(Wake { millis: 100 })
+            .await;
+
+        assert!(start.elapsed() >= Duration::from_millis(100), "{:?}", start.elapsed());
+        assert_eq!(results.len(), 2);
+        assert!(results.contains(&Ok(())));
+        assert!(results.iter().any(|result| matches!(result, Err(TitanError::TaskPanicked { .. }))), "{:?}", results);
+    }
+
+    #[tokio::test]
+    async fn any_source_triggers_the_fan_in_target() {
+        let channels = start_subsystems()
+            .await;
+
+        channels.subscribe_any::<Tally>(&[TypeId::of::<Wake>(), TypeId::of::<Inner>()])
+            .await
+            .unwrap();
+
+        channels.publish_await(Wake { millis: 0 })
+            .await;
+        channels.publish_await(Inner)
+            .await;
+
+        let tallies = channels
+            .get::<TestSubsystem>()
+            .send(Tallies)
+            .await
+            .unwrap();
+
+        assert_eq!(tallies, 2);
+    }
+
+    #[tokio::test]
+    async fn publish_sync_reaches_an_async_subscriber() {
+        let channels = start_subsystems()
+            .await;
+
+        channels.subscribe_any::<Tally>(&[TypeId::of::<Wake>()])
+            .await
+            .unwrap();
+
+        let callback = || channels.publish_sync(Wake { millis: 0 });
+
+        callback()
+            .unwrap();
+
+        let test_ref = channels.get::<TestSubsystem>();
+
+        for _ in 0..100 {
+            if test_ref.send(Tallies).await.unwrap() == 1 {
+                return;
+            }
+
+            tokio::time::sleep(Duration::from_millis(10))
+                .await;
+        }
+
+        panic!("Tally never ran");
+    }
+
+    #[test]
+    fn publish_sync_outside_a_runtime_errors() {
+        let result = Channels::default()
+            .publish_sync(Wake { millis: 0 });
+
+        assert_eq!(result, Err(TitanError::NoRuntime { task: <Wake as Task>::name() }));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn concurrent_adds_all_register() {
+        let channels = Channels::default();
+
+        // A held read lock used to make the adds skip their insert.
+        let read_guard = channels.channels
+            .read()
+            .await;
+
+        let adds = (0..32)
+            .map(|index| {
+                let mut channels = channels.clone();
+                let (test_ref, _test_receiver) = SubsystemRef::<TestSubsystem>::new();
+
+                tokio::spawn(async move {
+                    channels.add_named(&index.to_string(), test_ref)
+                        .await
+                })
+            })
+            .collect::<Vec<_>>();
+
+        tokio::time::sleep(Duration::from_millis(10))
+            .await;
+
+        drop(read_guard);
+
+        for add in adds {
+            assert!(add.await.unwrap().is_none());
+        }
+
+        for index in 0..32 {
+            assert!(channels.try_get_named::<TestSubsystem>(&index.to_string()).is_ok());
+        }
+    }
+
+    #[tokio::test]
+    async fn closure_subscriber_gets_the_published_inputs() {
+        let channels = start_subsystems()
+            .await;
+
+        let received = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let handler_received = received.clone();
+
+        channels.subscribe_fn::<Wake>(move |millis| handler_received.lock().unwrap().push(millis))
+            .await;
+
+        channels.publish_await(Wake { millis: 7 })
+            .await;
+        channels.publish_await(Wake { millis: 9 })
+            .await;
+
+        assert_eq!(*received.lock().unwrap(), [7, 9]);
+    }
+
+    #[tokio::test]
+    async fn self_subscription_is_rejected() {
+        let channels = start_subsystems()
+            .await;
+
+        let rejected = channels.subscribe::<Wake, Wake>()
+            .await;
+
+        assert_eq!(rejected, Err(TitanError::SelfSubscription { task: <Wake as Task>::name() }));
+
+        let rejected = channels.subscribe_any::<Tally>(&[TypeId::of::<Inner>(), TypeId::of::<Tally>()])
+            .await;
+
+        assert_eq!(rejected, Err(TitanError::SelfSubscription { task: <Tally as Task>::name() }));
+        assert!(channels.subscriptions().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn unregistered_target_is_reported_not_panicked() {
+        let channels = start_subsystems()
+            .await;
+
+        // `HookedSubsystem` is never registered by `start_subsystems`.
+        channels.subscribe_any::<Hooks>(&[TypeId::of::<Wake>()])
+            .await
+            .unwrap();
+        channels.subscribe_any::<Tally>(&[TypeId::of::<Wake>()])
+            .await
+            .unwrap();
+
+        let dispatched = channels.publish(Wake { millis: 0 })
+            .await;
+
+        assert_eq!(dispatched, 1);
+
+        let results = channels.publish_await(Wake { millis: 0 })
+            .await;
+
+        assert!(results.contains(&Ok(())));
+        assert!(results.iter().any(|result| matches!(result, Err(TitanError::NotRegistered { .. }))));
+    }
+
+    #[test]
+    #[should_panic(expected = "Loading the level: No subsystem of type")]
+    fn expect_subsystem_panics_with_the_context() {
+        Channels::default()
+            .expect_subsystem::<TestSubsystem>("Loading the level");
+    }
+
+    #[tokio::test]
+    async fn re_adding_the_same_ref_replaces_nothing() {
+        let mut channels = Channels::default();
+        let (test_ref, _test_receiver) = SubsystemRef::<TestSubsystem>::new();
+
+        assert!(channels.add(test_ref.clone()).await.is_none());
+        assert!(channels.add(test_ref.clone()).await.is_none());
+        assert!(channels.get::<TestSubsystem>() == test_ref);
+    }
+
+    #[tokio::test]
+    async fn re_adding_returns_the_replaced_registration() {
+        let mut channels = Channels::default();
+        let (first_ref, _first_receiver) = SubsystemRef::<TestSubsystem>::new();
+        let (second_ref, _second_receiver) = SubsystemRef::<TestSubsystem>::new();
+
+        assert!(channels.add(first_ref.clone()).await.is_none());
+
+        let replaced = channels.add(second_ref.clone())
+            .await;
+
+        assert!(replaced == Some(first_ref));
+        assert!(channels.get::<TestSubsystem>() == second_ref);
+
+        // A weak registration whose subsystem is gone has nothing to return.
+        let (weak_ref, _weak_receiver) = SubsystemRef::<HookedSubsystem>::new();
+
+        channels.add_weak(&weak_ref)
+            .await;
+
+        drop(weak_ref);
+
+        let (hooked_ref, _hooked_receiver) = SubsystemRef::<HookedSubsystem>::new();
+
+        assert!(channels.add(hooked_ref).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn re_adding_after_a_weak_ref_stopped_is_not_a_replacement() {
+        let history = LogHistory::new(64);
+        let _subscriber = tracing::subscriber::set_default(
+            tracing_subscriber::registry().with(history.clone()),
+        );
+
+        let replacements = || history
+            .recent(64)
+            .into_iter()
+            .filter(|line| line.starts_with("WARN") && line.contains("Replaced"))
+            .count();
+
+        let mut channels = Channels::default();
+        let (stopped_ref, _stopped_receiver) = SubsystemRef::<TestSubsystem>::new();
+
+        channels.add_weak(&stopped_ref)
+            .await;
+
+        drop(stopped_ref);
+
+        let (live_ref, _live_receiver) = SubsystemRef::<TestSubsystem>::new();
+
+        assert!(channels.add_weak(&live_ref).await.is_none());
+        assert_eq!(replacements(), 0);
+
+        // Replacing the weak ref while its subsystem is still alive is.
+        let (other_ref, _other_receiver) = SubsystemRef::<TestSubsystem>::new();
+
+        assert!(channels.add(other_ref).await == Some(live_ref));
+        assert_eq!(replacements(), 1);
+    }
+
+    #[tokio::test]
+    async fn unsubscribed_publish_goes_to_the_dead_letter() {
+        let channels = start_subsystems()
+            .await;
+
+        let dead_letters = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let handler_letters = dead_letters.clone();
+
+        channels.set_dead_letter(move |task| handler_letters.lock().unwrap().push(task))
+            .await;
+
+        assert_eq!(channels.publish(Wake { millis: 0 }).await, 0);
+
+        channels.subscribe_any::<Tally>(&[TypeId::of::<Wake>()])
+            .await
+            .unwrap();
+
+        assert_eq!(channels.publish(Wake { millis: 0 }).await, 1);
+        assert_eq!(*dead_letters.lock().unwrap(), [<Wake as Task>::name()]);
+    }
+
+    #[tokio::test]
+    async fn named_registrations_are_separate_instances() {
+        let mut channels = Channels::default();
+
+        for name in ["left", "right"] {
+            let (test_ref, test_receiver) = SubsystemRef::<TestSubsystem>::new();
+
+            channels.add_named(name, test_ref)
+                .await;
+
+            TestSubsystem::start_with_handle(TestSubsystem::new(channels.clone()), test_receiver, None);
+        }
+
+        for _ in 0..2 {
+            channels.get_named::<TestSubsystem>("left")
+                .send(Tally)
+                .await
+                .unwrap();
+        }
+
+        assert_eq!(channels.get_named::<TestSubsystem>("left").send(Tallies).await, Ok(2));
+        assert_eq!(channels.get_named::<TestSubsystem>("right").send(Tallies).await, Ok(0));
+
+        // The unnamed registration is its own slot.
+        assert!(matches!(channels.try_get::<TestSubsystem>(), Err(TitanError::NotRegistered { .. })));
+    }
+}
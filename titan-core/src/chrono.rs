@@ -1,12 +1,66 @@
 pub fn format_duration(seconds: &f64) -> String {
+    let (sign, value, unit) = duration_parts(seconds);
+
+    format!("{}{:0>7.2} {}", sign, value, unit)
+}
+
+/// Space-padded variant of `format_duration`, e.g. `  40.00 ms`, for aligned table columns.
+pub fn format_duration_compact(seconds: &f64) -> String {
+    let (sign, value, unit) = duration_parts(seconds);
+
+    // Signed before padding so a `-` sits against the digits.
+    format!("{:>7} {}", format!("{}{:.2}", sign, value), unit)
+}
+
+/// Sign, magnitude and unit; the unit is picked from the magnitude alone.
+fn duration_parts(seconds: &f64) -> (&'static str, f64, &'static str) {
+    let sign = match *seconds < 0.0 {
+        true => "-",
+        false => "",
+    };
+
     let abs_seconds = seconds.abs();
 
-    let (value, unit) = if abs_seconds < 1.0 {
+    if abs_seconds < 1.0 {
         let millis = abs_seconds * 1_000.0;
-        (millis, "ms")
+        (sign, millis, "ms")
     } else {
-        (abs_seconds, "s")
-    };
+        (sign, abs_seconds, "s")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compact_pads_with_spaces_across_magnitudes() {
+        let cases = [
+            (0.000_5, "0000.50 ms", "   0.50 ms"),
+            (0.04, "0040.00 ms", "  40.00 ms"),
+            (0.123_4, "0123.40 ms", " 123.40 ms"),
+            (2.5, "0002.50 s", "   2.50 s"),
+            (1234.5, "1234.50 s", "1234.50 s"),
+        ];
+
+        for (seconds, padded, compact) in cases {
+            assert_eq!(format_duration(&seconds), padded);
+            assert_eq!(format_duration_compact(&seconds), compact);
+            assert_eq!(padded.len(), compact.len());
+        }
+    }
+
+    #[test]
+    fn negative_durations_keep_their_sign() {
+        let cases = [
+            (-0.04, "-0040.00 ms", " -40.00 ms"),
+            (-2.5, "-0002.50 s", "  -2.50 s"),
+            (-0.0, "0000.00 ms", "   0.00 ms"),
+        ];
 
-    format!("{:0>7.2} {}", value, unit)
+        for (seconds, padded, compact) in cases {
+            assert_eq!(format_duration(&seconds), padded);
+            assert_eq!(format_duration_compact(&seconds), compact);
+        }
+    }
 }
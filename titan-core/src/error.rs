@@ -0,0 +1,98 @@
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TitanError {
+    /// The subsystem dropped the task before sending a result back.
+    ChannelClosed { task: &'static str },
+    TaskPanicked { task: &'static str },
+    Timeout { task: &'static str },
+    /// `Task::validate` rejected the inputs, so the task never ran.
+    InvalidInput { task: &'static str, reason: String },
+    NotRegistered { subsystem: &'static str },
+    /// The subsystem was registered weakly and all of its strong refs were dropped.
+    SubsystemDropped { subsystem: &'static str },
+    Downcast { type_name: &'static str },
+    LockUnavailable,
+    /// The task was dispatched deeper than `TasksSubsystem::max_task_depth`.
+    DepthExceeded { task: &'static str, depth: usize },
+    /// A task was subscribed to its own publications.
+    SelfSubscription { task: &'static str },
+    /// A sync dispatch was made from a thread with no tokio runtime entered.
+    NoRuntime { task: &'static str },
+    /// An idempotent task was sent while an identical one was still queued.
+    Coalesced { task: &'static str },
+    /// A mutable task was awaited from a mutable task on the same subsystem instance, which
+    /// holds the write lock it needs.
+    Reentrant { task: &'static str, subsystem: &'static str },
+}
+
+impl fmt::Display for TitanError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TitanError::ChannelClosed { task } => write!(f, "{}: Task channel closed before a result was sent", task),
+            TitanError::TaskPanicked { task } => write!(f, "{}: Task panicked during execution", task),
+            TitanError::Timeout { task } => write!(f, "{}: Task timed out", task),
+            TitanError::InvalidInput { task, reason } => write!(f, "{}: Invalid input: {}", task, reason),
+            TitanError::NotRegistered { subsystem } => write!(f, "No subsystem of type `{}` was registered!", subsystem),
+            TitanError::SubsystemDropped { subsystem } => write!(f, "Subsystem of type `{}` was dropped", subsystem),
+            TitanError::Downcast { type_name } => write!(f, "TypeId matched but downcast to `{}` failed", type_name),
+            TitanError::LockUnavailable => write!(f, "Failed to acquire channels lock"),
+            TitanError::DepthExceeded { task, depth } => write!(f, "{}: Task depth {} exceeds the limit", task, depth),
+            TitanError::SelfSubscription { task } => write!(f, "{}: Task can't subscribe to itself", task),
+            TitanError::NoRuntime { task } => write!(f, "{}: No tokio runtime available", task),
+            TitanError::Coalesced { task } => write!(f, "{}: Coalesced with an identical queued task", task),
+            TitanError::Reentrant { task, subsystem } => write!(f, "{}: Awaited from a mutable task on `{}`, which holds the lock it needs", task, subsystem),
+        }
+    }
+}
+
+impl std::error::Error for TitanError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{subsystem::tests::*, Channels, SubsystemRef, Task};
+    use std::time::Duration;
+
+    #[tokio::test(start_paused = true)]
+    async fn dispatch_failures_match_their_variant() {
+        let channels = start_subsystems()
+            .await;
+
+        let test_ref = channels.get::<TestSubsystem>();
+
+        let panicked = test_ref.send(Fail)
+            .await;
+
+        assert!(matches!(panicked, Err(TitanError::TaskPanicked { task }) if task == <Fail as Task>::name()));
+
+        let timed_out = test_ref.send(Nap { millis: 1000 })
+            .timeout(Duration::from_millis(10))
+            .await;
+
+        assert!(matches!(timed_out, Err(TitanError::Timeout { task }) if task == <Nap as Task>::name()));
+
+        let (closed_ref, closed_receiver) = SubsystemRef::<TestSubsystem>::new();
+        drop(closed_receiver);
+
+        let closed = closed_ref.send(Inner)
+            .await;
+
+        assert!(matches!(closed, Err(TitanError::ChannelClosed { task }) if task == <Inner as Task>::name()));
+
+        let unregistered = Channels::default()
+            .try_get::<TestSubsystem>();
+
+        assert!(matches!(unregistered, Err(TitanError::NotRegistered { subsystem }) if subsystem.ends_with("TestSubsystem")));
+    }
+
+    #[test]
+    fn display_names_the_task() {
+        let err = TitanError::InvalidInput {
+            task: "TestSubsystem::Nap",
+            reason: String::from("too long"),
+        };
+
+        assert_eq!(err.to_string(), "TestSubsystem::Nap: Invalid input: too long");
+    }
+}
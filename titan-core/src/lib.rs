@@ -2,19 +2,23 @@ mod subsystem;
 pub mod tasks;
 mod channels;
 mod arclock;
+mod error;
 pub mod chrono;
+pub mod logs;
 
 pub use anyhow::{anyhow, Result};
 pub use arclock::ArcLock;
 pub use async_trait::async_trait;
-pub use channels::Channels;
+pub use channels::{Channels, SubscriptionInfo};
+pub use error::TitanError;
 pub use dashmap::DashMap;
 pub use futures;
 pub use indexmap::IndexMap;
 pub use log;
-pub use subsystem::{Subsystem, SubsystemRef, Task, ImmutableTask, MutableTask, Event};
+pub use subsystem::{RestartPolicy, Subsystem, SubsystemControl, SubsystemRef, WeakSubsystemRef, Task, ImmutableTask, MutableTask, StreamTask, TaskStream, Event};
 pub use titan_macro::{subsystem, task};
 pub use tokio as runtime;
 pub use tracing::{debug, error, info, trace, warn};
 pub use tracing_subscriber;
 pub use tracing;
+pub use toml;
@@ -0,0 +1,118 @@
+use std::{
+    collections::VecDeque,
+    fmt::{self, Write},
+    sync::{Arc, Mutex},
+};
+use tracing::{field::{Field, Visit}, Subscriber};
+use tracing_subscriber::{layer::Context, Layer};
+
+/// Lines `LogHistory` keeps unless configured otherwise.
+pub const DEFAULT_LOG_HISTORY: usize = 1024;
+
+/// Bounded history of recent log lines. Install it as a tracing layer to fill it;
+/// once full, the oldest lines are dropped.
+#[derive(Clone)]
+pub struct LogHistory {
+    lines: Arc<Mutex<VecDeque<String>>>,
+    capacity: usize,
+}
+
+impl LogHistory {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            lines: Arc::new(Mutex::new(VecDeque::with_capacity(capacity))),
+            capacity,
+        }
+    }
+
+    /// The last `n` lines, oldest first.
+    pub fn recent(&self, n: usize) -> Vec<String> {
+        let lines = self.lines
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+
+        lines.iter()
+            .skip(lines.len().saturating_sub(n))
+            .cloned()
+            .collect()
+    }
+
+    fn push(&self, line: String) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        let mut lines = self.lines
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+
+        if lines.len() == self.capacity {
+            lines.pop_front();
+        }
+
+        lines.push_back(line);
+    }
+}
+
+impl Default for LogHistory {
+    fn default() -> Self {
+        Self::new(DEFAULT_LOG_HISTORY)
+    }
+}
+
+impl<S> Layer<S> for LogHistory
+where
+    S: Subscriber,
+{
+    fn on_event(&self, event: &tracing::Event<'_>, _ctx: Context<'_, S>) {
+        let metadata = event.metadata();
+
+        let mut line = LineVisitor(format!("{} {}:", metadata.level(), metadata.target()));
+
+        event.record(&mut line);
+
+        self.push(line.0);
+    }
+}
+
+/// Appends the message, then any other fields as `name=value`.
+struct LineVisitor(String);
+
+impl Visit for LineVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        let _ = match field.name() {
+            "message" => write!(self.0, " {:?}", value),
+            name => write!(self.0, " {}={:?}", name, value),
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LogHistory;
+    use tracing::{info, warn};
+    use tracing_subscriber::layer::SubscriberExt;
+
+    #[test]
+    fn keeps_the_latest_lines_oldest_first() {
+        let history = LogHistory::new(3);
+        let subscriber = tracing_subscriber::registry()
+            .with(history.clone());
+
+        tracing::subscriber::with_default(subscriber, || {
+            for frame in 0..4 {
+                info!(frame, "Frame");
+            }
+
+            warn!("Slow frame");
+        });
+
+        assert_eq!(history.recent(10), [
+            "INFO titan_core::logs::tests: Frame frame=2",
+            "INFO titan_core::logs::tests: Frame frame=3",
+            "WARN titan_core::logs::tests: Slow frame",
+        ]);
+
+        assert_eq!(history.recent(1), ["WARN titan_core::logs::tests: Slow frame"]);
+    }
+}
@@ -1,16 +1,42 @@
-use std::{any::Any, future::Future, pin::Pin, task::Poll};
+use std::{
+    any::Any, future::Future, panic::AssertUnwindSafe, pin::Pin,
+    collections::{BTreeSet, HashSet}, sync::{atomic::{AtomicU64, AtomicUsize, Ordering}, Arc, Mutex}, task::Poll, time::Duration,
+};
 use anyhow::Result;
 use async_trait::async_trait;
-use futures::{stream::FuturesUnordered, Stream};
+use futures::{stream::FuturesOrdered, FutureExt, Stream, TryStreamExt};
 use log::{error, trace};
 use tokio::{
-    sync::{mpsc, oneshot}, time::Instant
+    runtime::{Handle, RuntimeFlavor}, sync::{mpsc, oneshot, Notify}, task::JoinHandle, time::Instant
 };
-use tracing::{debug, info};
-use crate::{chrono, tasks::{self, TasksSubsystem}, ArcLock, Channels};
+use tracing::{debug, info, info_span, warn, Instrument};
+use crate::{tasks::{self, TasksSubsystem}, ArcLock, Channels, TitanError};
 
 pub trait Event: Send + 'static {}
 
+/// Identity of the task currently executing on this tokio task, used to
+/// nest tasks dispatched from within it.
+#[derive(Clone)]
+pub struct TaskScope {
+    pub id: String,
+    pub depth: usize,
+    /// Id of the top-level task this one descends from, shared by the whole dispatch chain.
+    pub correlation: String,
+}
+
+tokio::task_local! {
+    static CURRENT_TASK: TaskScope;
+    /// `ptr_id` of the subsystem instance whose write lock the mutable task running on
+    /// this tokio task holds. Named instances of one type have separate locks.
+    static LOCKED_SUBSYSTEM: usize;
+}
+
+pub fn current_task() -> Option<TaskScope> {
+    CURRENT_TASK
+        .try_with(|scope| scope.clone())
+        .ok()
+}
+
 pub trait Task: Clone + Send + 'static {
     type Subsystem: Subsystem;
     type Event: Event;
@@ -33,6 +59,40 @@ pub trait Task: Clone + Send + 'static {
         false
     }
 
+    /// Section the task's benchmark is listed under, the subsystem name unless set.
+    fn group() -> &'static str {
+        Self::Subsystem::name()
+    }
+
+    /// Duration past which a run logs a warning, set with `#[task(warn_after = "16ms")]`.
+    /// The task still runs to completion.
+    fn warn_after() -> Option<Duration> {
+        None
+    }
+
+    /// Relative expense of the task, for schedulers balancing how much heavy work runs at once.
+    fn cost() -> u32 {
+        1
+    }
+
+    /// Formats the task's return value for its task log display, enabled with
+    /// `#[task(display_output)]`.
+    fn display_output(_output: &Self::Output) -> Option<String> {
+        None
+    }
+
+    /// Idempotent tasks are coalesced: sending one while an identical task is still
+    /// queued rejects it with `TitanError::Coalesced` instead of queueing it again.
+    fn idempotent() -> bool {
+        false
+    }
+
+    /// Checks the task's inputs before it executes. A failure is sent back
+    /// to the caller as `TitanError::InvalidInput` and the body never runs.
+    fn validate(&self) -> Result<()> {
+        Ok(())
+    }
+
     fn inputs(&self) -> Self::Inputs;
  }
 
@@ -42,6 +102,11 @@ pub trait TaskInfo: Send + 'static {
     fn log(&self) -> bool;
     fn benchmark(&self) -> bool;
     fn io(&self) -> bool;
+    fn group(&self) -> &'static str;
+    fn cost(&self) -> u32;
+    fn warn_after(&self) -> Option<Duration>;
+    fn idempotent(&self) -> bool;
+    fn validate(&self) -> Result<()>;
     fn new_id(&self) -> String;
 }
 
@@ -65,11 +130,42 @@ where
         T::io()
     }
 
+    fn group(&self) -> &'static str {
+        T::group()
+    }
+
+    fn cost(&self) -> u32 {
+        T::cost()
+    }
+
+    fn warn_after(&self) -> Option<Duration> {
+        T::warn_after()
+    }
+
+    fn idempotent(&self) -> bool {
+        T::idempotent()
+    }
+
+    fn validate(&self) -> Result<()> {
+        Task::validate(self)
+    }
+
     fn new_id(&self) -> String {
-        format!("{}_{}", T::name(), nanoid::nanoid!(16))
+        format!("{}_{}", T::name(), next_task_id())
     }
 }
 
+#[cfg(not(feature = "deterministic-ids"))]
+fn next_task_id() -> String {
+    nanoid::nanoid!(16)
+}
+
+#[cfg(feature = "deterministic-ids")]
+fn next_task_id() -> String {
+    static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+
+    format!("{:016}", NEXT_ID.fetch_add(1, Ordering::Relaxed))
+}
 
 #[async_trait]
 pub trait ImmutableTask: Task {
@@ -81,14 +177,127 @@ pub trait MutableTask: Task {
     async fn execute(self, _subsystem: &mut Self::Subsystem) -> Self::Output;
 }
 
+/// Sending half handed to a streaming task's body for emitting items.
+pub type TaskStream<T> = mpsc::Sender<T>;
+
+/// Buffered items per stream before the task's `send` waits on the receiver.
+const STREAM_BUFFER: usize = 32;
+
+/// Longest a non-`io` task may run between yields, in debug builds, before it's reported
+/// as blocking the executor.
+#[cfg(debug_assertions)]
+const BLOCKING_POLL_THRESHOLD: Duration = Duration::from_millis(10);
+
+/// Times each poll of a task's future, which is how long it held the executor thread,
+/// and warns once when a poll exceeds `BLOCKING_POLL_THRESHOLD`.
+#[cfg(debug_assertions)]
+struct BlockingDetector<F> {
+    task: &'static str,
+    future: Pin<Box<F>>,
+    enabled: bool,
+}
+
+#[cfg(debug_assertions)]
+impl<F> BlockingDetector<F> {
+    fn new(task: &'static str, future: F, enabled: bool) -> Self {
+        Self {
+            task,
+            future: Box::pin(future),
+            enabled,
+        }
+    }
+}
+
+#[cfg(debug_assertions)]
+impl<F> Future for BlockingDetector<F>
+where
+    F: Future,
+{
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut std::task::Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+
+        let poll_start = Instant::now();
+        let poll = this.future.as_mut().poll(cx);
+        let poll_time = poll_start.elapsed();
+
+        if this.enabled && poll_time > BLOCKING_POLL_THRESHOLD {
+            this.enabled = false;
+
+            warn!("{}: Blocked the executor for {:?} without yielding, consider #[task(io)]",
+                this.task,
+                poll_time
+            );
+        }
+
+        poll
+    }
+}
+
+/// A task that emits items over time instead of returning a single output.
+/// It holds the subsystem read lock until the body returns, which closes the stream.
+#[async_trait]
+pub trait StreamTask: Task {
+    type Item: Send + 'static;
+
+    async fn execute(self, _subsystem: &Self::Subsystem, _items: TaskStream<Self::Item>) -> Self::Output;
+}
+
 #[async_trait]
 pub trait SubsystemMessage<S>: Send + 'static
 where
     S: Subsystem,
 {
     fn task(&self) -> &dyn TaskInfo;
-     
-    async fn execute(self: Box<Self>, subsystem: ArcLock<S>) -> Result<()>;
+
+    fn parent(&self) -> Option<&TaskScope>;
+
+    /// When the task was sent, to measure how long it waited in the queue.
+    fn sent(&self) -> Instant;
+
+    fn reject(self: Box<Self>, error: TitanError);
+
+    /// Runs the task and sends its result back, reporting whether it completed
+    /// and its output as formatted by `Task::display_output`.
+    async fn execute(self: Box<Self>, subsystem: ArcLock<S>) -> Result<Executed>;
+}
+
+/// How a task's execution went, for the task log.
+pub struct Executed {
+    /// False when the task panicked.
+    pub success: bool,
+    pub output: Option<String>,
+}
+
+/// Receives a task's result, the other end of its `ResultSender`.
+type TaskReceiver<T> = oneshot::Receiver<Result<T, TitanError>>;
+
+/// Sends a task's result back to its `TaskHandle`. If dropped while unwinding, e.g. when
+/// an `io` task's blocking thread panics outside the task body, it reports the panic
+/// instead of leaving the caller with a closed channel.
+struct ResultSender<T> {
+    task: &'static str,
+    sender: Option<oneshot::Sender<Result<T, TitanError>>>,
+}
+
+impl<T> ResultSender<T> {
+    fn send(mut self, result: Result<T, TitanError>) -> Result<(), Result<T, TitanError>> {
+        match self.sender.take() {
+            Some(sender) => sender.send(result),
+            None => Err(result),
+        }
+    }
+}
+
+impl<T> Drop for ResultSender<T> {
+    fn drop(&mut self) {
+        if let Some(sender) = self.sender.take() {
+            if std::thread::panicking() {
+                let _ = sender.send(Err(TitanError::TaskPanicked { task: self.task }));
+            }
+        }
+    }
 }
 
 struct ImmutableTaskMessage<T>
@@ -96,17 +305,21 @@ where
     T: ImmutableTask,
 {
     task: T,
-    sender: oneshot::Sender<T::Output>,
+    parent: Option<TaskScope>,
+    sent: Instant,
+    sender: ResultSender<T::Output>,
 }
 
 impl<T> ImmutableTaskMessage<T>
 where
     T: ImmutableTask
 {
-    pub fn from(task: T) -> (Box<dyn SubsystemMessage<T::Subsystem>>, oneshot::Receiver<T::Output>) {
+    pub fn from(task: T) -> (Box<dyn SubsystemMessage<T::Subsystem>>, TaskReceiver<T::Output>) {
         let (sender, receiver) = oneshot::channel();
         
-        let message = ImmutableTaskMessage { task, sender };
+        let sender = ResultSender { task: T::name(), sender: Some(sender) };
+
+        let message = ImmutableTaskMessage { task, parent: current_task(), sent: Instant::now(), sender };
         
         (Box::new(message), receiver)
     }
@@ -120,8 +333,20 @@ where
     fn task(&self) -> &dyn TaskInfo {
         &self.task
     }
+
+    fn parent(&self) -> Option<&TaskScope> {
+        self.parent.as_ref()
+    }
+
+    fn sent(&self) -> Instant {
+        self.sent
+    }
+
+    fn reject(self: Box<Self>, error: TitanError) {
+        let _ = self.sender.send(Err(error));
+    }
     
-    async fn execute(self: Box<Self>, subsystem: ArcLock<T::Subsystem>) -> Result<()> {
+    async fn execute(self: Box<Self>, subsystem: ArcLock<T::Subsystem>) -> Result<Executed> {
 
         let task_name = T::name();
         
@@ -138,12 +363,21 @@ where
         
         trace!("{}: Pre-Execute", &task_name);
         
-        let task_result = self.task.execute(&subsystem_ref)
-            .await;
+        let task_result = AssertUnwindSafe(self.task.execute(&subsystem_ref))
+            .catch_unwind()
+            .await
+            .map_err(|_| TitanError::TaskPanicked { task: task_name });
         
         trace!("{}: Post-Execute", &task_name);
         trace!("{}: Pre-Response", &task_name);
 
+        let success = task_result.is_ok();
+
+        let output_display = task_result
+            .as_ref()
+            .ok()
+            .and_then(T::display_output);
+
         let send_result = self.sender.send(task_result);
 
         if let Err(_err) = send_result {
@@ -152,7 +386,10 @@ where
 
         trace!("{}: Post-Response", &task_name);
         
-        Ok(())
+        Ok(Executed {
+            success,
+            output: output_display,
+        })
     }    
 }
 
@@ -162,17 +399,24 @@ where
     T: MutableTask,
 {
     task: T,
-    sender: oneshot::Sender<T::Output>,
+    parent: Option<TaskScope>,
+    sent: Instant,
+    ticket: Ticket,
+    /// `ptr_id` of the instance it was sent to.
+    instance: usize,
+    sender: ResultSender<T::Output>,
 }
 
 impl<T> MutableTaskMessage<T>
 where
     T: MutableTask
 {
-    pub fn from(task: T) -> (Box<dyn SubsystemMessage<T::Subsystem>>, oneshot::Receiver<T::Output>) {
+    pub fn from(task: T, ticket: Ticket, instance: usize) -> (Box<dyn SubsystemMessage<T::Subsystem>>, TaskReceiver<T::Output>) {
         let (sender, receiver) = oneshot::channel();
         
-        let message = MutableTaskMessage { task, sender };
+        let sender = ResultSender { task: T::name(), sender: Some(sender) };
+
+        let message = MutableTaskMessage { task, parent: current_task(), sent: Instant::now(), ticket, instance, sender };
         
         (Box::new(message), receiver)
     }
@@ -187,16 +431,35 @@ where
     fn task(&self) -> &dyn TaskInfo {
         &self.task
     }
+
+    fn parent(&self) -> Option<&TaskScope> {
+        self.parent.as_ref()
+    }
+
+    fn sent(&self) -> Instant {
+        self.sent
+    }
+
+    fn reject(self: Box<Self>, error: TitanError) {
+        let _ = self.sender.send(Err(error));
+    }
     
-    async fn execute(self: Box<Self>, subsystem: ArcLock<T::Subsystem>) -> Result<()> {
+    async fn execute(self: Box<Self>, subsystem: ArcLock<T::Subsystem>) -> Result<Executed> {
 
         let task_name = T::name();
         
         trace!("{}: Pre-WriteLock", &task_name);
+
+        // Mutable tasks are spawned concurrently, so the ticket puts them back in send
+        // order. The lock is fair, so once queued for it in order the ticket can go.
+        self.ticket.wait_turn()
+            .await;
         
         let mut subsystem_ref = subsystem.lock()
             .await;
 
+        drop(self.ticket);
+
         trace!("{}: Post-WriteLock", &task_name);
 
         // subsystem_ref.channels()
@@ -205,12 +468,24 @@ where
         
         trace!("{}: Pre-Execute", &task_name);
         
-        let task_result = self.task.execute(&mut subsystem_ref)
-            .await;
+        let execution = LOCKED_SUBSYSTEM
+            .scope(self.instance, self.task.execute(&mut subsystem_ref));
+
+        let task_result = AssertUnwindSafe(execution)
+            .catch_unwind()
+            .await
+            .map_err(|_| TitanError::TaskPanicked { task: task_name });
         
         trace!("{}: Post-Execute", &task_name);
         trace!("{}: Pre-Response", &task_name);
 
+        let success = task_result.is_ok();
+
+        let output_display = task_result
+            .as_ref()
+            .ok()
+            .and_then(T::display_output);
+
         let send_result = self.sender.send(task_result);
 
         if let Err(_err) = send_result {
@@ -219,42 +494,194 @@ where
         
         trace!("{}: Post-Response", &task_name);    
         
-        Ok(())
+        Ok(Executed {
+            success,
+            output: output_display,
+        })
     }    
 }
 
+struct StreamTaskMessage<T>
+where
+    T: StreamTask,
+{
+    task: T,
+    parent: Option<TaskScope>,
+    sent: Instant,
+    items: TaskStream<T::Item>,
+}
+
+impl<T> StreamTaskMessage<T>
+where
+    T: StreamTask
+{
+    pub fn from(task: T) -> (Box<dyn SubsystemMessage<T::Subsystem>>, mpsc::Receiver<T::Item>) {
+        let (items, receiver) = mpsc::channel(STREAM_BUFFER);
+
+        let message = StreamTaskMessage { task, parent: current_task(), sent: Instant::now(), items };
+
+        (Box::new(message), receiver)
+    }
+}
+
+#[async_trait]
+impl<T> SubsystemMessage<T::Subsystem> for StreamTaskMessage<T>
+where
+    T: StreamTask,
+{
+    fn task(&self) -> &dyn TaskInfo {
+        &self.task
+    }
+
+    fn parent(&self) -> Option<&TaskScope> {
+        self.parent.as_ref()
+    }
+
+    fn sent(&self) -> Instant {
+        self.sent
+    }
+
+    fn reject(self: Box<Self>, error: TitanError) {
+        // Dropping the sender closes the stream; the receiver only sees it end early.
+        debug!("{}", error);
+    }
+
+    async fn execute(self: Box<Self>, subsystem: ArcLock<T::Subsystem>) -> Result<Executed> {
+
+        let task_name = T::name();
+
+        trace!("{}: Pre-ReadLock", &task_name);
+
+        let subsystem_ref = subsystem.read()
+            .await;
+
+        trace!("{}: Post-ReadLock", &task_name);
+        trace!("{}: Pre-Execute", &task_name);
+
+        let task_result = AssertUnwindSafe(self.task.execute(&subsystem_ref, self.items))
+            .catch_unwind()
+            .await;
+
+        trace!("{}: Post-Execute", &task_name);
+
+        match task_result {
+            Ok(output) => Ok(Executed {
+                success: true,
+                output: T::display_output(&output),
+            }),
+            Err(_) => Err(anyhow::anyhow!(TitanError::TaskPanicked { task: task_name })),
+        }
+    }
+}
+
 pub struct TaskHandle<T>{
-    receiver: oneshot::Receiver<T>,
+    task: &'static str,
+    receiver: TaskReceiver<T>,
+    /// `ptr_id` and name of the subsystem a mutable task needs the write lock of.
+    locks: Option<(usize, &'static str)>,
+}
+
+impl<T> TaskHandle<T>
+where
+    T: Send + 'static
+{
+    /// Awaits the task result, failing with `TitanError::Timeout` once `duration` elapses.
+    pub async fn timeout(self, duration: Duration) -> Result<T, TitanError> {
+        let task = self.task;
+
+        tokio::time::timeout(duration, self)
+            .await
+            .map_err(|_| TitanError::Timeout { task })?
+    }
 }
 
 impl<T> Future for TaskHandle<T>
 where
     T: Send + 'static
 {
-    type Output = Result<T>;
+    type Output = Result<T, TitanError>;
 
     fn poll(
         self: Pin<&mut Self>,
         cx: &mut std::task::Context<'_>,
     ) -> std::task::Poll<Self::Output> {
         let this = self.get_mut();
+
+        // Awaiting a mutable task from one on the same subsystem waits on the lock the
+        // caller holds. The task still runs once the caller's lock is released.
+        if let Some((locks, subsystem)) = this.locks {
+            if LOCKED_SUBSYSTEM.try_with(|locked| *locked == locks).unwrap_or(false) {
+                return Poll::Ready(Err(TitanError::Reentrant { task: this.task, subsystem }));
+            }
+        }
+
         match Pin::new(&mut this.receiver).poll(cx) {
-            Poll::Ready(Ok(task_result)) => Poll::Ready(Ok(task_result)),
-            Poll::Ready(Err(err)) => Poll::Ready(Err(anyhow::anyhow!("Error retrieving task result: {}", err))),
+            Poll::Ready(Ok(task_result)) => Poll::Ready(task_result),
+            Poll::Ready(Err(_err)) => Poll::Ready(Err(TitanError::ChannelClosed { task: this.task })),
             Poll::Pending => Poll::Pending,
         }
     }
 }
 
-pub struct BatchHandle<T> {
-    handles: FuturesUnordered<TaskHandle<T>>,
+/// Resolves to the results of a batch of tasks, in the order they were sent.
+pub struct BatchHandle<T>
+where
+    T: Send + 'static,
+{
+    // Boxed so polling it doesn't need outputs to be `Unpin`.
+    handles: Pin<Box<FuturesOrdered<TaskHandle<T>>>>,
+    /// Results taken on earlier polls, kept until the whole batch is done.
+    results: Vec<Result<T, TitanError>>,
 }
 
-impl<T> BatchHandle<T> {
+// Nothing is ever pinned through a `BatchHandle`, the handles are boxed.
+impl<T> Unpin for BatchHandle<T>
+where
+    T: Send + 'static,
+{}
+
+impl<T> BatchHandle<T>
+where
+    T: Send + 'static,
+{
     pub fn new(handles: Vec<TaskHandle<T>>) -> Self {
         Self {
-            handles: handles.into_iter().collect(),
+            handles: Box::pin(handles.into_iter().collect()),
+            results: Vec::new(),
+        }
+    }
+
+    /// Every output in send order, or the first error in send order without waiting
+    /// for the tasks after it.
+    pub async fn try_all(self) -> Result<Vec<T>, TitanError> {
+        let BatchHandle { handles, results } = self;
+
+        let mut outputs = results
+            .into_iter()
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let remaining = handles
+            .try_collect::<Vec<_>>()
+            .await?;
+
+        outputs.extend(remaining);
+
+        Ok(outputs)
+    }
+
+    /// Waits for the whole batch and splits it into outputs and errors, each in send order.
+    pub async fn partition(self) -> (Vec<T>, Vec<anyhow::Error>) {
+        let mut outputs = Vec::new();
+        let mut errors = Vec::new();
+
+        for result in self.await {
+            match result {
+                Ok(output) => outputs.push(output),
+                Err(err) => errors.push(err.into()),
+            }
         }
+
+        (outputs, errors)
     }
 }
 
@@ -262,18 +689,17 @@ impl<T> Future for BatchHandle<T>
 where
     T: Send + 'static,
 {
-    type Output = Vec<Result<T>>;
+    type Output = Vec<Result<T, TitanError>>;
 
     fn poll(self: Pin<&mut Self>, cx: &mut std::task::Context<'_>) -> Poll<Self::Output> {
         let this = self.get_mut();
 
-        let mut results = Vec::new();
-        while let Poll::Ready(Some(result)) = Pin::new(&mut this.handles).poll_next(cx) {
-            results.push(result);
+        while let Poll::Ready(Some(result)) = this.handles.as_mut().poll_next(cx) {
+            this.results.push(result);
         }
 
         if this.handles.is_empty() {
-            Poll::Ready(results)
+            Poll::Ready(std::mem::take(&mut this.results))
         } else {
             Poll::Pending
         }
@@ -281,110 +707,325 @@ where
 }
  
 // Subsystem trait definition
+#[async_trait]
 pub trait Subsystem: Sized + Send + Sync + 'static {
     fn name() -> &'static str;
     
     fn channels(&self) -> Channels;
 
-    fn start_quiet<S>(subsystem: S, mut subsystem_receiver: SubsystemReceiver<S>)
+    /// Runs before the subsystem loop receives its first message. Errors are only logged,
+    /// so setup the caller has to know failed belongs in an explicit task instead.
+    async fn on_start(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    /// Runs once the subsystem loop has stopped receiving messages.
+    async fn on_stop(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    fn start_quiet<S>(subsystem: S, subsystem_receiver: SubsystemReceiver<S>)
     where
         S: Subsystem,
     {        
-        tokio::spawn(async move {
-            let subsystem_inst = ArcLock::new(subsystem);
-            let subsystem = subsystem_inst.clone();
-                            
-            while let Some(task_message) = subsystem_receiver.recv().await {
-                let subsystem = subsystem.clone();
-                let subsystem_name = S::name();
-                
-                trace!("{} - {}: Received", &subsystem_name, task_message.task().name());
-            
-                launch_task(subsystem, task_message, None);                
-            }
-
-            info!("Subsystem stopped!");
-        });
+        Self::start_with_handle(subsystem, subsystem_receiver, None);
     }
 
     fn start<S>(
         subsystem: S,
-        mut subsystem_receiver: SubsystemReceiver<S>,
+        subsystem_receiver: SubsystemReceiver<S>,
         tasks: SubsystemRef<TasksSubsystem>,
     ) 
     where
         S: Subsystem,
     {        
-        tokio::spawn(async move {
-            let subsystem = ArcLock::new(subsystem);
-            let subsystem = subsystem.clone();
-            
-            while let Some(task_message) = subsystem_receiver.recv().await {
-                let subsystem = subsystem.clone();
-                let subsystem_name = S::name();
-                let tasks = tasks.clone();
+        Self::start_with_handle(subsystem, subsystem_receiver, Some(tasks));
+    }
 
-                trace!("{} - {}: Received", &subsystem_name, task_message.task().name());
+    fn start_with_handle<S>(
+        subsystem: S,
+        mut subsystem_receiver: SubsystemReceiver<S>,
+        tasks: Option<SubsystemRef<TasksSubsystem>>,
+    ) -> SubsystemControl
+    where
+        S: Subsystem,
+    {
+        let (shutdown_sender, mut shutdown_receiver) = oneshot::channel();
+
+        let handle = tokio::spawn(async move {
+            let mut subsystem = subsystem;
 
-                launch_task(subsystem, task_message, Some(tasks));
+            if let Err(err) = subsystem.on_start().await {
+                error!("{}: Start error: {}", S::name(), err);
             }
 
-            info!("Subsystem stopped!");                
+            receive_loop(subsystem, &mut subsystem_receiver, &mut shutdown_receiver, tasks, false)
+                .await;
         });
+
+        SubsystemControl {
+            handle,
+            shutdown: Some(shutdown_sender),
+        }
     }
-}
 
-fn launch_task<S>(
-    subsystem: ArcLock<S>,
-    task_message: Box<dyn SubsystemMessage<S>>,    
-    tasks: Option<SubsystemRef<TasksSubsystem>>,
-)
-where
-    S: Subsystem
-{
-    let subsystem_name = S::name();
-    let task_name = task_message.task().name();
-    
-    match task_message.task().io() {
-        false => {
-            tokio::task::spawn(async move {
-                let exec_result = subsystem_run_task(subsystem, task_message, tasks)
-                    .await;
+    /// Starts a subsystem built by `factory`, building a fresh one whenever `on_start`
+    /// fails or panics, or one of its tasks panics, for as long as `policy` allows.
+    /// Once it gives up the receiver is dropped, so senders see `TitanError::ChannelClosed`.
+    fn start_supervised<S>(
+        factory: impl Fn() -> S + Send + Sync + 'static,
+        mut subsystem_receiver: SubsystemReceiver<S>,
+        tasks: Option<SubsystemRef<TasksSubsystem>>,
+        policy: RestartPolicy,
+    ) -> SubsystemControl
+    where
+        S: Subsystem,
+    {
+        let (shutdown_sender, mut shutdown_receiver) = oneshot::channel();
 
-                if let Err(err) = exec_result {
-                    error!("{} - {}: Execution error: {}",                            
-                        subsystem_name,
-                        task_name,
-                        err
-                    );
-                }
-            });
-        },
-        true => {
-            tokio::task::spawn_blocking(move || {
-                tokio::runtime::Handle::current()
-                    .block_on(async move {
-                        let exec_result = subsystem_run_task(subsystem, task_message, tasks)
+        let handle = tokio::spawn(async move {
+            let mut restarts = 0;
+
+            loop {
+                let start_result = AssertUnwindSafe(async {
+                    let mut subsystem = factory();
+
+                    subsystem.on_start()
+                        .await
+                        .map(|_| subsystem)
+                })
+                .catch_unwind()
+                .await;
+
+                let failure = match start_result {
+                    Ok(Ok(subsystem)) => {
+                        let panicked = receive_loop(subsystem, &mut subsystem_receiver, &mut shutdown_receiver, tasks.clone(), true)
                             .await;
 
-                        if let Err(err) = exec_result {
-                            error!("{} - {}: Execution error: {}",                            
-                                subsystem_name,
-                                task_name,
-                                err
-                            );
+                        match panicked {
+                            Some(task) => format!("{} panicked", task),
+                            None => break,
                         }
-                    });
-            });
-        },
-    };
+                    },
+                    Ok(Err(err)) => format!("Start failed: {}", err),
+                    Err(_) => String::from("Start panicked"),
+                };
+
+                if !policy.allows(restarts) {
+                    error!("{}: {}, giving up after {} restarts", S::name(), failure, restarts);
+                    break;
+                }
+
+                restarts += 1;
+
+                warn!("{}: {}, restarting ({})", S::name(), failure, restarts);
+            }
+        });
+
+        SubsystemControl {
+            handle,
+            shutdown: Some(shutdown_sender),
+        }
+    }
 }
 
-async fn subsystem_run_task<S>(
-    subsystem: ArcLock<S>,
-    task_message: Box<dyn SubsystemMessage<S>>,    
+/// Whether `Subsystem::start_supervised` rebuilds a subsystem that failed to start or
+/// had a task panic.
+#[derive(Clone, Copy, Debug)]
+pub enum RestartPolicy {
+    Never,
+    Always,
+    /// Restarts at most this many times.
+    UpTo(usize),
+}
+
+impl RestartPolicy {
+    fn allows(&self, restarts: usize) -> bool {
+        match self {
+            RestartPolicy::Never => false,
+            RestartPolicy::Always => true,
+            RestartPolicy::UpTo(max_restarts) => restarts < *max_restarts,
+        }
+    }
+}
+
+/// Receives and launches tasks until the queue closes or shutdown is requested. With
+/// `stop_on_panic` a panicking task stops it too, returning that task's name.
+async fn receive_loop<S>(
+    subsystem: S,
+    subsystem_receiver: &mut SubsystemReceiver<S>,
+    shutdown_receiver: &mut oneshot::Receiver<()>,
+    tasks: Option<SubsystemRef<TasksSubsystem>>,
+    stop_on_panic: bool,
+) -> Option<&'static str>
+where
+    S: Subsystem,
+{
+    let subsystem = ArcLock::new(subsystem);
+
+    // Read once per loop rather than on every dispatch.
+    let max_depth = match &tasks {
+        Some(tasks) => tasks.send(tasks::MaxTaskDepth)
+            .await
+            .unwrap_or(tasks::DEFAULT_MAX_TASK_DEPTH),
+        None => tasks::DEFAULT_MAX_TASK_DEPTH,
+    };
+
+    // Per loop, so tasks still running on a replaced subsystem can't stop the next one.
+    let (panic_sender, mut panic_receiver) = mpsc::unbounded_channel();
+    let panic_sender = stop_on_panic.then_some(panic_sender);
+
+    let mut panicked = None;
+
+    loop {
+        // A dropped control disables the shutdown branch instead of stopping the loop.
+        let task_message = tokio::select! {
+            task_message = subsystem_receiver.recv() => task_message,
+            Ok(()) = &mut *shutdown_receiver => None,
+            Some(task) = panic_receiver.recv(), if stop_on_panic => {
+                panicked = Some(task);
+                None
+            },
+        };
+
+        let Some(task_message) = task_message else {
+            break;
+        };
+
+        let subsystem = subsystem.clone();
+        let subsystem_name = S::name();
+        let tasks = tasks.clone();
+
+        trace!("{} - {}: Received", &subsystem_name, task_message.task().name());
+
+        launch_task(subsystem, task_message, tasks, max_depth, panic_sender.clone());
+    }
+
+    if let Err(err) = subsystem.lock().await.on_stop().await {
+        error!("{}: Stop error: {}", S::name(), err);
+    }
+
+    info!("Subsystem stopped!");
+
+    panicked
+}
+
+/// Control over a running subsystem loop. Awaiting it waits for the loop to exit.
+pub struct SubsystemControl {
+    handle: JoinHandle<()>,
+    shutdown: Option<oneshot::Sender<()>>,
+}
+
+impl SubsystemControl {
+    /// Stops the loop from receiving further messages. Tasks already launched run to completion.
+    pub fn shutdown(&mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(());
+        }
+    }
+
+    /// Aborts the loop immediately.
+    pub fn abort(&self) {
+        self.handle.abort();
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.handle.is_finished()
+    }
+}
+
+impl Future for SubsystemControl {
+    type Output = Result<()>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut std::task::Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        match Pin::new(&mut this.handle).poll(cx) {
+            Poll::Ready(Ok(())) => Poll::Ready(Ok(())),
+            Poll::Ready(Err(err)) if err.is_cancelled() => Poll::Ready(Ok(())),
+            Poll::Ready(Err(err)) => Poll::Ready(Err(anyhow::anyhow!("Subsystem loop failed: {}", err))),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+/// Runs a task on the runtime, or the blocking pool for `io` tasks. If it panics, its
+/// name goes to `panics` when the loop restarts on panics.
+fn launch_task<S>(
+    subsystem: ArcLock<S>,
+    task_message: Box<dyn SubsystemMessage<S>>,    
+    tasks: Option<SubsystemRef<TasksSubsystem>>,
+    max_depth: usize,
+    panics: Option<mpsc::UnboundedSender<&'static str>>,
+)
+where
+    S: Subsystem
+{
+    let subsystem_name = S::name();
+    let task_name = task_message.task().name();
+    
+    match task_message.task().io() {
+        false => {
+            tokio::task::spawn(async move {
+                let exec_result = subsystem_run_task(subsystem, task_message, tasks, max_depth)
+                    .await;
+
+                report_execution(subsystem_name, task_name, exec_result, panics);
+            });
+        },
+        true => {
+            let blocking_handle = tokio::task::spawn_blocking(move || {
+                tokio::runtime::Handle::current()
+                    .block_on(async move {
+                        let exec_result = subsystem_run_task(subsystem, task_message, tasks, max_depth)
+                            .await;
+
+                        report_execution(subsystem_name, task_name, exec_result, panics);
+                    });
+            });
+
+            // The caller already hears about a panic through `ResultSender`; this keeps
+            // the blocking thread's own failure from going unnoticed.
+            tokio::task::spawn(async move {
+                if let Err(err) = blocking_handle.await {
+                    error!("{} - {}: Blocking thread failed: {}",
+                        subsystem_name,
+                        task_name,
+                        err
+                    );
+                }
+            });
+        },
+    };
+}
+
+fn report_execution(
+    subsystem_name: &'static str,
+    task_name: &'static str,
+    exec_result: Result<bool>,
+    panics: Option<mpsc::UnboundedSender<&'static str>>,
+) {
+    match exec_result {
+        Ok(true) => {},
+        Ok(false) => {
+            if let Some(panics) = panics {
+                let _ = panics.send(task_name);
+            }
+        },
+        Err(err) => error!("{} - {}: Execution error: {}",
+            subsystem_name,
+            task_name,
+            err
+        ),
+    }
+}
+
+/// Returns whether the task ran without panicking.
+async fn subsystem_run_task<S>(
+    subsystem: ArcLock<S>,
+    task_message: Box<dyn SubsystemMessage<S>>,    
     tasks: Option<SubsystemRef<TasksSubsystem>>,
-) -> Result<()>
+    max_depth: usize,
+) -> Result<bool>
 where
     S: Subsystem,
 {
@@ -392,15 +1033,37 @@ where
     let task_name = task_message.task().name();
     let task_logs = task_message.task().log();
     let task_benchmarks = task_message.task().benchmark();
+    let task_warn_after = task_message.task().warn_after();
+    #[cfg(debug_assertions)]
+    let task_io = task_message.task().io();
+    let task_parent = task_message.parent()
+        .map(|parent| parent.id.clone());
+    let task_depth = task_message.parent()
+        .map_or(0, |parent| parent.depth + 1);
+    let task_correlation = task_message.parent()
+        .map_or(task_id.clone(), |parent| parent.correlation.clone());
 
     let time_start = Instant::now();
+    let queue_latency = time_start
+        .duration_since(task_message.sent())
+        .as_secs_f64();
+
+    // Checked before anything is logged, so quiet and benchmarked tasks can't recurse without bound either.
+    if task_depth > max_depth {
+        let err = TitanError::DepthExceeded { task: task_name, depth: task_depth };
+        task_message.reject(err.clone());
+        return Err(err.into());
+    }
 
     if let Some(tasks) = tasks.as_ref() {
         if task_logs && !task_benchmarks {
             tasks.send(tasks::StartTask {
                 id: task_id.clone(),
                 name: task_name,
-                depth: 0,
+                depth: task_depth,
+                parent: task_parent,
+                correlation: task_correlation.clone(),
+                queue_latency,
             })
             .await?;
         }
@@ -408,51 +1071,291 @@ where
         if task_benchmarks {
             tasks.send(tasks::StartBenchmark {
                 name: task_name,
+                group: task_message.task().group(),
             })
             .await?;
         }
     }
 
-    task_message.execute(subsystem)
+    if let Err(err) = task_message.task().validate() {
+        task_message.reject(TitanError::InvalidInput {
+            task: task_name,
+            reason: err.to_string(),
+        });
+
+        if let Some(tasks) = tasks.as_ref() {
+            if task_logs && !task_benchmarks {
+                tasks.send(tasks::EndTask::from(tasks::TaskEnd {
+                    id: task_id,
+                    name: task_name,
+                    end: time_start.elapsed().as_secs_f64(),
+                    output: None,
+                    display: |_| "Invalid input".to_string(),
+                    subsystem: S::name(),
+                    success: false,
+                }));
+            }
+
+            // `StartBenchmark` was already sent, so the run has to be closed.
+            if task_benchmarks {
+                tasks.send(tasks::EndBenchmark {
+                    name: task_name,
+                    end: time_start.elapsed().as_secs_f64(),
+                })
+                .await?;
+            }
+        }
+
+        return Err(anyhow::anyhow!("{}: Input validation failed: {}", task_name, err));
+    }
+
+    let task_scope = TaskScope {
+        id: task_id.clone(),
+        depth: task_depth,
+        correlation: task_correlation.clone(),
+    };
+
+    // Task, subsystem and correlation become structured fields on everything logged during the task.
+    let task_span = info_span!("task",
+        task = task_name,
+        subsystem = S::name(),
+        correlation = task_correlation.as_str()
+    );
+
+    let execution = CURRENT_TASK
+        .scope(task_scope, task_message.execute(subsystem))
+        .instrument(task_span);
+
+    // `io` tasks are on a blocking thread already, and benchmarks are expected to be busy.
+    #[cfg(debug_assertions)]
+    let execution = BlockingDetector::new(task_name, execution, !task_io && !task_benchmarks);
+
+    let executed = execution
         .await?;
 
+    if let Some(warn_after) = task_warn_after {
+        let task_duration = time_start.elapsed();
+
+        if task_duration > warn_after {
+            warn!("{}: Took {:?}, expected under {:?}", task_name, task_duration, warn_after);
+        }
+    }
+
     if let Some(tasks) = tasks.as_ref() {
         if task_logs && !task_benchmarks {
-            tasks.send(tasks::EndTask {
+            tasks.send(tasks::EndTask::from(tasks::TaskEnd {
                 id: task_id,
+                name: task_name,
                 end: time_start.elapsed().as_secs_f64(),
-                display: |task| chrono::format_duration(&task.duration),
-            });
+                output: executed.output,
+                display: tasks::format_task,
+                subsystem: S::name(),
+                success: executed.success,
+            }));
         }
 
         if task_benchmarks {
             tasks.send(tasks::EndBenchmark {
                 name: task_name,
                 end: time_start.elapsed().as_secs_f64(),
-                display: |bench| {
-                    format!("{} ~ [{}] <=> [{} - {}]",
-                        &chrono::format_duration(&bench.duration),
-                        &chrono::format_duration(&bench.average),
-                        &chrono::format_duration(&bench.min),
-                        &chrono::format_duration(&bench.max)
-                    )
-                },
             })
             .await?;
         }
     }
 
-    Ok(())
+    Ok(executed.success)
+}
+
+fn block_on_handle<T>(task_handle: TaskHandle<T>) -> Result<T>
+where
+    T: Send + 'static,
+{
+    let runtime = Handle::try_current()
+        .map_err(|_| anyhow::anyhow!("{}: send_sync called outside of a tokio runtime", task_handle.task))?;
+
+    if runtime.runtime_flavor() != RuntimeFlavor::MultiThread {
+        return Err(anyhow::anyhow!("{}: send_sync requires a multi-threaded runtime", task_handle.task));
+    }
+
+    let task_result = tokio::task::block_in_place(|| runtime.block_on(task_handle))?;
+
+    Ok(task_result)
+}
+
+/// Queue bookkeeping shared by a subsystem's refs and its receiver.
+#[derive(Default)]
+struct QueueState {
+    pending: AtomicUsize,
+    /// Names of idempotent tasks sent but not yet taken by the subsystem loop.
+    idempotent: Mutex<HashSet<&'static str>>,
+    tickets: Arc<TicketLock>,
+}
+
+/// Admits mutable tasks to the subsystem write lock in the order they were sent.
+/// Tickets are numbered at send time and served in order; a ticket dropped before its
+/// turn, e.g. with a rejected message, is skipped once the tickets ahead of it are done.
+#[derive(Default)]
+struct TicketLock {
+    next: AtomicU64,
+    state: Mutex<TicketState>,
+    notify: Notify,
+}
+
+#[derive(Default)]
+struct TicketState {
+    serving: u64,
+    /// Tickets released ahead of their turn.
+    released: BTreeSet<u64>,
+}
+
+impl TicketLock {
+    fn take(self: &Arc<Self>) -> Ticket {
+        Ticket {
+            number: self.next.fetch_add(1, Ordering::SeqCst),
+            lock: self.clone(),
+        }
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, TicketState> {
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn release(&self, number: u64) {
+        let mut state_lock = self.state();
+        let state = &mut *state_lock;
+
+        match state.serving == number {
+            true => {
+                state.serving += 1;
+
+                while state.released.remove(&state.serving) {
+                    state.serving += 1;
+                }
+            },
+            false => {
+                state.released.insert(number);
+            },
+        }
+
+        drop(state_lock);
+
+        self.notify.notify_waiters();
+    }
+}
+
+/// A mutable task's place in line, released when dropped.
+struct Ticket {
+    number: u64,
+    lock: Arc<TicketLock>,
+}
+
+impl Ticket {
+    async fn wait_turn(&self) {
+        loop {
+            // Created before checking, so a release in between still wakes it.
+            let notified = self.lock.notify.notified();
+
+            if self.lock.state().serving == self.number {
+                return;
+            }
+
+            notified.await;
+        }
+    }
+}
+
+impl Drop for Ticket {
+    fn drop(&mut self) {
+        self.lock.release(self.number);
+    }
+}
+
+impl QueueState {
+    fn idempotent_lock(&self) -> std::sync::MutexGuard<'_, HashSet<&'static str>> {
+        self.idempotent
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Receiving end of a subsystem's queue, counting messages down as they're taken.
+pub struct SubsystemReceiver<S>
+where
+    S: Subsystem,
+{
+    receiver: mpsc::UnboundedReceiver<Box<dyn SubsystemMessage<S>>>,
+    queue: Arc<QueueState>,
+}
+
+impl<S> SubsystemReceiver<S>
+where
+    S: Subsystem,
+{
+    pub async fn recv(&mut self) -> Option<Box<dyn SubsystemMessage<S>>> {
+        let message = self.receiver
+            .recv()
+            .await;
+
+        if let Some(message) = &message {
+            self.queue.pending.fetch_sub(1, Ordering::Relaxed);
+
+            // Once taken, an idempotent task counts as executing, so the next one queues again.
+            if message.task().idempotent() {
+                self.queue
+                    .idempotent_lock()
+                    .remove(message.task().name());
+            }
+        }
+
+        message
+    }
 }
 
-pub type SubsystemReceiver<S> = mpsc::UnboundedReceiver<Box<dyn SubsystemMessage<S>>>;
 pub type SubsystemSender<S> = mpsc::UnboundedSender<Box<dyn SubsystemMessage<S>>>;
 
+pub type WeakSubsystemSender<S> = mpsc::WeakUnboundedSender<Box<dyn SubsystemMessage<S>>>;
+
 pub struct SubsystemRef<S>
 where
     S: Subsystem,
 {
     sender: SubsystemSender<S>,
+    queue: Arc<QueueState>,
+}
+
+/// A `SubsystemRef` that doesn't keep the subsystem loop alive.
+pub struct WeakSubsystemRef<S>
+where
+    S: Subsystem,
+{
+    sender: WeakSubsystemSender<S>,
+    queue: Arc<QueueState>,
+}
+
+impl<S> Clone for WeakSubsystemRef<S>
+where
+    S: Subsystem,
+{
+    fn clone(&self) -> Self {
+        WeakSubsystemRef {
+            sender: self.sender.clone(),
+            queue: self.queue.clone(),
+        }
+    }
+}
+
+impl<S> WeakSubsystemRef<S>
+where
+    S: Subsystem,
+{
+    /// Returns `None` once every strong `SubsystemRef` has been dropped.
+    pub fn upgrade(&self) -> Option<SubsystemRef<S>> {
+        self.sender
+            .upgrade()
+            .map(|sender| SubsystemRef { sender, queue: self.queue.clone() })
+    }
 }
 
 impl<S> Clone for SubsystemRef<S>
@@ -462,19 +1365,81 @@ where
     fn clone(&self) -> Self {
         SubsystemRef {
             sender: self.sender.clone(),
+            queue: self.queue.clone(),
         }
     }
 }
 
+/// Refs are equal when they send to the same subsystem, e.g. one is a clone of the other.
+impl<S> PartialEq for SubsystemRef<S>
+where
+    S: Subsystem,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.sender.same_channel(&other.sender)
+    }
+}
+
+impl<S> Eq for SubsystemRef<S>
+where
+    S: Subsystem,
+{}
+
 impl<S> SubsystemRef<S>
 where
     S: Subsystem,
 {
     pub fn new() -> (Self, SubsystemReceiver<S>) {
         let (sender, receiver) = mpsc::unbounded_channel();
-        let subsystem_ref = SubsystemRef { sender };
+        let queue = Arc::new(QueueState::default());
+
+        let subsystem_ref = SubsystemRef { sender, queue: queue.clone() };
+        let subsystem_receiver = SubsystemReceiver { receiver, queue };
+
+        (subsystem_ref, subsystem_receiver)
+    }
+
+    /// Identifies the subsystem this ref sends to, shared by all of its clones and
+    /// upgraded weak refs. Only unique while the subsystem's channel is alive.
+    pub fn ptr_id(&self) -> usize {
+        Arc::as_ptr(&self.queue) as usize
+    }
+
+    pub fn downgrade(&self) -> WeakSubsystemRef<S> {
+        WeakSubsystemRef {
+            sender: self.sender.downgrade(),
+            queue: self.queue.clone(),
+        }
+    }
+
+    /// Messages sent to the subsystem that its loop hasn't picked up yet.
+    pub fn pending_len(&self) -> usize {
+        self.queue.pending.load(Ordering::Relaxed)
+    }
+
+    fn enqueue(&self, message: Box<dyn SubsystemMessage<S>>) -> Result<(), mpsc::error::SendError<Box<dyn SubsystemMessage<S>>>> {
+        let task_name = message.task().name();
+        let idempotent = message.task().idempotent();
+
+        if idempotent && !self.queue.idempotent_lock().insert(task_name) {
+            trace!("{}: Coalesced with a queued task", task_name);
+            message.reject(TitanError::Coalesced { task: task_name });
+            return Ok(());
+        }
+
+        self.queue.pending.fetch_add(1, Ordering::Relaxed);
+
+        self.sender
+            .send(message)
+            .inspect_err(|_| {
+                self.queue.pending.fetch_sub(1, Ordering::Relaxed);
 
-        (subsystem_ref, receiver)
+                if idempotent {
+                    self.queue
+                        .idempotent_lock()
+                        .remove(task_name);
+                }
+            })
     }
 
     pub fn send<T>(&self, task: T) -> TaskHandle<T::Output>
@@ -486,7 +1451,7 @@ where
 
         trace!("{}: Sender Pre-Send", &task_name);
 
-        let send_res = self.sender.send(task_message);
+        let send_res = self.enqueue(task_message);
 
         if let Err(err) = send_res {
             let subsystem_name = S::name();
@@ -500,7 +1465,7 @@ where
 
         trace!("{}: Sender Post-Send", &task_name);
 
-        TaskHandle { receiver: task_receiver }
+        TaskHandle { task: task_name, receiver: task_receiver, locks: None }
     }
 
     
@@ -508,12 +1473,12 @@ where
     where
         T: MutableTask<Subsystem = S>,
     {
-        let (mut_task_message, mut_task_receiver) = MutableTaskMessage::from(task);
+        let (mut_task_message, mut_task_receiver) = MutableTaskMessage::from(task, self.queue.tickets.take(), self.ptr_id());
         let mut_task_name = mut_task_message.task().name();
         
         trace!("{}: Sender Pre-Send", &mut_task_name);
 
-        let send_res = self.sender.send(mut_task_message);
+        let send_res = self.enqueue(mut_task_message);
 
         if let Err(err) = send_res {
             let subsystem_name = S::name();
@@ -527,44 +1492,1353 @@ where
 
         trace!("{}: Sender Post-Send", &mut_task_name);
 
-        TaskHandle { receiver: mut_task_receiver }
+        TaskHandle { task: mut_task_name, receiver: mut_task_receiver, locks: Some((self.ptr_id(), S::name())) }
     }
 
-    pub fn send_batch<T>(&self, tasks: Vec<T>) -> BatchHandle<T::Output>
+    /// Dispatches a task and blocks the current thread until it completes, for
+    /// synchronous callbacks that can't `.await`. Requires a multi-threaded runtime.
+    pub fn send_sync<T>(&self, task: T) -> Result<T::Output>
     where
         T: ImmutableTask<Subsystem = S>,
     {
-        let handles = tasks
-            .into_iter()
-            .map(|task| self.send(task))
-            .collect();
+        block_on_handle(self.send(task))
+    }
 
-        BatchHandle::new(handles)
+    /// Mutable variant of `send_sync`.
+    pub fn send_mut_sync<T>(&self, task: T) -> Result<T::Output>
+    where
+        T: MutableTask<Subsystem = S>,
+    {
+        block_on_handle(self.send_mut(task))
     }
 
+    /// `send` for anything that converts into the task. Every task converts from its
+    /// inputs, so `send_into::<tasks::GetRecentLogs, _>(10)` works without naming fields.
+    pub fn send_into<T, U>(&self, task: U) -> TaskHandle<T::Output>
+    where
+        T: ImmutableTask<Subsystem = S>,
+        U: Into<T>,
+    {
+        self.send(task.into())
+    }
 
-    pub fn send_batch_mut<T>(&self, tasks: Vec<T>) -> BatchHandle<T::Output>
+    /// Mutable variant of `send_into`.
+    pub fn send_mut_into<T, U>(&self, task: U) -> TaskHandle<T::Output>
     where
         T: MutableTask<Subsystem = S>,
+        U: Into<T>,
     {
-        let handles = tasks
-            .into_iter()
-            .map(|task| self.send_mut(task))
-            .collect();
+        self.send_mut(task.into())
+    }
 
-        BatchHandle::new(handles)
+    /// Sends `task` and calls `callback` with its result once it completes, without
+    /// holding on to the handle. Outside a runtime the callback gets `TitanError::NoRuntime`.
+    pub fn send_then<T>(&self, task: T, callback: impl FnOnce(Result<T::Output, TitanError>) + Send + 'static)
+    where
+        T: ImmutableTask<Subsystem = S>,
+    {
+        Self::then(T::name(), self.send(task), callback);
     }
-}
 
-pub trait ErasedSubsystemRef: Send + Sync {    
-    fn as_any(&self) -> &dyn Any; 
-}
+    /// Mutable variant of `send_then`.
+    pub fn send_mut_then<T>(&self, task: T, callback: impl FnOnce(Result<T::Output, TitanError>) + Send + 'static)
+    where
+        T: MutableTask<Subsystem = S>,
+    {
+        Self::then(T::name(), self.send_mut(task), callback);
+    }
 
-impl<S> ErasedSubsystemRef for SubsystemRef<S>
-where
-    S: Subsystem,
-{
-    fn as_any(&self) -> &dyn Any {
-        self
+    fn then<O>(task: &'static str, handle: TaskHandle<O>, callback: impl FnOnce(Result<O, TitanError>) + Send + 'static)
+    where
+        O: Send + 'static,
+    {
+        let Ok(runtime) = Handle::try_current() else {
+            callback(Err(TitanError::NoRuntime { task }));
+            return;
+        };
+
+        runtime.spawn(async move {
+            callback(handle.await);
+        });
+    }
+
+    /// Like `send`, but fails with `TitanError::ChannelClosed` when the subsystem
+    /// loop is gone instead of only logging it.
+    pub fn try_send<T>(&self, task: T) -> Result<TaskHandle<T::Output>, TitanError>
+    where
+        T: ImmutableTask<Subsystem = S>,
+    {
+        let (task_message, task_receiver) = ImmutableTaskMessage::from(task);
+        let task_name = task_message.task().name();
+
+        self.enqueue(task_message)
+            .map_err(|_| TitanError::ChannelClosed { task: task_name })?;
+
+        Ok(TaskHandle { task: task_name, receiver: task_receiver, locks: None })
+    }
+
+    /// Mutable variant of `try_send`.
+    pub fn try_send_mut<T>(&self, task: T) -> Result<TaskHandle<T::Output>, TitanError>
+    where
+        T: MutableTask<Subsystem = S>,
+    {
+        let (mut_task_message, mut_task_receiver) = MutableTaskMessage::from(task, self.queue.tickets.take(), self.ptr_id());
+        let mut_task_name = mut_task_message.task().name();
+
+        self.enqueue(mut_task_message)
+            .map_err(|_| TitanError::ChannelClosed { task: mut_task_name })?;
+
+        Ok(TaskHandle { task: mut_task_name, receiver: mut_task_receiver, locks: Some((self.ptr_id(), S::name())) })
+    }
+
+    /// Dispatches a streaming task, returning a receiver that yields its items
+    /// and closes once the task finishes.
+    pub fn send_stream<T>(&self, task: T) -> mpsc::Receiver<T::Item>
+    where
+        T: StreamTask<Subsystem = S>,
+    {
+        let (stream_task_message, stream_receiver) = StreamTaskMessage::from(task);
+        let stream_task_name = stream_task_message.task().name();
+
+        trace!("{}: Sender Pre-Send", &stream_task_name);
+
+        let send_res = self.enqueue(stream_task_message);
+
+        if let Err(err) = send_res {
+            let subsystem_name = S::name();
+
+            debug!("Failed to send task {} to subsystem {:?}: {}",
+                stream_task_name,
+                subsystem_name,
+                err
+            );
+        }
+
+        trace!("{}: Sender Post-Send", &stream_task_name);
+
+        stream_receiver
+    }
+
+    pub fn send_batch<T>(&self, tasks: Vec<T>) -> BatchHandle<T::Output>
+    where
+        T: ImmutableTask<Subsystem = S>,
+    {
+        let handles = tasks
+            .into_iter()
+            .map(|task| self.send(task))
+            .collect();
+
+        BatchHandle::new(handles)
+    }
+
+
+    pub fn send_batch_mut<T>(&self, tasks: Vec<T>) -> BatchHandle<T::Output>
+    where
+        T: MutableTask<Subsystem = S>,
+    {
+        let handles = tasks
+            .into_iter()
+            .map(|task| self.send_mut(task))
+            .collect();
+
+        BatchHandle::new(handles)
+    }
+}
+
+pub trait ErasedSubsystemRef: Send + Sync {    
+    fn as_any(&self) -> &dyn Any; 
+    fn name(&self) -> &'static str;
+    fn pending_len(&self) -> usize;
+}
+
+impl<S> ErasedSubsystemRef for SubsystemRef<S>
+where
+    S: Subsystem,
+{
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn name(&self) -> &'static str {
+        S::name()
+    }
+
+    fn pending_len(&self) -> usize {
+        self.queue.pending.load(Ordering::Relaxed)
+    }
+}
+
+impl<S> ErasedSubsystemRef for WeakSubsystemRef<S>
+where
+    S: Subsystem,
+{
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+    fn name(&self) -> &'static str {
+        S::name()
+    }
+
+    fn pending_len(&self) -> usize {
+        self.queue.pending.load(Ordering::Relaxed)
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::logs::LogHistory;
+    use indexmap::IndexMap;
+    use std::collections::{HashMap, VecDeque};
+    use tracing_subscriber::layer::SubscriberExt;
+
+    /// Shared with the `Channels` tests.
+    pub struct TestSubsystem {
+        pub channels: Channels,
+        /// Runs of `tally`.
+        pub tallies: AtomicUsize,
+        /// Values applied by `record`, in the order it ran.
+        pub recorded: Vec<u32>,
+    }
+
+    impl TestSubsystem {
+        pub fn new(channels: Channels) -> Self {
+            Self {
+                channels,
+                tallies: AtomicUsize::new(0),
+                recorded: Vec::new(),
+            }
+        }
+    }
+
+    #[crate::subsystem]
+    impl TestSubsystem {
+
+        #[crate::task]
+        async fn outer(&self) -> Result<Option<TaskScope>, TitanError> {
+            self.channels
+                .get::<TestSubsystem>()
+                .send(Inner)
+                .await
+        }
+
+        #[crate::task]
+        async fn inner(&self) -> Option<TaskScope> {
+            current_task()
+        }
+
+        #[crate::task(validate = positive)]
+        fn double(&self, value: i64) -> i64 {
+            value * 2
+        }
+
+        fn positive(value: &i64) -> Result<()> {
+            match *value > 0 {
+                true => Ok(()),
+                false => Err(anyhow::anyhow!("{} isn't positive", value)),
+            }
+        }
+
+        /// Emits `0..count`.
+        #[crate::task(stream)]
+        async fn count(&self, count: u32, items: TaskStream<u32>) {
+            for item in 0..count {
+                if items.send(item).await.is_err() {
+                    break;
+                }
+            }
+        }
+
+        #[crate::task]
+        fn tally(&self) {
+            self.tallies.fetch_add(1, Ordering::SeqCst);
+        }
+
+        /// Squares `value`, tallying every time the body actually runs.
+        #[crate::task(cache)]
+        fn square(&self, value: i64) -> i64 {
+            self.tallies.fetch_add(1, Ordering::SeqCst);
+            value * value
+        }
+
+        /// `tally`, coalesced while one is queued.
+        #[crate::task(idempotent)]
+        fn mark(&self) {
+            self.tallies.fetch_add(1, Ordering::SeqCst);
+        }
+
+        #[crate::task(display_output)]
+        fn tallies(&self) -> usize {
+            self.tallies.load(Ordering::SeqCst)
+        }
+
+        #[crate::task(validate = below_limit)]
+        async fn record(&mut self, value: u32) {
+            self.recorded.push(value);
+        }
+
+        fn below_limit(value: &u32) -> Result<()> {
+            match *value < 1000 {
+                true => Ok(()),
+                false => Err(anyhow::anyhow!("{} is over the limit", value)),
+            }
+        }
+
+        /// Awaits `record` on the instance registered as `name`, `""` for the unnamed
+        /// one, while holding this instance's write lock.
+        #[crate::task]
+        async fn reenter(&mut self, name: String, value: u32) -> Result<(), TitanError> {
+            self.channels
+                .get_named::<TestSubsystem>(&name)
+                .send_mut(Record { value })
+                .await
+        }
+
+        #[crate::task]
+        fn recorded(&self) -> Vec<u32> {
+            self.recorded.clone()
+        }
+
+        #[crate::task]
+        async fn fail(&self) {
+            panic!("Fail always panics");
+        }
+
+        /// Panics on the blocking pool rather than a runtime thread.
+        #[crate::task(io)]
+        fn crash(&self) {
+            panic!("Crash always panics");
+        }
+
+        #[crate::task(cost = 5, group = "heavy")]
+        fn heavy(&self) {}
+
+        /// Dispatches itself until that's rejected, returning the rejection.
+        #[crate::task]
+        async fn recurse(&self) -> TitanError {
+            let result = self.channels
+                .get::<TestSubsystem>()
+                .send(Recurse)
+                .await;
+
+            match result {
+                Ok(err) | Err(err) => err,
+            }
+        }
+
+        /// Published for `nap` and `doze`, which sleep for `millis` each.
+        #[crate::task]
+        async fn wake(&self, millis: u64) -> u64 {
+            millis
+        }
+
+        #[crate::task]
+        async fn nap(&self, millis: u64) {
+            tokio::time::sleep(Duration::from_millis(millis))
+                .await;
+        }
+
+        #[crate::task]
+        async fn doze(&self, millis: u64) {
+            tokio::time::sleep(Duration::from_millis(millis))
+                .await;
+        }
+
+        /// Holds its thread for `millis` without yielding.
+        #[crate::task]
+        fn spin(&self, millis: u64) {
+            std::thread::sleep(Duration::from_millis(millis));
+        }
+
+        /// `spin` on the blocking pool.
+        #[crate::task(io)]
+        fn grind(&self, millis: u64) {
+            std::thread::sleep(Duration::from_millis(millis));
+        }
+
+        #[crate::task(warn_after = "10ms")]
+        async fn linger(&self, millis: u64) {
+            tokio::time::sleep(Duration::from_millis(millis))
+                .await;
+        }
+    }
+
+    /// Records its lifecycle hooks as they run.
+    pub struct HookedSubsystem {
+        channels: Channels,
+        hooks: Arc<Mutex<Vec<&'static str>>>,
+    }
+
+    #[crate::subsystem]
+    impl HookedSubsystem {
+
+        async fn on_start(&mut self) -> Result<()> {
+            self.hooks.lock().unwrap().push("on_start");
+            Ok(())
+        }
+
+        async fn on_stop(&mut self) -> Result<()> {
+            self.hooks.lock().unwrap().push("on_stop");
+            Ok(())
+        }
+
+        #[crate::task]
+        fn hooks(&self) -> Vec<&'static str> {
+            self.hooks.lock().unwrap().clone()
+        }
+    }
+
+    /// Fails `on_start` while `failures` is above zero, counting it down each time.
+    pub struct FlakySubsystem {
+        channels: Channels,
+        failures: Arc<AtomicUsize>,
+        /// Like `failures`, but `on_start` panics instead of returning an error.
+        panics: Arc<AtomicUsize>,
+        starts: Arc<AtomicUsize>,
+    }
+
+    #[crate::subsystem]
+    impl FlakySubsystem {
+
+        async fn on_start(&mut self) -> Result<()> {
+            self.starts.fetch_add(1, Ordering::SeqCst);
+
+            if self.panics.load(Ordering::SeqCst) > 0 {
+                self.panics.fetch_sub(1, Ordering::SeqCst);
+                panic!("Start panicked");
+            }
+
+            if self.failures.load(Ordering::SeqCst) == 0 {
+                return Ok(());
+            }
+
+            self.failures.fetch_sub(1, Ordering::SeqCst);
+
+            Err(anyhow::anyhow!("Not yet"))
+        }
+
+        #[crate::task]
+        fn ping(&self) {}
+
+        #[crate::task]
+        fn explode(&self) {
+            panic!("Explode always panics");
+        }
+    }
+
+    pub async fn start_subsystems() -> Channels {
+        let (test_ref, test_receiver) = SubsystemRef::<TestSubsystem>::new();
+
+        let mut channels = Channels::default();
+        let tasks_ref = start_tasks(&mut channels)
+            .await;
+
+        channels.add(test_ref)
+            .await;
+
+        TestSubsystem::start(
+            TestSubsystem::new(channels.clone()),
+            test_receiver,
+            tasks_ref,
+        );
+
+        channels
+    }
+
+    /// Starts `TasksSubsystem` quietly and adds it to `channels`.
+    pub async fn start_tasks(channels: &mut Channels) -> SubsystemRef<TasksSubsystem> {
+        start_tasks_with_depth(channels, tasks::DEFAULT_MAX_TASK_DEPTH)
+            .await
+    }
+
+    pub async fn start_tasks_with_depth(channels: &mut Channels, max_task_depth: usize) -> SubsystemRef<TasksSubsystem> {
+        let (tasks_ref, tasks_receiver) = SubsystemRef::<TasksSubsystem>::new();
+
+        channels.add(tasks_ref.clone())
+            .await;
+
+        TasksSubsystem::start_quiet(
+            TasksSubsystem {
+                channels: channels.clone(),
+                tasks: ArcLock::new(IndexMap::new()),
+                benchmarks: ArcLock::new(IndexMap::new()),
+                formatters: ArcLock::new(HashMap::new()),
+                log_history: LogHistory::default(),
+                audit: ArcLock::new(VecDeque::new()),
+                audit_capacity: tasks::DEFAULT_AUDIT_CAPACITY,
+                max_task_depth,
+            },
+            tasks_receiver,
+        );
+
+        tasks_ref
+    }
+
+    #[tokio::test]
+    async fn nested_dispatch_records_depth_and_parent() {
+        let channels = start_subsystems()
+            .await;
+
+        let inner_scope = channels
+            .get::<TestSubsystem>()
+            .send(Outer)
+            .await
+            .unwrap()
+            .unwrap()
+            .expect("Inner ran outside a task scope");
+
+        assert_eq!(inner_scope.depth, 1);
+
+        let tree = channels
+            .get::<TasksSubsystem>()
+            .send(tasks::GetTaskTree)
+            .await
+            .unwrap();
+
+        let outer = tree
+            .iter()
+            .find(|node| node.name == <Outer as Task>::name())
+            .expect("Outer wasn't logged as a root task");
+
+        assert_eq!(outer.depth, 0);
+        assert_eq!(outer.children.len(), 1);
+        assert_eq!(outer.children[0].name, <Inner as Task>::name());
+        assert_eq!(outer.children[0].id, inner_scope.id);
+        assert_eq!(outer.children[0].depth, 1);
+    }
+
+    #[tokio::test]
+    async fn aborted_control_stops_the_loop() {
+        let (test_ref, test_receiver) = SubsystemRef::<TestSubsystem>::new();
+        let mut channels = Channels::default();
+
+        channels.add(test_ref.clone())
+            .await;
+
+        let control = TestSubsystem::start_with_handle(TestSubsystem::new(channels), test_receiver, None);
+
+        assert!(test_ref.send(Inner).await.is_ok());
+
+        control.abort();
+
+        control
+            .await
+            .unwrap();
+
+        let result = test_ref.send(Inner)
+            .await;
+
+        assert!(matches!(result, Err(TitanError::ChannelClosed { .. })));
+    }
+
+    #[tokio::test]
+    async fn lifecycle_hooks_run_around_the_loop() {
+        let (hooked_ref, hooked_receiver) = SubsystemRef::<HookedSubsystem>::new();
+        let hooks = Arc::new(Mutex::new(Vec::new()));
+
+        let mut control = HookedSubsystem::start_with_handle(
+            HookedSubsystem {
+                channels: Channels::default(),
+                hooks: hooks.clone(),
+            },
+            hooked_receiver,
+            None,
+        );
+
+        assert_eq!(hooked_ref.send(Hooks).await.unwrap(), ["on_start"]);
+
+        control.shutdown();
+
+        control
+            .await
+            .unwrap();
+
+        assert_eq!(*hooks.lock().unwrap(), ["on_start", "on_stop"]);
+    }
+
+    #[tokio::test]
+    async fn weakly_registered_subsystem_stops_with_its_last_strong_ref() {
+        let (test_ref, test_receiver) = SubsystemRef::<TestSubsystem>::new();
+        let mut channels = Channels::default();
+
+        channels.add_weak(&test_ref)
+            .await;
+
+        // The subsystem holds the same `Channels`, which would keep it alive if it were strong.
+        let control = TestSubsystem::start_with_handle(TestSubsystem::new(channels.clone()), test_receiver, None);
+
+        assert!(channels.get::<TestSubsystem>().send(Inner).await.is_ok());
+
+        drop(test_ref);
+
+        tokio::time::timeout(Duration::from_secs(1), control)
+            .await
+            .expect("The loop outlived its last strong ref")
+            .unwrap();
+
+        assert!(matches!(channels.try_get::<TestSubsystem>(), Err(TitanError::SubsystemDropped { .. })));
+    }
+
+    #[tokio::test]
+    async fn rejected_input_never_runs_the_task() {
+        let channels = start_subsystems()
+            .await;
+
+        let test_ref = channels.get::<TestSubsystem>();
+
+        assert_eq!(test_ref.send(Double { value: 2 }).await, Ok(4));
+
+        let rejected = test_ref.send(Double { value: -2 })
+            .await;
+
+        assert_eq!(rejected, Err(TitanError::InvalidInput {
+            task: <Double as Task>::name(),
+            reason: String::from("-2 isn't positive"),
+        }));
+    }
+
+    /// The part of an `Inner` id after its name.
+    fn id_suffix(id: &str) -> &str {
+        id.strip_prefix(<Inner as Task>::name())
+            .and_then(|suffix| suffix.strip_prefix('_'))
+            .unwrap()
+    }
+
+    #[cfg(feature = "deterministic-ids")]
+    #[test]
+    fn deterministic_ids_count_up() {
+        let first = Inner.new_id();
+        let second = Inner.new_id();
+
+        assert_eq!(id_suffix(&first).len(), 16);
+
+        // Other tests draw from the same counter, so only the order is certain.
+        let first = id_suffix(&first).parse::<u64>().unwrap();
+        let second = id_suffix(&second).parse::<u64>().unwrap();
+
+        assert!(second > first);
+    }
+
+    #[cfg(not(feature = "deterministic-ids"))]
+    #[test]
+    fn random_ids_differ() {
+        let first = Inner.new_id();
+        let second = Inner.new_id();
+
+        assert_eq!(id_suffix(&first).len(), 16);
+        assert_ne!(first, second);
+    }
+
+    #[tokio::test]
+    async fn stream_task_emits_every_item_then_ends() {
+        let channels = start_subsystems()
+            .await;
+
+        let mut items = channels
+            .get::<TestSubsystem>()
+            .send_stream(Count { count: 3 });
+
+        let mut received = Vec::new();
+
+        while let Some(item) = items.recv().await {
+            received.push(item);
+        }
+
+        assert_eq!(received, [0, 1, 2]);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn send_sync_blocks_inside_a_synchronous_callback() {
+        let channels = start_subsystems()
+            .await;
+
+        let test_ref = channels.get::<TestSubsystem>();
+        let callback = |value| test_ref.send_sync(Double { value });
+
+        assert_eq!(callback(3).unwrap(), 6);
+    }
+
+    #[test]
+    fn send_sync_outside_a_runtime_errors() {
+        let (test_ref, _test_receiver) = SubsystemRef::<TestSubsystem>::new();
+
+        let err = test_ref.send_sync(Tallies)
+            .unwrap_err();
+
+        assert!(err.to_string().contains("outside of a tokio runtime"));
+    }
+
+    #[tokio::test]
+    async fn depth_guard_stops_a_self_dispatching_task() {
+        let channels = start_subsystems()
+            .await;
+
+        let err = channels
+            .get::<TestSubsystem>()
+            .send(Recurse)
+            .await
+            .unwrap();
+
+        assert_eq!(err, TitanError::DepthExceeded {
+            task: <Recurse as Task>::name(),
+            depth: tasks::DEFAULT_MAX_TASK_DEPTH + 1,
+        });
+    }
+
+    #[tokio::test]
+    async fn depth_limit_comes_from_the_tasks_subsystem() {
+        let (test_ref, test_receiver) = SubsystemRef::<TestSubsystem>::new();
+
+        let mut channels = Channels::default();
+        let tasks_ref = start_tasks_with_depth(&mut channels, 3)
+            .await;
+
+        channels.add(test_ref)
+            .await;
+
+        TestSubsystem::start(TestSubsystem::new(channels.clone()), test_receiver, tasks_ref);
+
+        let err = channels
+            .get::<TestSubsystem>()
+            .send(Recurse)
+            .await
+            .unwrap();
+
+        assert_eq!(err, TitanError::DepthExceeded {
+            task: <Recurse as Task>::name(),
+            depth: 4,
+        });
+    }
+
+    #[tokio::test]
+    async fn pending_len_follows_the_undrained_sends() {
+        let (test_ref, test_receiver) = SubsystemRef::<TestSubsystem>::new();
+
+        let handles = (0..3)
+            .map(|_| test_ref.send(Tally))
+            .collect::<Vec<_>>();
+
+        assert_eq!(test_ref.pending_len(), 3);
+
+        TestSubsystem::start_with_handle(TestSubsystem::new(Channels::default()), test_receiver, None);
+
+        for handle in handles {
+            handle
+                .await
+                .unwrap();
+        }
+
+        assert_eq!(test_ref.pending_len(), 0);
+    }
+
+    #[test]
+    fn cost_is_exposed_through_task_info() {
+        let heavy: Box<dyn TaskInfo> = Box::new(Heavy);
+        let inner: Box<dyn TaskInfo> = Box::new(Inner);
+
+        assert_eq!(heavy.cost(), 5);
+        assert_eq!(inner.cost(), 1);
+    }
+
+    #[tokio::test]
+    async fn panicking_io_task_reports_the_panic() {
+        let channels = start_subsystems()
+            .await;
+
+        let result = channels
+            .get::<TestSubsystem>()
+            .send(Crash)
+            .await;
+
+        assert_eq!(result, Err(TitanError::TaskPanicked { task: <Crash as Task>::name() }));
+
+        // The loop carries on after the panic.
+        assert!(channels.get::<TestSubsystem>().send(Tallies).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn flooded_idempotent_task_coalesces() {
+        let (test_ref, test_receiver) = SubsystemRef::<TestSubsystem>::new();
+
+        // Sent before the loop starts, so they all queue up behind the first.
+        let handles = (0..100)
+            .map(|_| test_ref.send(Mark))
+            .collect::<Vec<_>>();
+
+        TestSubsystem::start_with_handle(TestSubsystem::new(Channels::default()), test_receiver, None);
+
+        let mut coalesced = 0;
+
+        for handle in handles {
+            match handle.await {
+                Ok(()) => {},
+                Err(TitanError::Coalesced { .. }) => coalesced += 1,
+                Err(err) => panic!("Mark failed: {}", err),
+            }
+        }
+
+        assert_eq!(coalesced, 99);
+        assert_eq!(test_ref.send(Tallies).await, Ok(1));
+
+        // Once the queued one ran, the next is queued again.
+        assert_eq!(test_ref.send(Mark).await, Ok(()));
+        assert_eq!(test_ref.send(Tallies).await, Ok(2));
+    }
+
+    #[tokio::test]
+    async fn dispatch_chain_shares_the_root_correlation() {
+        let channels = start_subsystems()
+            .await;
+
+        let inner_scope = channels
+            .get::<TestSubsystem>()
+            .send(Outer)
+            .await
+            .unwrap()
+            .unwrap()
+            .expect("Inner ran outside a task scope");
+
+        let tree = channels
+            .get::<TasksSubsystem>()
+            .send(tasks::GetTaskTree)
+            .await
+            .unwrap();
+
+        let outer = tree
+            .iter()
+            .find(|node| node.name == <Outer as Task>::name())
+            .expect("Outer wasn't logged as a root task");
+
+        assert_eq!(inner_scope.correlation, outer.id);
+        assert_ne!(inner_scope.correlation, inner_scope.id);
+
+        // A separate top-level dispatch starts its own chain.
+        let root_scope = channels
+            .get::<TestSubsystem>()
+            .send(Inner)
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(root_scope.correlation, root_scope.id);
+    }
+
+    #[test]
+    fn group_defaults_to_the_subsystem_name() {
+        assert_eq!(<Inner as Task>::group(), TestSubsystem::name());
+        assert_eq!(<Heavy as Task>::group(), "heavy");
+    }
+
+    #[tokio::test]
+    async fn displayed_output_lands_in_the_task_log() {
+        let channels = start_subsystems()
+            .await;
+
+        channels
+            .get::<TestSubsystem>()
+            .send(Tally)
+            .await
+            .unwrap();
+
+        channels
+            .get::<TestSubsystem>()
+            .send(Tallies)
+            .await
+            .unwrap();
+
+        // `EndTask` follows the reply, so the display may still say "Exec...".
+        for _ in 0..100 {
+            let tree = channels
+                .get::<TasksSubsystem>()
+                .send(tasks::GetTaskTree)
+                .await
+                .unwrap();
+
+            let display = |name| tree
+                .iter()
+                .find(|node| node.name == name)
+                .map(|node| (node.complete, node.display.clone()));
+
+            if let (Some((true, tally)), Some((true, tallies))) = (display(<Tally as Task>::name()), display(<Tallies as Task>::name())) {
+                assert!(!tally.contains("->"), "{}", tally);
+                assert!(tallies.ends_with(" -> 1"), "{}", tallies);
+                return;
+            }
+
+            tokio::time::sleep(Duration::from_millis(1))
+                .await;
+        }
+
+        panic!("The tasks never completed in the task log");
+    }
+
+    #[tokio::test]
+    async fn send_into_converts_the_inputs() {
+        let channels = start_subsystems()
+            .await;
+
+        let doubled = channels
+            .get::<TestSubsystem>()
+            .send_into::<Double, _>(21)
+            .await;
+
+        assert_eq!(doubled, Ok(42));
+
+        // Converted tasks are still validated.
+        let rejected = channels
+            .get::<TestSubsystem>()
+            .send_into::<Double, _>(-1)
+            .await;
+
+        assert!(matches!(rejected, Err(TitanError::InvalidInput { .. })), "{:?}", rejected);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn overrunning_task_warns_but_completes() {
+        let history = LogHistory::new(64);
+        let _subscriber = tracing::subscriber::set_default(
+            tracing_subscriber::registry().with(history.clone()),
+        );
+
+        let channels = start_subsystems()
+            .await;
+
+        let overruns = || history
+            .recent(64)
+            .into_iter()
+            .filter(|line| line.starts_with("WARN") && line.contains(<Linger as Task>::name()))
+            .collect::<Vec<_>>();
+
+        channels
+            .get::<TestSubsystem>()
+            .send(Linger { millis: 5 })
+            .await
+            .unwrap();
+
+        assert!(overruns().is_empty());
+
+        channels
+            .get::<TestSubsystem>()
+            .send(Linger { millis: 20 })
+            .await
+            .unwrap();
+
+        let overruns = overruns();
+
+        assert_eq!(overruns.len(), 1);
+        assert!(overruns[0].contains("expected under 10ms"), "{}", overruns[0]);
+    }
+
+    #[tokio::test]
+    async fn batch_results_come_back_in_send_order() {
+        let channels = start_subsystems()
+            .await;
+
+        let test_ref = channels.get::<TestSubsystem>();
+        let batch = |values: &[i64]| test_ref.send_batch(
+            values.iter()
+                .map(|&value| Double { value })
+                .collect()
+        );
+
+        assert_eq!(batch(&[3, 1, 2]).try_all().await, Ok(vec![6, 2, 4]));
+
+        let failed = batch(&[3, -1, 5]).try_all()
+            .await;
+
+        assert!(matches!(failed, Err(TitanError::InvalidInput { .. })), "{:?}", failed);
+
+        let (outputs, errors) = batch(&[3, -1, 5, -2]).partition()
+            .await;
+
+        assert_eq!(outputs, [6, 10]);
+        assert_eq!(errors.len(), 2);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn queue_latency_counts_the_wait_before_running() {
+        let mut channels = Channels::default();
+        let tasks_ref = start_tasks(&mut channels)
+            .await;
+        let (test_ref, test_receiver) = SubsystemRef::<TestSubsystem>::new();
+
+        channels.add(test_ref.clone())
+            .await;
+
+        // Queued while the loop isn't running yet.
+        let handle = test_ref.send(Tally);
+
+        tokio::time::advance(Duration::from_millis(50))
+            .await;
+
+        TestSubsystem::start(TestSubsystem::new(channels.clone()), test_receiver, tasks_ref.clone());
+
+        handle
+            .await
+            .unwrap();
+
+        let queued = format!("(queued {})", crate::chrono::format_duration_compact(&0.05));
+
+        for _ in 0..100 {
+            let tree = tasks_ref
+                .send(tasks::GetTaskTree)
+                .await
+                .unwrap();
+
+            if let Some(tally) = tree.iter().find(|node| node.name == <Tally as Task>::name() && node.complete) {
+                assert!(tally.display.contains(&queued), "{}", tally.display);
+                return;
+            }
+
+            tokio::task::yield_now()
+                .await;
+        }
+
+        panic!("Tally never completed in the task log");
+    }
+
+    #[tokio::test]
+    async fn send_then_calls_back_with_the_result() {
+        let channels = start_subsystems()
+            .await;
+
+        let (result_sender, result_receiver) = oneshot::channel();
+
+        channels
+            .get::<TestSubsystem>()
+            .send_then(Double { value: 4 }, move |result| {
+                let _ = result_sender.send(result);
+            });
+
+        assert_eq!(result_receiver.await, Ok(Ok(8)));
+    }
+
+    #[test]
+    fn send_then_outside_a_runtime_calls_back_right_away() {
+        let (test_ref, _test_receiver) = SubsystemRef::<TestSubsystem>::new();
+        let result = Arc::new(Mutex::new(None));
+        let callback_result = result.clone();
+
+        test_ref.send_then(Tallies, move |tallies| {
+            *callback_result.lock().unwrap() = Some(tallies);
+        });
+
+        assert_eq!(*result.lock().unwrap(), Some(Err(TitanError::NoRuntime { task: <Tallies as Task>::name() })));
+    }
+
+    #[tokio::test]
+    async fn supervisor_restarts_until_the_policy_gives_up() {
+        let start_flaky = |failures: usize, policy| {
+            let (flaky_ref, flaky_receiver) = SubsystemRef::<FlakySubsystem>::new();
+            let failures = Arc::new(AtomicUsize::new(failures));
+
+            FlakySubsystem::start_supervised(
+                move || FlakySubsystem {
+                    channels: Channels::default(),
+                    failures: failures.clone(),
+                    panics: Arc::new(AtomicUsize::new(0)),
+                    starts: Arc::new(AtomicUsize::new(0)),
+                },
+                flaky_receiver,
+                None,
+                policy,
+            );
+
+            flaky_ref
+        };
+
+        assert_eq!(start_flaky(2, RestartPolicy::UpTo(2)).send(Ping).await, Ok(()));
+        assert_eq!(start_flaky(5, RestartPolicy::Always).send(Ping).await, Ok(()));
+
+        let gave_up = start_flaky(2, RestartPolicy::UpTo(1)).send(Ping)
+            .await;
+
+        assert!(matches!(gave_up, Err(TitanError::ChannelClosed { .. })), "{:?}", gave_up);
+
+        let gave_up = start_flaky(1, RestartPolicy::Never).send(Ping)
+            .await;
+
+        assert!(matches!(gave_up, Err(TitanError::ChannelClosed { .. })), "{:?}", gave_up);
+    }
+
+    #[tokio::test]
+    async fn supervisor_restarts_after_panics_within_the_policy() {
+        let starts = Arc::new(AtomicUsize::new(0));
+
+        let start_panicky = |policy| {
+            let (flaky_ref, flaky_receiver) = SubsystemRef::<FlakySubsystem>::new();
+            let panics = Arc::new(AtomicUsize::new(1));
+            let starts = starts.clone();
+
+            starts.store(0, Ordering::SeqCst);
+
+            FlakySubsystem::start_supervised(
+                move || FlakySubsystem {
+                    channels: Channels::default(),
+                    failures: Arc::new(AtomicUsize::new(0)),
+                    panics: panics.clone(),
+                    starts: starts.clone(),
+                },
+                flaky_receiver,
+                None,
+                policy,
+            );
+
+            flaky_ref
+        };
+
+        // Waits for the supervisor to have started the subsystem `count` times.
+        let started = |count| {
+            let starts = starts.clone();
+
+            async move {
+                for _ in 0..100 {
+                    if starts.load(Ordering::SeqCst) == count {
+                        return;
+                    }
+
+                    tokio::time::sleep(Duration::from_millis(10))
+                        .await;
+                }
+
+                panic!("Started {} times, expected {}", starts.load(Ordering::SeqCst), count);
+            }
+        };
+
+        // `on_start` panics once, then a task panics: two restarts.
+        let flaky_ref = start_panicky(RestartPolicy::UpTo(2));
+
+        assert_eq!(flaky_ref.send(Ping).await, Ok(()));
+        assert_eq!(starts.load(Ordering::SeqCst), 2);
+
+        let exploded = flaky_ref.send(Explode)
+            .await;
+
+        assert!(matches!(exploded, Err(TitanError::TaskPanicked { .. })), "{:?}", exploded);
+
+        started(3)
+            .await;
+
+        assert_eq!(flaky_ref.send(Ping).await, Ok(()));
+
+        // The start panic uses up the only restart, so the task panic ends it.
+        let flaky_ref = start_panicky(RestartPolicy::UpTo(1));
+
+        assert_eq!(flaky_ref.send(Ping).await, Ok(()));
+
+        let _ = flaky_ref.send(Explode)
+            .await;
+
+        for _ in 0..100 {
+            if flaky_ref.send(Ping).await.is_err() {
+                break;
+            }
+
+            tokio::time::sleep(Duration::from_millis(10))
+                .await;
+        }
+
+        let gave_up = flaky_ref.send(Ping)
+            .await;
+
+        assert!(matches!(gave_up, Err(TitanError::ChannelClosed { .. })), "{:?}", gave_up);
+        assert_eq!(starts.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn audit_records_whether_tasks_succeeded() {
+        let channels = start_subsystems()
+            .await;
+
+        let test_ref = channels.get::<TestSubsystem>();
+
+        test_ref.send(Tally)
+            .await
+            .unwrap();
+
+        assert!(test_ref.send(Double { value: -1 }).await.is_err());
+        assert!(test_ref.send(Fail).await.is_err());
+
+        // Audit entries land after the callers were answered.
+        for _ in 0..100 {
+            let audit = channels
+                .get::<TasksSubsystem>()
+                .send(tasks::GetAudit { n: 16 })
+                .await
+                .unwrap();
+
+            let success = |name| audit
+                .iter()
+                .find(|entry| entry.name == name)
+                .map(|entry| entry.success);
+
+            if audit.len() == 3 {
+                assert_eq!(success(<Tally as Task>::name()), Some(true));
+                assert_eq!(success(<Double as Task>::name()), Some(false));
+                assert_eq!(success(<Fail as Task>::name()), Some(false));
+                assert!(audit.iter().all(|entry| entry.subsystem == TestSubsystem::name()));
+                return;
+            }
+
+            tokio::time::sleep(Duration::from_millis(1))
+                .await;
+        }
+
+        panic!("The audit never got all three tasks");
+    }
+
+    #[cfg(debug_assertions)]
+    #[tokio::test]
+    async fn blocking_task_warns_unless_it_is_io() {
+        let history = LogHistory::new(64);
+        let _subscriber = tracing::subscriber::set_default(
+            tracing_subscriber::registry().with(history.clone()),
+        );
+
+        let channels = start_subsystems()
+            .await;
+
+        let test_ref = channels.get::<TestSubsystem>();
+
+        test_ref.send(Grind { millis: 20 })
+            .await
+            .unwrap();
+        test_ref.send(Spin { millis: 20 })
+            .await
+            .unwrap();
+
+        let blocked = history
+            .recent(64)
+            .into_iter()
+            .filter(|line| line.contains("Blocked the executor"))
+            .collect::<Vec<_>>();
+
+        assert_eq!(blocked.len(), 1);
+        assert!(blocked[0].contains(<Spin as Task>::name()), "{}", blocked[0]);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn mutable_tasks_apply_in_send_order() {
+        let channels = start_subsystems()
+            .await;
+
+        let test_ref = channels.get::<TestSubsystem>();
+
+        // The rejected one drops its ticket without running, which mustn't stall the rest.
+        let handles = (0..200)
+            .map(|value| match value {
+                100 => test_ref.send_mut(Record { value: 1000 }),
+                value => test_ref.send_mut(Record { value }),
+            })
+            .collect::<Vec<_>>();
+
+        for handle in handles {
+            let _ = handle.await;
+        }
+
+        let expected = (0..200)
+            .filter(|value| *value != 100)
+            .collect::<Vec<_>>();
+
+        assert_eq!(test_ref.send(Recorded).await, Ok(expected));
+    }
+
+    #[tokio::test]
+    async fn reentrant_mutable_await_errors_instead_of_deadlocking() {
+        let channels = start_subsystems()
+            .await;
+
+        let test_ref = channels.get::<TestSubsystem>();
+
+        let result = tokio::time::timeout(Duration::from_secs(1), test_ref.send_mut(Reenter { name: String::new(), value: 3 }))
+            .await
+            .expect("Re-entrant await deadlocked");
+
+        assert_eq!(
+            result,
+            Ok(Err(TitanError::Reentrant {
+                task: <Record as Task>::name(),
+                subsystem: TestSubsystem::name(),
+            })),
+        );
+
+        // The inner task was still queued and runs once the outer one lets go.
+        assert_eq!(test_ref.send_mut(Record { value: 4 }).await, Ok(()));
+        assert_eq!(test_ref.send(Recorded).await, Ok(vec![3, 4]));
+    }
+
+    #[tokio::test]
+    async fn named_instances_of_one_type_are_not_reentrant() {
+        let mut channels = Channels::default();
+
+        for name in ["left", "right"] {
+            let (test_ref, test_receiver) = SubsystemRef::<TestSubsystem>::new();
+
+            channels.add_named(name, test_ref)
+                .await;
+
+            TestSubsystem::start_with_handle(TestSubsystem::new(channels.clone()), test_receiver, None);
+        }
+
+        let timeout = |handle| async {
+            tokio::time::timeout(Duration::from_secs(1), handle)
+                .await
+                .expect("Cross-instance await deadlocked")
+        };
+
+        let left = channels.get_named::<TestSubsystem>("left");
+        let right = channels.get_named::<TestSubsystem>("right");
+
+        // Each holds its own lock while awaiting a task on the other, whose lock is free.
+        let to_right = left.send_mut(Reenter { name: "right".to_string(), value: 1 });
+
+        assert_eq!(timeout(to_right).await, Ok(Ok(())));
+
+        let to_left = right.send_mut(Reenter { name: "left".to_string(), value: 2 });
+
+        assert_eq!(timeout(to_left).await, Ok(Ok(())));
+
+        assert_eq!(left.send(Recorded).await, Ok(vec![2]));
+        assert_eq!(right.send(Recorded).await, Ok(vec![1]));
+    }
+
+    #[tokio::test]
+    async fn cached_task_runs_once_until_invalidated() {
+        let channels = start_subsystems()
+            .await;
+
+        let test_ref = channels.get::<TestSubsystem>();
+
+        assert_eq!(test_ref.send(Square { value: 3 }).await, Ok(9));
+        assert_eq!(test_ref.send(Square { value: 3 }).await, Ok(9));
+        assert_eq!(test_ref.send(Tallies).await, Ok(1));
+
+        // Other inputs miss the cache.
+        assert_eq!(test_ref.send(Square { value: 4 }).await, Ok(16));
+        assert_eq!(test_ref.send(Tallies).await, Ok(2));
+        assert_eq!(channels.task_cache::<Square>().len(), 2);
+
+        channels.invalidate_cache::<Square>();
+
+        assert_eq!(test_ref.send(Square { value: 3 }).await, Ok(9));
+        assert_eq!(test_ref.send(Tallies).await, Ok(3));
+        assert_eq!(channels.task_cache::<Square>().len(), 1);
+    }
+
+    #[test]
+    fn refs_equal_their_clones_and_upgrades() {
+        let (test_ref, _test_receiver) = SubsystemRef::<TestSubsystem>::new();
+        let (other_ref, _other_receiver) = SubsystemRef::<TestSubsystem>::new();
+
+        let upgraded = test_ref.downgrade()
+            .upgrade()
+            .unwrap();
+
+        assert!(test_ref == test_ref.clone());
+        assert!(test_ref == upgraded);
+        assert!(test_ref != other_ref);
+
+        assert_eq!(test_ref.ptr_id(), upgraded.ptr_id());
+        assert_ne!(test_ref.ptr_id(), other_ref.ptr_id());
     }
 }
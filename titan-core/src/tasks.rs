@@ -1,7 +1,10 @@
+use std::{collections::{HashMap, VecDeque}, time::{Duration, SystemTime}};
+
 use indexmap::IndexMap;
-use crate::{ArcLock, Channels};
+use anyhow::Result;
+use crate::{chrono, logs::LogHistory, ArcLock, Channels};
 
-#[derive(Clone, Default)]
+#[derive(Clone, Default, Hash)]
 pub struct Display {
     pub name: String,
     pub display: String,
@@ -12,15 +15,71 @@ pub struct TaskLog {
     pub id: String,
     pub name: &'static str,
     pub depth: usize,
+    pub parent: Option<String>,
+    /// Id of the top-level task of the dispatch chain, see `TaskScope::correlation`.
+    pub correlation: String,
+    /// Seconds the task waited in its subsystem's queue before it started running.
+    pub queue_latency: f64,
     pub complete: bool,
     pub start: f64,
     pub duration: f64,
+    /// The task's return value, for tasks with `#[task(display_output)]`.
+    pub output: Option<String>,
     pub display: String,
 }
 
+/// A finished task as kept by the audit log, see `GetAudit`.
+#[derive(Clone, Debug)]
+pub struct AuditEntry {
+    pub name: &'static str,
+    pub subsystem: &'static str,
+    pub start: SystemTime,
+    /// Seconds.
+    pub duration: f64,
+    /// False when the task panicked or its input was rejected.
+    pub success: bool,
+}
+
+/// A finished task as reported to `EndTask`, which publishes it on to its subscribers.
+#[derive(Clone)]
+pub struct TaskEnd {
+    pub id: String,
+    pub name: &'static str,
+    /// Seconds since the task started.
+    pub end: f64,
+    pub output: Option<String>,
+    pub display: TaskDisplayFn,
+    pub subsystem: &'static str,
+    /// False when the task panicked or its input was rejected.
+    pub success: bool,
+}
+
+/// Benchmark displays sharing a group, in registration order.
+#[derive(Clone, Default, Hash)]
+pub struct BenchmarkGroup {
+    pub name: String,
+    pub benches: Vec<Display>,
+}
+
+pub type TaskDisplayFn = fn(&TaskLog) -> String;
+pub type BenchmarkDisplayFn = fn(&BenchmarkLog) -> String;
+
+#[derive(Clone)]
+pub struct TaskNode {
+    pub id: String,
+    pub name: &'static str,
+    pub depth: usize,
+    pub complete: bool,
+    pub duration: f64,
+    pub display: String,
+    pub children: Vec<TaskNode>,
+}
+
 #[derive(Clone)]
 pub struct BenchmarkLog {
     pub name: &'static str,
+    /// Section the benchmark is listed under, see `Task::group`.
+    pub group: &'static str,
     pub start: f64,
     pub duration: f64,
     pub max: f64,
@@ -29,27 +88,100 @@ pub struct BenchmarkLog {
     pub runs: u64,
     pub run_time: f64,
     pub display: String,
+    /// The most recent `BENCHMARK_SAMPLES` durations, used for percentiles.
+    pub samples: VecDeque<f64>,
+}
+
+/// How many recent durations each benchmark keeps for percentiles.
+pub const BENCHMARK_SAMPLES: usize = 1024;
+
+impl BenchmarkLog {
+    /// Nearest-rank percentile over the recent samples, `p` in `0.0..=1.0`.
+    pub fn percentile(&self, p: f64) -> f64 {
+        if self.samples.is_empty() {
+            return 0.0;
+        }
+
+        let mut sorted = self.samples
+            .iter()
+            .copied()
+            .collect::<Vec<_>>();
+
+        sorted.sort_by(f64::total_cmp);
+
+        let rank = (p.clamp(0.0, 1.0) * sorted.len() as f64).ceil() as usize;
+
+        sorted[rank.saturating_sub(1)]
+    }
 }
 
 pub struct TasksSubsystem {
     pub channels: Channels,
     pub tasks: ArcLock<IndexMap<String, TaskLog>>,
     pub benchmarks: ArcLock<IndexMap<&'static str, BenchmarkLog>>,
+    pub formatters: ArcLock<HashMap<&'static str, BenchmarkDisplayFn>>,
+    /// Recent log lines, filled wherever the history is installed as a tracing layer.
+    pub log_history: LogHistory,
+    /// Most recently finished tasks, oldest first. Unlike `tasks` it isn't pruned.
+    pub audit: ArcLock<VecDeque<AuditEntry>>,
+    /// Entries `audit` holds before dropping the oldest.
+    pub audit_capacity: usize,
+    /// Deepest nested dispatch allowed, tasks past it fail with `TitanError::DepthExceeded`.
+    pub max_task_depth: usize,
+}
+
+/// Nesting limit that stops tasks re-dispatching themselves, directly or through subscriptions.
+/// Also the limit for quiet subsystems, which can't reach `TasksSubsystem`.
+pub const DEFAULT_MAX_TASK_DEPTH: usize = 64;
+
+/// Finished tasks the audit log keeps unless configured otherwise.
+pub const DEFAULT_AUDIT_CAPACITY: usize = 256;
+
+/// Default task display: the duration, followed by the output when the task records it.
+pub fn format_task(task: &TaskLog) -> String {
+    let duration = format!("{} (queued {})",
+        chrono::format_duration_compact(&task.duration),
+        chrono::format_duration_compact(&task.queue_latency)
+    );
+
+    match &task.output {
+        Some(output) => format!("{} -> {}", duration, output),
+        None => duration,
+    }
+}
+
+/// Display used by benchmarks without a registered formatter.
+pub fn format_benchmark(bench: &BenchmarkLog) -> String {
+    format!("{} ~ [{}] <=> [{} - {}]",
+        &chrono::format_duration_compact(&bench.duration),
+        &chrono::format_duration_compact(&bench.average),
+        &chrono::format_duration_compact(&bench.min),
+        &chrono::format_duration_compact(&bench.max)
+    )
 }
 
 #[crate::subsystem]
 impl TasksSubsystem {
 
+    /// `max_task_depth`, read by each subsystem loop as it starts.
     #[crate::task]
-    async fn start_task(&self, id: String, name: &'static str, depth: usize) {
-         
+    fn max_task_depth(&self) -> usize {
+        self.max_task_depth
+    }
+
+    #[crate::task]
+    async fn start_task(&self, id: String, name: &'static str, depth: usize, parent: Option<String>, correlation: String, queue_latency: f64) {
         let task = TaskLog {
             id,
             name,
             depth,
+            parent,
+            correlation,
+            queue_latency,
             complete: false,
             start: 0.0,
             duration: 0.0,
+            output: None,
             display: "Exec...".to_string(),
         };
 
@@ -61,18 +193,16 @@ impl TasksSubsystem {
     }
 
     #[crate::task]
-    async fn end_task<F>(&self, id: String, end: f64, display: F) -> Display 
-    where
-        F: Fn(&TaskLog) -> String + Clone + Send + Sync + 'static,
-    {
+    async fn end_task(&self, task_end: TaskEnd) -> Display {
         self.tasks
             .lock()
             .await
-            .entry(id.clone())
+            .entry(task_end.id.clone())
             .and_modify(|task| {
                 task.complete = true;
-                task.duration = end;
-                task.display = display(task);
+                task.duration = task_end.end;
+                task.output = task_end.output.clone();
+                task.display = (task_end.display)(task);
             });
 
         let task_lock = self.tasks
@@ -80,13 +210,33 @@ impl TasksSubsystem {
             .await;
 
         let task = task_lock
-            .get(&id)
+            .get(&task_end.id)
             .expect("Failed to get task log!");
 
-        Display {
+        let task_display = Display {
             name: task.name.to_string(),
             display: task.display.clone(),
+        };
+
+        drop(task_lock);
+
+        self.record_audit(AuditEntry {
+            name: task_end.name,
+            subsystem: task_end.subsystem,
+            start: SystemTime::now() - Duration::from_secs_f64(task_end.end),
+            duration: task_end.end,
+            success: task_end.success,
+        })
+        .await;
+
+        // Every task ends here, so skip the publish unless something like metrics listens.
+        if self.channels.has_subscribers::<EndTask>().await {
+            self.channels
+                .publish(EndTask::from(task_end))
+                .await;
         }
+
+        task_display
     }
 
     #[crate::task]
@@ -103,9 +253,57 @@ impl TasksSubsystem {
     }
 
     #[crate::task]
-     async fn start_benchmark(&self, name: &'static str) {
+    async fn get_task_tree(&self) -> Vec<TaskNode> {
+        let tasks = self.tasks
+            .lock()
+            .await;
+
+        let mut children: HashMap<&str, Vec<&TaskLog>> = HashMap::new();
+        let mut roots = Vec::new();
+
+        for task in tasks.values() {
+            match task.parent.as_deref() {
+                Some(parent) if tasks.contains_key(parent) => {
+                    children
+                        .entry(parent)
+                        .or_default()
+                        .push(task);
+                },
+                _ => roots.push(task),
+            }
+        }
+
+        roots.into_iter()
+            .map(|task| Self::build_task_node(task, &children))
+            .collect()
+    }
+
+    fn build_task_node(task: &TaskLog, children: &HashMap<&str, Vec<&TaskLog>>) -> TaskNode {
+        let task_children = children
+            .get(task.id.as_str())
+            .map(|logs| {
+                logs.iter()
+                    .map(|child| Self::build_task_node(child, children))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        TaskNode {
+            id: task.id.clone(),
+            name: task.name,
+            depth: task.depth,
+            complete: task.complete,
+            duration: task.duration,
+            display: task.display.clone(),
+            children: task_children,
+        }
+    }
+
+    #[crate::task]
+     async fn start_benchmark(&self, name: &'static str, group: &'static str) {
         let bench = BenchmarkLog {
             name,
+            group,
             start: 0.0,
             duration: 0.0,
             average: 0.0,
@@ -114,6 +312,7 @@ impl TasksSubsystem {
             display: String::from(name),
             max: 0.0,
             min: f64::MAX,
+            samples: VecDeque::with_capacity(BENCHMARK_SAMPLES),
         };
           
         self.benchmarks
@@ -123,11 +322,36 @@ impl TasksSubsystem {
             .or_insert(bench);
     }
 
+    /// Registers the formatter `EndBenchmark` uses for `name` from now on.
+    #[crate::task]
+    async fn register_benchmark(&self, name: &'static str, display: BenchmarkDisplayFn) {
+        self.formatters
+            .lock()
+            .await
+            .insert(name, display);
+    }
+
     #[crate::task]
-    async fn end_benchmark<F>(&self, name: &'static str, end: f64, display: F)
-    where
-        F: Fn(&BenchmarkLog) -> String + Clone + Send + Sync + 'static,
-    {
+    async fn end_benchmark(&self, name: &'static str, end: f64) {
+        let display = self.formatters
+            .read()
+            .await
+            .get(name)
+            .copied()
+            .unwrap_or(format_benchmark);
+
+        self.update_benchmark(name, end, display)
+            .await;
+    }
+
+    /// `end_benchmark` with an explicit formatter instead of the registered one.
+    #[crate::task]
+    async fn end_benchmark_with(&self, name: &'static str, end: f64, display: BenchmarkDisplayFn) {
+        self.update_benchmark(name, end, display)
+            .await;
+    }
+
+    async fn update_benchmark(&self, name: &'static str, end: f64, display: impl Fn(&BenchmarkLog) -> String) {
         self.benchmarks
             .lock()
             .await
@@ -139,6 +363,12 @@ impl TasksSubsystem {
                 task.average = task.run_time / task.runs as f64;
                 task.max = f64::max(task.duration, task.max);
                 task.min = f64::min(task.duration, task.min);
+
+                if task.samples.len() == BENCHMARK_SAMPLES {
+                    task.samples.pop_front();
+                }
+
+                task.samples.push_back(task.duration);
                 task.display = display(task)
             });
     }
@@ -156,4 +386,329 @@ impl TasksSubsystem {
             })
             .collect()
     }
+
+    /// Benchmark displays grouped by `BenchmarkLog::group`, groups ordered by first registration.
+    #[crate::task]
+    async fn get_benchmark_groups(&self) -> Vec<BenchmarkGroup> {
+        let mut groups: IndexMap<&'static str, Vec<Display>> = IndexMap::new();
+
+        for bench in self.benchmarks.read().await.values() {
+            groups
+                .entry(bench.group)
+                .or_default()
+                .push(Display {
+                    name: bench.name.to_string(),
+                    display: bench.display.clone(),
+                });
+        }
+
+        groups.into_iter()
+            .map(|(name, benches)| BenchmarkGroup {
+                name: name.to_string(),
+                benches,
+            })
+            .collect()
+    }
+
+    #[crate::task]
+    async fn get_benchmarks(&self) -> Vec<BenchmarkLog> {
+        self.benchmarks
+            .lock()
+            .await
+            .values()
+            .cloned()
+            .collect()
+    }
+
+    /// Appends to the audit log, dropping the oldest entry once it holds `audit_capacity`.
+    async fn record_audit(&self, entry: AuditEntry) {
+        let mut audit = self.audit
+            .lock()
+            .await;
+
+        if audit.len() >= self.audit_capacity {
+            audit.pop_front();
+        }
+
+        audit.push_back(entry);
+    }
+
+    /// The last `n` finished tasks, oldest first.
+    #[crate::task]
+    async fn get_audit(&self, n: usize) -> Vec<AuditEntry> {
+        let audit = self.audit
+            .read()
+            .await;
+
+        audit
+            .iter()
+            .skip(audit.len().saturating_sub(n))
+            .cloned()
+            .collect()
+    }
+
+    /// The last `n` log lines, oldest first.
+    #[crate::task]
+    async fn get_recent_logs(&self, n: usize) -> Vec<String> {
+        self.log_history.recent(n)
+    }
+
+    /// Writes one row per benchmark with columns `name,runs,avg,min,max,p95`, in seconds.
+    #[crate::task(io)]
+    async fn export_benchmarks_csv(&self, path: String) -> Result<()> {
+        let mut csv = String::from("name,runs,avg,min,max,p95\n");
+
+        for bench in self.benchmarks.read().await.values() {
+            let min = match bench.runs {
+                0 => 0.0,
+                _ => bench.min,
+            };
+
+            csv.push_str(&format!("{},{},{},{},{},{}\n",
+                csv_field(bench.name),
+                bench.runs,
+                bench.average,
+                min,
+                bench.max,
+                bench.percentile(0.95),
+            ));
+        }
+
+        std::fs::write(&path, csv)?;
+
+        Ok(())
+    }
+}
+
+fn csv_field(field: &str) -> String {
+    match field.contains([',', '"', '\n']) {
+        true => format!("\"{}\"", field.replace('"', "\"\"")),
+        false => field.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tasks_subsystem() -> TasksSubsystem {
+        TasksSubsystem {
+            channels: Channels::default(),
+            tasks: ArcLock::new(IndexMap::new()),
+            benchmarks: ArcLock::new(IndexMap::new()),
+            formatters: ArcLock::new(HashMap::new()),
+            log_history: LogHistory::default(),
+            audit: ArcLock::new(VecDeque::new()),
+            audit_capacity: DEFAULT_AUDIT_CAPACITY,
+            max_task_depth: DEFAULT_MAX_TASK_DEPTH,
+        }
+    }
+
+    async fn start(tasks: &TasksSubsystem, id: &str, name: &'static str, depth: usize, parent: Option<&str>) {
+        tasks.start_task(id.to_string(), name, depth, parent.map(String::from), String::from("root"), 0.0)
+            .await;
+    }
+
+    #[tokio::test]
+    async fn task_tree_nests_three_levels() {
+        let tasks = tasks_subsystem();
+
+        start(&tasks, "root", "test::Root", 0, None)
+            .await;
+        start(&tasks, "child", "test::Child", 1, Some("root"))
+            .await;
+        start(&tasks, "sibling", "test::Sibling", 1, Some("root"))
+            .await;
+        start(&tasks, "grandchild", "test::Grandchild", 2, Some("child"))
+            .await;
+        // A parent that's already pruned from the log makes the task a root.
+        start(&tasks, "orphan", "test::Orphan", 3, Some("pruned"))
+            .await;
+
+        let tree = tasks.get_task_tree()
+            .await;
+
+        let roots = tree.iter()
+            .map(|node| node.name)
+            .collect::<Vec<_>>();
+
+        assert_eq!(roots, ["test::Root", "test::Orphan"]);
+
+        let root = &tree[0];
+        let children = root.children
+            .iter()
+            .map(|node| node.name)
+            .collect::<Vec<_>>();
+
+        assert_eq!(children, ["test::Child", "test::Sibling"]);
+        assert_eq!(root.children[0].children.len(), 1);
+        assert_eq!(root.children[0].children[0].name, "test::Grandchild");
+        assert_eq!(root.children[0].children[0].depth, 2);
+        assert!(root.children[1].children.is_empty());
+    }
+
+    #[tokio::test]
+    async fn registered_formatter_and_closure_agree() {
+        fn runs_display(bench: &BenchmarkLog) -> String {
+            format!("{} runs, {:.3} avg", bench.runs, bench.average)
+        }
+
+        let tasks = tasks_subsystem();
+
+        tasks.register_benchmark("test::Registered", runs_display)
+            .await;
+
+        tasks.start_benchmark("test::Registered", "test")
+            .await;
+        tasks.start_benchmark("test::Closure", "test")
+            .await;
+
+        for run in 0..100 {
+            let end = run as f64 * 1e-3;
+
+            tasks.end_benchmark("test::Registered", end)
+                .await;
+            tasks.update_benchmark("test::Closure", end, |bench: &BenchmarkLog| {
+                format!("{} runs, {:.3} avg", bench.runs, bench.average)
+            })
+            .await;
+        }
+
+        let benchmarks = tasks.get_benchmarks()
+            .await;
+
+        assert_eq!(benchmarks[0].runs, 100);
+        assert_eq!(benchmarks[0].display, "100 runs, 0.050 avg");
+        assert_eq!(benchmarks[0].display, benchmarks[1].display);
+    }
+
+    #[tokio::test]
+    async fn registered_formatter_is_used_for_the_display() {
+        let tasks = tasks_subsystem();
+
+        tasks.register_benchmark("test::Frame", |bench| format!("{} runs", bench.runs))
+            .await;
+
+        tasks.start_benchmark("test::Frame", "test")
+            .await;
+        tasks.start_benchmark("test::Unregistered", "test")
+            .await;
+
+        for _ in 0..3 {
+            tasks.end_benchmark("test::Frame", 0.01)
+                .await;
+        }
+
+        tasks.end_benchmark("test::Unregistered", 0.01)
+            .await;
+
+        let displays = tasks.get_benchmark_displays()
+            .await;
+
+        assert_eq!(displays[0].display, "3 runs");
+        assert_eq!(displays[1].display, format_benchmark(&tasks.get_benchmarks().await[1]));
+    }
+
+    #[tokio::test]
+    async fn exported_csv_parses_back() {
+        let tasks = tasks_subsystem();
+
+        tasks.start_benchmark("test::Frame", "test")
+            .await;
+        tasks.start_benchmark("test::Load, Parse", "test")
+            .await;
+
+        for end in [0.25, 0.75] {
+            tasks.end_benchmark("test::Frame", end)
+                .await;
+        }
+
+        tasks.end_benchmark("test::Load, Parse", 0.5)
+            .await;
+
+        let dir = tempfile::tempdir()
+            .unwrap();
+        let path = dir.path().join("benchmarks.csv");
+
+        tasks.export_benchmarks_csv(path.display().to_string())
+            .await
+            .unwrap();
+
+        let csv = std::fs::read_to_string(&path)
+            .unwrap();
+        let rows = csv.lines()
+            .collect::<Vec<_>>();
+
+        assert_eq!(rows, [
+            "name,runs,avg,min,max,p95",
+            "test::Frame,2,0.5,0.25,0.75,0.75",
+            "\"test::Load, Parse\",1,0.5,0.5,0.5,0.5",
+        ]);
+    }
+
+    #[tokio::test]
+    async fn benchmark_groups_follow_first_registration() {
+        let tasks = tasks_subsystem();
+
+        for (name, group) in [
+            ("test::Frame", "engine"),
+            ("test::Load", "assets"),
+            ("test::Render", "engine"),
+        ] {
+            tasks.start_benchmark(name, group)
+                .await;
+        }
+
+        let groups = tasks.get_benchmark_groups()
+            .await
+            .into_iter()
+            .map(|group| {
+                let benches = group.benches
+                    .into_iter()
+                    .map(|bench| bench.name)
+                    .collect::<Vec<_>>();
+
+                (group.name, benches)
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(groups, [
+            (String::from("engine"), vec![String::from("test::Frame"), String::from("test::Render")]),
+            (String::from("assets"), vec![String::from("test::Load")]),
+        ]);
+    }
+
+    #[tokio::test]
+    async fn audit_keeps_the_latest_entries_oldest_first() {
+        let tasks = TasksSubsystem {
+            audit_capacity: 2,
+            max_task_depth: DEFAULT_MAX_TASK_DEPTH,
+            ..tasks_subsystem()
+        };
+
+        for (id, name, success) in [("a", "test::First", true), ("b", "test::Second", false), ("c", "test::Third", true)] {
+            start(&tasks, id, name, 0, None)
+                .await;
+
+            tasks.end_task(TaskEnd {
+                id: id.to_string(),
+                name,
+                end: 0.01,
+                output: None,
+                display: format_task,
+                subsystem: "test",
+                success,
+            })
+            .await;
+        }
+
+        let audit = tasks.get_audit(10)
+            .await
+            .into_iter()
+            .map(|entry| (entry.name, entry.success))
+            .collect::<Vec<_>>();
+
+        assert_eq!(audit, [("test::Second", false), ("test::Third", true)]);
+        assert_eq!(tasks.get_audit(1).await[0].name, "test::Third");
+    }
 }
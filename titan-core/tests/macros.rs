@@ -0,0 +1,5 @@
+#[test]
+fn subsystem_attribute() {
+    let cases = trybuild::TestCases::new();
+    cases.pass("tests/ui/channels-field.rs");
+}
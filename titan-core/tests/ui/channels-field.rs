@@ -0,0 +1,44 @@
+use titan_core::{subsystem, Channels, Subsystem};
+
+pub struct RenamedSubsystem {
+    pub bus: Channels,
+}
+
+#[subsystem(channels = bus)]
+impl RenamedSubsystem {
+    #[task]
+    pub fn ping(&self) -> bool {
+        true
+    }
+}
+
+pub struct Core {
+    pub channels: Channels,
+}
+
+pub struct NestedSubsystem {
+    pub core: Core,
+}
+
+#[subsystem(channels = core.channels)]
+impl NestedSubsystem {
+    #[task]
+    pub fn pong(&self) -> bool {
+        true
+    }
+}
+
+fn main() {
+    let renamed = RenamedSubsystem {
+        bus: Channels::default(),
+    };
+
+    let nested = NestedSubsystem {
+        core: Core {
+            channels: Channels::default(),
+        },
+    };
+
+    let _: Channels = renamed.channels();
+    let _: Channels = nested.channels();
+}
@@ -9,6 +9,16 @@ use syn::{
 };
 use syn::{parse_macro_input, LitStr, Meta, TypeTuple};
 
+// Expansion tracing for debugging the macros, printed only when `TITAN_MACRO_DEBUG` is set
+// so regular builds stay quiet.
+macro_rules! debug_log {
+    ($($arg:tt)*) => {
+        if std::env::var_os("TITAN_MACRO_DEBUG").is_some() {
+            eprintln!($($arg)*);
+        }
+    };
+}
+
 fn is_task_attribute(attr: &syn::Attribute) -> bool {
     attr.path()
         .segments
@@ -17,9 +27,23 @@ fn is_task_attribute(attr: &syn::Attribute) -> bool {
 }
 
 #[proc_macro_attribute]
-pub fn subsystem(_attr: TokenStream, item: TokenStream) -> TokenStream {
+pub fn subsystem(attr: TokenStream, item: TokenStream) -> TokenStream {
     let input = parse_macro_input!(item as ItemImpl);
 
+    // Supported: #[subsystem(channels = field)], where `field` may be nested, e.g. `core.channels`.
+    let mut channels_field: syn::Expr = syn::parse_quote!(channels);
+
+    let attr_parser = syn::meta::parser(|meta| {
+        if meta.path.is_ident("channels") {
+            channels_field = meta.value()?.parse()?;
+            Ok(())
+        } else {
+            Err(meta.error("unsupported argument in #[subsystem] attribute"))
+        }
+    });
+
+    parse_macro_input!(attr with attr_parser);
+
     let path = match &*input.self_ty {
         Type::Path(type_path) => {
             let path_str = type_path.path.segments.iter()
@@ -40,6 +64,17 @@ pub fn subsystem(_attr: TokenStream, item: TokenStream) -> TokenStream {
             _ => false,
         });
 
+    // Lifecycle hooks written as inherent methods are forwarded to the Subsystem impl.
+    let lifecycle_hooks = ["on_start", "on_stop"]
+        .into_iter()
+        .filter_map(|hook| {
+            other_items.iter().find_map(|item| match item {
+                ImplItem::Fn(method) if method.sig.ident == hook => Some(method.sig.clone()),
+                _ => None,
+            })
+        })
+        .collect::<Vec<_>>();
+
     // Process each task-annotated method, generating the associated tasks and
     // stripping off the #[task] attribute from the method.
     let mut generated_tasks = Vec::new();
@@ -75,24 +110,31 @@ pub fn subsystem(_attr: TokenStream, item: TokenStream) -> TokenStream {
         ..input
     };
 
-    let titan_core_path = get_crate_path("titan_core")
+    let titan_core_path = get_titan_core_path()
         .expect("Failed to find titan_core");
     
     let self_ty = &updated_impl.self_ty;
 
     let subsystem_name = get_subsystem_name(self_ty);
 
+    let lifecycle_fns = lifecycle_hooks
+        .iter()
+        .map(|hook| build_lifecycle_function(self_ty, &titan_core_path, hook));
+
     let expanded = quote! {
         #updated_impl
 
+        #[#titan_core_path::async_trait]
         impl #titan_core_path::Subsystem for #self_ty {
             fn name() -> &'static str {
                 #subsystem_name
             }
             
             fn channels(&self) -> #titan_core_path::Channels {
-                self.channels.clone()
+                self.#channels_field.clone()
             }
+
+            #(#lifecycle_fns)*
         }
             
         #(#generated_tasks)*
@@ -101,6 +143,25 @@ pub fn subsystem(_attr: TokenStream, item: TokenStream) -> TokenStream {
     TokenStream::from(expanded)
 }
 
+fn build_lifecycle_function(
+    self_ty: &Type,
+    titan_core_path: &proc_macro2::TokenStream,
+    hook: &syn::Signature,
+) -> proc_macro2::TokenStream {
+    let hook_name = &hook.ident;
+
+    let await_hook = match hook.asyncness.is_some() {
+        true => quote! { .await },
+        false => quote! {},
+    };
+
+    quote! {
+        async fn #hook_name(&mut self) -> #titan_core_path::Result<()> {
+            <#self_ty>::#hook_name(self) #await_hook
+        }
+    }
+}
+
 #[proc_macro_attribute]
 pub fn task(_attr: TokenStream, item: TokenStream) -> TokenStream {
     // Return the input tokens unmodified
@@ -118,15 +179,23 @@ fn generate_task(
     build_task(self_ty, &task_data, module_path)
 }
 
-// Supported: #[task(benchmark, io)]
+// Supported: #[task(benchmark, io, stream, idempotent, cache, display_output, cost = N, group = "name", warn_after = "16ms", validate = fn_name)]
 #[derive(Debug)]
 struct TaskMacroAttributes {
     pub benchmark: bool,
     pub io: bool,
+    pub stream: bool,
+    pub idempotent: bool,
+    pub cache: bool,
+    pub display_output: bool,
+    pub cost: Option<syn::LitInt>,
+    pub group: Option<LitStr>,
+    pub warn_after: Option<LitStr>,
+    pub validate: Option<syn::Ident>,
 }
 
 fn extract_macro_attributes(macro_attributes: &Attribute) -> TaskMacroAttributes {
-    eprintln!("extract macro attributes");
+    debug_log!("extract macro attributes");
 
     let last_path_segment = macro_attributes.path()
         .segments
@@ -136,7 +205,7 @@ fn extract_macro_attributes(macro_attributes: &Attribute) -> TaskMacroAttributes
     let nested_meta = if last_path_segment.ident == "task" {
         match &macro_attributes.meta {
             Meta::List(nested_meta) => {
-                eprintln!("Nested meta found");
+                debug_log!("Nested meta found");
                 Some(nested_meta.clone())
             },
             _ => None
@@ -149,6 +218,14 @@ fn extract_macro_attributes(macro_attributes: &Attribute) -> TaskMacroAttributes
         Some(_) => {
             let mut benchmark = false;
             let mut io = false;
+            let mut stream = false;
+            let mut idempotent = false;
+            let mut cache = false;
+            let mut display_output = false;
+            let mut cost = None;
+            let mut group = None;
+            let mut warn_after = None;
+            let mut validate = None;
 
             // If `#[task]` has no parentheses, `parse_nested_meta` won't call the closure.
             // If `#[task(...)]` has arguments, the closure is called for each nested meta item.
@@ -160,17 +237,41 @@ fn extract_macro_attributes(macro_attributes: &Attribute) -> TaskMacroAttributes
                 } else if meta.path.is_ident("io") {
                     io = true;
                     Ok(())
+                } else if meta.path.is_ident("stream") {
+                    stream = true;
+                    Ok(())
+                } else if meta.path.is_ident("idempotent") {
+                    idempotent = true;
+                    Ok(())
+                } else if meta.path.is_ident("cache") {
+                    cache = true;
+                    Ok(())
+                } else if meta.path.is_ident("display_output") {
+                    display_output = true;
+                    Ok(())
+                } else if meta.path.is_ident("cost") {
+                    cost = Some(meta.value()?.parse()?);
+                    Ok(())
+                } else if meta.path.is_ident("group") {
+                    group = Some(meta.value()?.parse()?);
+                    Ok(())
+                } else if meta.path.is_ident("warn_after") {
+                    warn_after = Some(meta.value()?.parse()?);
+                    Ok(())
+                } else if meta.path.is_ident("validate") {
+                    validate = Some(meta.value()?.parse()?);
+                    Ok(())
                 } else {
-                    eprintln!("Error parsing nested meta for task attribute");
+                    debug_log!("Error parsing nested meta for task attribute");
                     Err(meta.error("unsupported argument in #[task] attribute"))
                 }
             });
             
-            TaskMacroAttributes { benchmark, io }
+            TaskMacroAttributes { benchmark, io, stream, idempotent, cache, display_output, cost, group, warn_after, validate }
         },
         None => {
-            eprintln!("No nested meta found");
-            TaskMacroAttributes { benchmark: false, io: false }
+            debug_log!("No nested meta found");
+            TaskMacroAttributes { benchmark: false, io: false, stream: false, idempotent: false, cache: false, display_output: false, cost: None, group: None, warn_after: None, validate: None }
         },
     }
 }
@@ -185,6 +286,14 @@ struct TaskFunctionData {
     pub macro_attributes: TaskMacroAttributes,
     pub generics: syn::Generics,
     pub is_mut: bool,
+    pub stream: Option<StreamParam>,
+}
+
+// The trailing `TaskStream<Item>` parameter of a `#[task(stream)]` method.
+#[derive(Debug)]
+struct StreamParam {
+    pub name: syn::Pat,
+    pub item_type: syn::Type,
 }
 
 fn extract_task_function_data(method: ImplItemFn, macro_attributes: Attribute) -> TaskFunctionData {
@@ -195,15 +304,20 @@ fn extract_task_function_data(method: ImplItemFn, macro_attributes: Attribute) -
     let task_output = method.sig.output;
     let task_generics = method.sig.generics;
 
-    eprintln!();
-    eprintln!("{}", task_name);
-    eprintln!("extract task data");
+    debug_log!();
+    debug_log!("{}", task_name);
+    debug_log!("extract task data");
 
-    let (task_input_types, task_input_names, task_mutability) = extract_params(task_input);
+    let (mut task_input_types, mut task_input_names, task_mutability) = extract_params(task_input);
     let task_output_type = extract_output(task_output);
 
     let macro_attributes = extract_macro_attributes(&macro_attributes);
 
+    let task_stream = match macro_attributes.stream {
+        true => Some(extract_stream_param(&mut task_input_types, &mut task_input_names)),
+        false => None,
+    };
+
     TaskFunctionData {
         name: task_name,
         input_types: task_input_types,
@@ -213,11 +327,42 @@ fn extract_task_function_data(method: ImplItemFn, macro_attributes: Attribute) -
         macro_attributes,
         generics: task_generics,
         is_mut: task_mutability,
+        stream: task_stream,
     }
 }
 
+fn extract_stream_param(input_types: &mut Vec<syn::Type>, input_names: &mut Vec<syn::Pat>) -> StreamParam {
+    debug_log!("extract stream param");
+
+    let (Some(stream_type), Some(name)) = (input_types.pop(), input_names.pop()) else {
+        panic!("#[task(stream)] expects a trailing `TaskStream<Item>` parameter!");
+    };
+
+    let item_type = match &stream_type {
+        syn::Type::Path(type_path) => {
+            let last_seg = type_path.path.segments.last().unwrap();
+
+            match &last_seg.arguments {
+                syn::PathArguments::AngleBracketed(args) => args.args
+                    .iter()
+                    .find_map(|arg| match arg {
+                        syn::GenericArgument::Type(ty) => Some(ty.clone()),
+                        _ => None,
+                    }),
+                _ => None,
+            }
+        }
+        _ => None,
+    };
+
+    let item_type = item_type
+        .expect("#[task(stream)] expects a trailing `TaskStream<Item>` parameter!");
+
+    StreamParam { name, item_type }
+}
+
 fn extract_params(task_params: Punctuated<FnArg, Comma>) -> (Vec<syn::Type>, Vec<syn::Pat>, bool) {
-    eprintln!("extract params");
+    debug_log!("extract params");
     
     let mut task_call_param_types = Vec::new();
     let mut task_call_param_names = Vec::new();
@@ -252,7 +397,7 @@ fn extract_params(task_params: Punctuated<FnArg, Comma>) -> (Vec<syn::Type>, Vec
 }
 
 fn extract_output(task_output: ReturnType) -> syn::Type {
-    eprintln!("extract output");
+    debug_log!("extract output");
     
     match task_output {
         syn::ReturnType::Type(_, ty) => {
@@ -273,7 +418,7 @@ fn build_task(
     task_data: &TaskFunctionData,
     module_path: String,
 ) -> proc_macro2::TokenStream {
-    eprintln!("build task");
+    debug_log!("build task");
     
     let task_struct = build_task_struct(task_data);    
     let task_impl = build_task_impl(subsystem_type, task_data, module_path);
@@ -285,7 +430,7 @@ fn build_task(
 }
 
 fn build_task_struct(task_data: &TaskFunctionData) -> proc_macro2::TokenStream {
-    eprintln!("build task struct");
+    debug_log!("build task struct");
 
     let task_name = get_task_name(&task_data.name.to_string());
     let generics = &task_data.generics;
@@ -326,20 +471,26 @@ fn build_task_impl(
     task_data: &TaskFunctionData,
     module_path: String,
 ) -> proc_macro2::TokenStream {
-    eprintln!("build task impl");
+    debug_log!("build task impl");
      
-    let titan_core_path = get_crate_path("titan_core")
+    let titan_core_path = get_titan_core_path()
         .expect("Failed to find titan_core!");
     
     let task_name = get_task_name(&task_data.name.to_string());
     let task_input_types = &task_data.input_types;
     let task_input_names = &task_data.input_names;
-    let task_trait = build_task_trait(&titan_core_path.to_string(), task_data);
+    let task_trait = build_task_trait(&titan_core_path, task_data);
     let output_type = &task_data.output_type;
     let id_fn = build_id_functions(task_data, module_path);
     let benchmark_fn = build_task_benchmark_function(task_data);
     let io_fn = build_task_io_function(task_data);
-    let execute_fn = build_task_execute_function(subsystem_type, task_data);
+    let cost_fn = build_task_cost_function(task_data);
+    let idempotent_fn = build_task_idempotent_function(task_data);
+    let group_fn = build_task_group_function(task_data);
+    let display_output_fn = build_task_display_output_function(task_data);
+    let warn_after_fn = build_task_warn_after_function(task_data);
+    let validate_fn = build_task_validate_function(&titan_core_path, subsystem_type, task_data);
+    let execute_fn = build_task_execute_function(&titan_core_path, subsystem_type, task_data);
     let generics = &task_data.generics;
     let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
     let event_name = format_ident!("On{}", task_name);
@@ -365,6 +516,12 @@ fn build_task_impl(
             #id_fn
             #io_fn
             #benchmark_fn
+            #cost_fn
+            #idempotent_fn
+            #group_fn
+            #display_output_fn
+            #warn_after_fn
+            #validate_fn
 
             fn inputs(&self) -> Self::Inputs {
                 #inputs_tuple
@@ -391,13 +548,13 @@ fn build_task_impl(
     }
 }
 
-fn build_task_trait(titan_core_path: &str, task_data: &TaskFunctionData) -> proc_macro2::TokenStream {
-    if task_data.is_mut {
-        let path = syn::Ident::new(titan_core_path, proc_macro2::Span::call_site());
-        quote! { #path::MutableTask }
+fn build_task_trait(titan_core_path: &proc_macro2::TokenStream, task_data: &TaskFunctionData) -> proc_macro2::TokenStream {
+    if task_data.stream.is_some() {
+        quote! { #titan_core_path::StreamTask }
+    } else if task_data.is_mut {
+        quote! { #titan_core_path::MutableTask }
     } else {
-        let path = syn::Ident::new(titan_core_path, proc_macro2::Span::call_site());
-        quote! { #path::ImmutableTask }
+        quote! { #titan_core_path::ImmutableTask }
     }
 }
 
@@ -419,7 +576,7 @@ fn build_id_functions(task_data: &TaskFunctionData, module_path: String) -> proc
 }
 
 fn build_task_benchmark_function(task_data: &TaskFunctionData) -> proc_macro2::TokenStream {
-    eprintln!("build task benchmark fn");
+    debug_log!("build task benchmark fn");
  
     match task_data.macro_attributes.benchmark {
         true => quote! {
@@ -432,7 +589,7 @@ fn build_task_benchmark_function(task_data: &TaskFunctionData) -> proc_macro2::T
 }
 
 fn build_task_io_function(task_data: &TaskFunctionData) -> proc_macro2::TokenStream { 
-    eprintln!("build task io fn");
+    debug_log!("build task io fn");
     
     match task_data.macro_attributes.io {
         true => quote! {
@@ -444,11 +601,117 @@ fn build_task_io_function(task_data: &TaskFunctionData) -> proc_macro2::TokenStr
     }
 }
 
+fn build_task_cost_function(task_data: &TaskFunctionData) -> proc_macro2::TokenStream {
+    debug_log!("build task cost fn");
+
+    match &task_data.macro_attributes.cost {
+        Some(cost) => quote! {
+            fn cost() -> u32 {
+                #cost
+            }
+        },
+        None => quote! {},
+    }
+}
+
+fn build_task_idempotent_function(task_data: &TaskFunctionData) -> proc_macro2::TokenStream {
+    debug_log!("build task idempotent fn");
+
+    match task_data.macro_attributes.idempotent {
+        true => quote! {
+            fn idempotent() -> bool {
+                true
+            }
+        },
+        false => quote! {},
+    }
+}
+
+fn build_task_group_function(task_data: &TaskFunctionData) -> proc_macro2::TokenStream {
+    debug_log!("build task group fn");
+
+    match &task_data.macro_attributes.group {
+        Some(group) => quote! {
+            fn group() -> &'static str {
+                #group
+            }
+        },
+        None => quote! {},
+    }
+}
+
+// Outputs are formatted with `Debug`, which covers `Result` and `Option` returns too.
+fn build_task_display_output_function(task_data: &TaskFunctionData) -> proc_macro2::TokenStream {
+    debug_log!("build task display output fn");
+
+    match task_data.macro_attributes.display_output {
+        true => quote! {
+            fn display_output(output: &Self::Output) -> Option<String> {
+                Some(format!("{:?}", output))
+            }
+        },
+        false => quote! {},
+    }
+}
+
+fn build_task_warn_after_function(task_data: &TaskFunctionData) -> proc_macro2::TokenStream {
+    debug_log!("build task warn after fn");
+
+    match &task_data.macro_attributes.warn_after {
+        Some(warn_after) => {
+            let micros = parse_duration_micros(&warn_after.value())
+                .unwrap_or_else(|| panic!("Invalid warn_after duration `{}`, expected e.g. \"16ms\"", warn_after.value()));
+
+            quote! {
+                fn warn_after() -> Option<std::time::Duration> {
+                    Some(std::time::Duration::from_micros(#micros))
+                }
+            }
+        },
+        None => quote! {},
+    }
+}
+
+// Parses `<integer><unit>` with unit `us`, `ms` or `s` into microseconds.
+fn parse_duration_micros(duration: &str) -> Option<u64> {
+    let duration = duration.trim();
+    let split = duration.find(|c: char| !c.is_ascii_digit())?;
+    let (value, unit) = duration.split_at(split);
+    let value = value.parse::<u64>().ok()?;
+
+    match unit {
+        "us" => Some(value),
+        "ms" => value.checked_mul(1_000),
+        "s" => value.checked_mul(1_000_000),
+        _ => None,
+    }
+}
+
+fn build_task_validate_function(
+    titan_core_path: &proc_macro2::TokenStream,
+    subsystem_type: &syn::Type,
+    task_data: &TaskFunctionData,
+) -> proc_macro2::TokenStream {
+    debug_log!("build task validate fn");
+
+    let task_args = &task_data.input_names;
+
+    match &task_data.macro_attributes.validate {
+        Some(validator) => quote! {
+            fn validate(&self) -> #titan_core_path::Result<()> {
+                <#subsystem_type>::#validator(#(&self.#task_args),*)
+            }
+        },
+        None => quote! {},
+    }
+}
+
 fn build_task_execute_function(
+    titan_core_path: &proc_macro2::TokenStream,
     subsystem_type: &syn::Type,
     task_data: &TaskFunctionData,
 ) -> proc_macro2::TokenStream {
-    eprintln!("build task execute fn");
+    debug_log!("build task execute fn");
         
     let task_name = &task_data.name;
     
@@ -457,7 +720,11 @@ fn build_task_execute_function(
             quote! { self.#name }
         });
     
-    let execute_call = quote! { subsystem.#task_name(#(#task_args),*) };
+    let stream_arg = task_data.stream
+        .as_ref()
+        .map(|stream| &stream.name);
+
+    let execute_call = quote! { subsystem.#task_name(#(#task_args,)* #stream_arg) };
     
     // Determine if `.await` should be appended
     let await_execute = if task_data.is_async {
@@ -466,6 +733,54 @@ fn build_task_execute_function(
         quote! {}
     };
         
+    if let Some(stream) = &task_data.stream {
+        let stream_name = &stream.name;
+        let item_type = &stream.item_type;
+
+        if task_data.is_mut {
+            panic!("#[task(stream)] only supports `&self` methods!");
+        }
+
+        if task_data.macro_attributes.cache {
+            panic!("#[task(cache)] doesn't support stream tasks!");
+        }
+
+        return quote! {
+            type Item = #item_type;
+
+            async fn execute(self, subsystem: &Self::Subsystem, #stream_name: #titan_core_path::TaskStream<Self::Item>) -> Self::Output {
+                #execute_call #await_execute
+            }
+        };
+    }
+
+    // Cached tasks look their inputs up in `Channels::task_cache` before running the body.
+    // Requires `Inputs: Hash + Eq` and `Output: Clone`.
+    if task_data.macro_attributes.cache {
+        if task_data.is_mut {
+            panic!("#[task(cache)] only supports `&self` methods!");
+        }
+
+        return quote! {
+            async fn execute(self, subsystem: &Self::Subsystem) -> Self::Output {
+                let cache = #titan_core_path::Subsystem::channels(subsystem)
+                    .task_cache::<Self>();
+
+                let inputs = #titan_core_path::Task::inputs(&self);
+
+                if let Some(output) = cache.get(&inputs) {
+                    return output.clone();
+                }
+
+                let output = #execute_call #await_execute;
+
+                cache.insert(inputs, output.clone());
+
+                output
+            }
+        };
+    }
+
     // Conditionally generate the `execute_mut` function if `is_mut` is true
     if task_data.is_mut {
         quote! {
@@ -513,6 +828,12 @@ fn get_task_name(function_name: &str) -> syn::Ident {
     Ident::new(&name, Span::call_site())
 }
 
+fn get_titan_core_path() -> anyhow::Result<proc_macro2::TokenStream, syn::Error> {
+    // Apps depending only on `titan` reach the core crate through its re-export.
+    get_crate_path("titan_core")
+        .or_else(|_| get_crate_path("titan").map(|titan| quote!(#titan::titan_core)))
+}
+
 fn get_crate_path(name: &str) -> anyhow::Result<proc_macro2::TokenStream, syn::Error> {
     match crate_name(name) {
         Ok(FoundCrate::Itself) => Ok(quote!(crate)),
@@ -521,7 +842,7 @@ fn get_crate_path(name: &str) -> anyhow::Result<proc_macro2::TokenStream, syn::E
             Ok(quote!(#ident))
         }
         Err(err) => {
-            eprintln!("Error finding crate: {}", err);
+            debug_log!("Error finding crate: {}", err);
             Err(syn::Error::new(
                 proc_macro2::Span::call_site(),
                 format!("Crate '{}' not found", name),
@@ -1,7 +1,36 @@
-use titan::info;
+use titan::{info, titan_core::{anyhow, tasks::TasksSubsystem, TaskStream}, Channels, Subsystem, SubsystemRef};
 
 struct Viewer;
 
+pub struct GreeterSubsystem {
+    pub channels: Channels,
+}
+
+#[titan::subsystem]
+impl GreeterSubsystem {
+
+    fn check_name(name: &str) -> titan::Result<()> {
+        match name.is_empty() {
+            true => Err(anyhow!("Greeter: Name must not be empty")),
+            false => Ok(()),
+        }
+    }
+
+    #[titan::task(validate = check_name)]
+    fn greet(&self, name: String) {
+        info!("Hello, {}!", name);
+    }
+
+    #[titan::task(stream)]
+    async fn greet_all(&self, names: Vec<String>, greetings: TaskStream<String>) {
+        for name in names {
+            if greetings.send(format!("Hello, {}!", name)).await.is_err() {
+                break;
+            }
+        }
+    }
+}
+
 #[titan::async_trait]
 impl titan::App for Viewer {
     async fn init(&self) -> titan::Result<()> {
@@ -13,6 +42,27 @@ impl titan::App for Viewer {
         info!("Shutdown!");
         Ok(())
     }
+
+    async fn register_subsystems(&self, channels: &mut Channels) -> titan::Result<()> {
+        let (greeter_ref, greeter_receiver) = SubsystemRef::<GreeterSubsystem>::new();
+
+        channels.add(greeter_ref)
+            .await;
+
+        GreeterSubsystem::start(
+            GreeterSubsystem {
+                channels: channels.clone(),
+            },
+            greeter_receiver,
+            channels.get::<TasksSubsystem>(),
+        );
+
+        channels
+            .get::<GreeterSubsystem>()
+            .send(Greet { name: "Titan".to_string() });
+
+        Ok(())
+    }
 }
 
 pub fn run() -> titan::Result<()> {
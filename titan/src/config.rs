@@ -0,0 +1,375 @@
+#[cfg(feature = "graphics")]
+use crate::graphics::GraphicsConfig;
+#[cfg(feature = "terminal")]
+use crate::terminal::Theme;
+use serde::Deserialize;
+#[cfg(feature = "metrics")]
+use std::net::SocketAddr;
+use std::{
+    path::{Path, PathBuf},
+    time::Duration,
+};
+use titan_core::{anyhow, logs, tasks, toml, Result};
+
+/// Config file `TitanConfig::load` looks for in the working directory.
+pub const CONFIG_FILE: &str = "titan.toml";
+
+/// Where the metrics subsystem serves `/metrics` unless configured otherwise.
+#[cfg(feature = "metrics")]
+pub const DEFAULT_METRICS_ADDRESS: SocketAddr = SocketAddr::V4(std::net::SocketAddrV4::new(std::net::Ipv4Addr::LOCALHOST, 9184));
+
+/// Runtime settings for the tokio runtime `run` builds. `None` keeps tokio's default.
+#[derive(Debug, Clone, Default)]
+pub struct RunConfig {
+    /// Async worker threads, defaults to the number of logical CPUs.
+    pub worker_threads: Option<usize>,
+    /// Upper bound on the blocking pool that `io` tasks run on.
+    pub max_blocking_threads: Option<usize>,
+    /// Log newline-delimited JSON instead of the human readable format.
+    #[cfg(feature = "json-logs")]
+    pub json_logs: bool,
+}
+
+/// Optional subsystems `start_subsystems` starts. Engine, tasks and resources always run.
+/// Subsystems whose feature is disabled are never started.
+#[derive(Debug, Clone, Copy)]
+pub struct EngineSubsystems {
+    pub graphics: bool,
+    pub terminal: bool,
+}
+
+impl EngineSubsystems {
+    /// Every subsystem built in.
+    pub const ALL: Self = Self {
+        graphics: cfg!(feature = "graphics"),
+        terminal: cfg!(feature = "terminal"),
+    };
+    /// Neither a GPU nor a TTY, as used by `run_headless`.
+    pub const HEADLESS: Self = Self { graphics: false, terminal: false };
+}
+
+impl Default for EngineSubsystems {
+    fn default() -> Self {
+        Self::ALL
+    }
+}
+
+/// Engine configuration, returned by `App::config` and applied when `run` starts
+/// the runtime and subsystems.
+#[derive(Clone)]
+pub struct TitanConfig {
+    pub runtime: RunConfig,
+    #[cfg(feature = "graphics")]
+    pub graphics: GraphicsConfig,
+    /// Minimum frame time, `None` leaves the frame rate uncapped.
+    pub frame_limit: Option<Duration>,
+    /// Directory the resource subsystem scans and watches, relative to the working directory.
+    pub assets_dir: PathBuf,
+    /// Tracing filter used when `RUST_LOG` isn't set.
+    pub log_level: String,
+    /// Recent log lines kept for `GetRecentLogs`.
+    pub log_history: usize,
+    /// Deepest nested task dispatch, see `tasks::TasksSubsystem::max_task_depth`.
+    pub max_task_depth: usize,
+    /// Finished tasks kept for `GetAudit`.
+    pub audit_capacity: usize,
+    /// Seed for `RandomSubsystem`, random when `None`.
+    pub random_seed: Option<u64>,
+    /// Minimum time between terminal redraws.
+    pub refresh_interval: Duration,
+    pub subsystems: EngineSubsystems,
+    /// Terminal colors on start, 't' cycles through the others.
+    #[cfg(feature = "terminal")]
+    pub theme: Theme,
+    /// Per-level log colors in the terminal, 'c' toggles them. `NO_COLOR` turns them off regardless.
+    #[cfg(feature = "terminal")]
+    pub color: bool,
+    /// Address the metrics subsystem serves Prometheus metrics on.
+    #[cfg(feature = "metrics")]
+    pub metrics_address: SocketAddr,
+    /// File this config was read from. Loading happens before logging is set up, so
+    /// `engine::Init` logs it instead.
+    pub source: Option<PathBuf>,
+}
+
+impl Default for TitanConfig {
+    fn default() -> Self {
+        Self {
+            runtime: RunConfig::default(),
+            #[cfg(feature = "graphics")]
+            graphics: GraphicsConfig::default(),
+            frame_limit: None,
+            assets_dir: PathBuf::from("/resources"),
+            log_level: String::from("info"),
+            log_history: logs::DEFAULT_LOG_HISTORY,
+            max_task_depth: tasks::DEFAULT_MAX_TASK_DEPTH,
+            audit_capacity: tasks::DEFAULT_AUDIT_CAPACITY,
+            random_seed: None,
+            refresh_interval: Duration::from_millis(100),
+            subsystems: EngineSubsystems::default(),
+            #[cfg(feature = "terminal")]
+            theme: Theme::default(),
+            #[cfg(feature = "terminal")]
+            color: true,
+            #[cfg(feature = "metrics")]
+            metrics_address: DEFAULT_METRICS_ADDRESS,
+            source: None,
+        }
+    }
+}
+
+/// `titan.toml` layout. Omitted fields keep the `TitanConfig` default.
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+struct ConfigFile {
+    assets_dir: Option<PathBuf>,
+    log_level: Option<String>,
+    log_history: Option<usize>,
+    /// Frames per second, 0 for uncapped.
+    frame_cap: Option<u32>,
+    worker_threads: Option<usize>,
+    max_blocking_threads: Option<usize>,
+    max_task_depth: Option<usize>,
+    random_seed: Option<u64>,
+    refresh_interval_ms: Option<u64>,
+    /// Name of a terminal theme preset, e.g. `"light"`.
+    #[cfg_attr(not(feature = "terminal"), allow(dead_code))]
+    theme: Option<String>,
+    #[cfg_attr(not(feature = "terminal"), allow(dead_code))]
+    color: Option<bool>,
+    /// Parsed even without the `graphics` feature so shared config files still load.
+    graphics: GraphicsFile,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+#[cfg_attr(not(feature = "graphics"), allow(dead_code))]
+struct GraphicsFile {
+    sample_count: Option<u32>,
+    /// One of `fifo`, `fifo_relaxed`, `mailbox`, `immediate`, `auto_vsync`, `auto_no_vsync`.
+    present_mode: Option<String>,
+    /// Comma separated, in the format of `WGPU_BACKEND`, e.g. `"vulkan,metal"`.
+    backends: Option<String>,
+}
+
+impl TitanConfig {
+    /// Loads `titan.toml` from the working directory, or the defaults if there is none.
+    pub fn load() -> Result<Self> {
+        let path = std::env::current_dir()?.join(CONFIG_FILE);
+
+        match path.is_file() {
+            true => Self::from_file(&path),
+            false => Ok(Self::default()),
+        }
+    }
+
+    /// Reads a `titan.toml`, using the defaults for anything it leaves out.
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|err| anyhow!("Config: Failed to read {}: {}", path.display(), err))?;
+
+        let file = toml::from_str::<ConfigFile>(&contents)
+            .map_err(|err| anyhow!("Config: Failed to parse {}: {}", path.display(), err))?;
+
+        let config = Self {
+            source: Some(path.to_path_buf()),
+            ..Self::default()
+        };
+
+        config.with_file(file)
+    }
+
+    fn with_file(mut self, file: ConfigFile) -> Result<Self> {
+        if let Some(assets_dir) = file.assets_dir {
+            self.assets_dir = assets_dir;
+        }
+
+        if let Some(log_level) = file.log_level {
+            self.log_level = log_level;
+        }
+
+        if let Some(log_history) = file.log_history {
+            self.log_history = log_history;
+        }
+
+        if let Some(frame_cap) = file.frame_cap {
+            self.frame_limit = match frame_cap {
+                0 => None,
+                fps => Some(Duration::from_secs_f64(1.0 / fps as f64)),
+            };
+        }
+
+        if let Some(max_task_depth) = file.max_task_depth {
+            self.max_task_depth = max_task_depth;
+        }
+
+        self.random_seed = file.random_seed
+            .or(self.random_seed);
+
+        if let Some(refresh_interval_ms) = file.refresh_interval_ms {
+            self.refresh_interval = Duration::from_millis(refresh_interval_ms);
+        }
+
+        self.runtime.worker_threads = file.worker_threads
+            .or(self.runtime.worker_threads);
+
+        self.runtime.max_blocking_threads = file.max_blocking_threads
+            .or(self.runtime.max_blocking_threads);
+
+        #[cfg(feature = "terminal")]
+        if let Some(theme) = file.theme {
+            self.theme = Theme::from_name(&theme)
+                .ok_or_else(|| anyhow!("Config: Unknown theme `{}`", theme))?;
+        }
+
+        #[cfg(feature = "terminal")]
+        if let Some(color) = file.color {
+            self.color = color;
+        }
+
+        #[cfg(feature = "graphics")]
+        self.with_graphics_file(file.graphics)?;
+
+        Ok(self)
+    }
+
+    #[cfg(feature = "graphics")]
+    fn with_graphics_file(&mut self, file: GraphicsFile) -> Result<()> {
+        if let Some(sample_count) = file.sample_count {
+            self.graphics.sample_count = sample_count;
+        }
+
+        if let Some(present_mode) = file.present_mode {
+            self.graphics.present_mode = parse_present_mode(&present_mode)?;
+        }
+
+        if let Some(backends) = file.backends {
+            self.graphics.backends = wgpu::util::parse_backends_from_comma_list(&backends.to_lowercase());
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(feature = "graphics")]
+fn parse_present_mode(present_mode: &str) -> Result<wgpu::PresentMode> {
+    match present_mode.to_lowercase().as_str() {
+        "fifo" => Ok(wgpu::PresentMode::Fifo),
+        "fifo_relaxed" => Ok(wgpu::PresentMode::FifoRelaxed),
+        "mailbox" => Ok(wgpu::PresentMode::Mailbox),
+        "immediate" => Ok(wgpu::PresentMode::Immediate),
+        "auto_vsync" => Ok(wgpu::PresentMode::AutoVsync),
+        "auto_no_vsync" => Ok(wgpu::PresentMode::AutoNoVsync),
+        _ => Err(anyhow!("Config: Unknown present mode `{}`", present_mode)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn write_config(contents: &str) -> (tempfile::TempDir, PathBuf) {
+        let dir = tempfile::tempdir()
+            .unwrap();
+        let path = dir.path().join(CONFIG_FILE);
+
+        fs::write(&path, contents)
+            .unwrap();
+
+        (dir, path)
+    }
+
+    #[test]
+    fn file_overrides_only_what_it_sets() {
+        let (_dir, path) = write_config(r#"
+            assets_dir = "assets"
+            frame_cap = 50
+            worker_threads = 2
+            refresh_interval_ms = 250
+        "#);
+
+        let config = TitanConfig::from_file(&path)
+            .unwrap();
+        let defaults = TitanConfig::default();
+
+        assert_eq!(config.assets_dir, PathBuf::from("assets"));
+        assert_eq!(config.frame_limit, Some(Duration::from_millis(20)));
+        assert_eq!(config.runtime.worker_threads, Some(2));
+        assert_eq!(config.refresh_interval, Duration::from_millis(250));
+        assert_eq!(config.source, Some(path));
+
+        assert_eq!(config.log_level, defaults.log_level);
+        assert_eq!(config.max_task_depth, defaults.max_task_depth);
+        assert_eq!(config.runtime.max_blocking_threads, None);
+    }
+
+    #[test]
+    fn zero_frame_cap_is_uncapped() {
+        let (_dir, path) = write_config("frame_cap = 0");
+
+        assert_eq!(TitanConfig::from_file(&path).unwrap().frame_limit, None);
+    }
+
+    #[test]
+    fn unknown_keys_are_rejected() {
+        let (_dir, path) = write_config("frame_rate = 60");
+
+        assert!(TitanConfig::from_file(&path).is_err());
+    }
+
+    #[cfg(feature = "graphics")]
+    #[test]
+    fn graphics_table_is_applied() {
+        let (_dir, path) = write_config(r#"
+            [graphics]
+            sample_count = 4
+            present_mode = "Mailbox"
+            backends = "vulkan,gl"
+        "#);
+
+        let config = TitanConfig::from_file(&path)
+            .unwrap();
+
+        assert_eq!(config.graphics.sample_count, 4);
+        assert_eq!(config.graphics.present_mode, wgpu::PresentMode::Mailbox);
+        assert_eq!(config.graphics.backends, wgpu::Backends::VULKAN | wgpu::Backends::GL);
+    }
+
+    #[cfg(feature = "graphics")]
+    #[test]
+    fn unknown_present_mode_is_rejected() {
+        let (_dir, path) = write_config("graphics = { present_mode = \"tearing\" }");
+
+        let Err(err) = TitanConfig::from_file(&path) else {
+            panic!("An unknown present mode was accepted");
+        };
+
+        assert!(err.to_string().contains("tearing"));
+    }
+
+    #[test]
+    fn all_subsystems_are_the_built_in_ones() {
+        let all = EngineSubsystems::ALL;
+
+        assert_eq!(all.graphics, cfg!(feature = "graphics"));
+        assert_eq!(all.terminal, cfg!(feature = "terminal"));
+    }
+
+    #[cfg(not(feature = "graphics"))]
+    #[test]
+    fn graphics_table_loads_without_the_graphics_feature() {
+        let (_dir, path) = write_config(r#"
+            frame_cap = 30
+
+            [graphics]
+            sample_count = 4
+            present_mode = "mailbox"
+        "#);
+
+        let config = TitanConfig::from_file(&path)
+            .unwrap();
+
+        assert_eq!(config.frame_limit, Some(Duration::from_secs_f64(1.0 / 30.0)));
+    }
+}
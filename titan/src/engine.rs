@@ -1,17 +1,83 @@
-use crate::{
-    graphics::{self, GraphicsSubsystem},
-    terminal::{self, TerminalSubsystem},
-    App, Channels,
-};
+#[cfg(feature = "graphics")]
+use crate::graphics::{self, GraphicsSubsystem};
+#[cfg(feature = "terminal")]
+use crate::terminal::{self, TerminalSubsystem};
+use crate::{time::{self as clock, TimeSubsystem}, App, Channels, EngineSubsystems};
 use titan_assets::{assets, ResourceSubsystem};
-use titan_core::{chrono, runtime::time::Instant, tasks::{self, TasksSubsystem}, Result};
-use titan_core::info;
+use std::{fmt, path::PathBuf, time::Duration};
+use titan_core::{chrono, futures, runtime::time::{self, Instant}, tasks::{self, BenchmarkLog, TasksSubsystem}, ArcLock, Result, Subsystem};
+use titan_core::{info, warn};
+
+const FPS_BENCHMARK: &str = "engine::Fps";
+/// Frame time spent rendering, excluding the `frame_limit` sleep.
+const WORK_BENCHMARK: &str = "engine::FrameWork";
+/// Per-frame share of `WORK_BENCHMARK` spent in each render.
+#[cfg(all(feature = "terminal", not(feature = "tracing")))]
+const TERMINAL_RENDER_BENCHMARK: &str = "engine::TerminalRender";
+#[cfg(feature = "graphics")]
+const GRAPHICS_RENDER_BENCHMARK: &str = "engine::GraphicsRender";
+
+fn format_fps(bench: &BenchmarkLog) -> String {
+    format!(
+        "{:>4.0} [{}] ~ {:>4.0} [{}] <=> [{:.0} - {:.0}]",
+        1.0 / bench.duration,
+        &chrono::format_duration_compact(&bench.duration),
+        (bench.runs as f64) * 1.0 / bench.run_time,
+        &chrono::format_duration_compact(&bench.average),
+        1.0 / bench.max,
+        1.0 / bench.min,
+    )
+}
+
+/// Snapshot of frame timings returned by `Stats`.
+#[derive(Clone, Debug, Default)]
+pub struct EngineStats {
+    pub fps: f64,
+    /// Last frame time in seconds.
+    pub frame_time: f64,
+    pub average_frame_time: f64,
+    pub renders: u32,
+    pub uptime: Duration,
+    /// Scaled frame delta from `TimeSubsystem`.
+    pub delta: Duration,
+}
+
+/// Session summary `Shutdown` logs, built from the `engine::Fps` benchmark.
+#[derive(Clone, Debug, Default)]
+pub struct FrameReport {
+    pub frames: u32,
+    pub runtime: Duration,
+    pub average_fps: f64,
+    /// FPS of the slowest frame.
+    pub min_fps: f64,
+    /// FPS of the fastest frame.
+    pub max_fps: f64,
+}
+
+impl fmt::Display for FrameReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} frames in {} | FPS avg {:.1}, min {:.1}, max {:.1}",
+            self.frames,
+            chrono::format_duration(&self.runtime.as_secs_f64()),
+            self.average_fps,
+            self.min_fps,
+            self.max_fps,
+        )
+    }
+}
 
 pub struct EngineSubsystem {
     pub channels: Channels,
     pub quit: bool,
     pub app: Box<dyn App>,
-    pub renders: u32,
+    pub renders: ArcLock<u32>,
+    pub started: Instant,
+    /// Minimum frame time; `run` sleeps off what's left after rendering.
+    pub frame_limit: Option<Duration>,
+    /// Which of graphics and terminal were started, the rest are always there.
+    pub subsystems: EngineSubsystems,
+    /// `TitanConfig::source`, logged by `init` once logging is set up.
+    pub config_source: Option<PathBuf>,
 }
 
 #[titan_core::subsystem]
@@ -19,23 +85,39 @@ impl EngineSubsystem {
     
     #[titan_core::task]
     pub async fn init(&self) -> Result<()> {
+
+        self.channels
+            .get::<TasksSubsystem>()
+            .send(tasks::RegisterBenchmark {
+                name: FPS_BENCHMARK,
+                display: format_fps,
+            })
+            .await?;
         
-        #[cfg(not(feature = "tracing"))] {
+        #[cfg(all(feature = "terminal", not(feature = "tracing")))]
+        if self.subsystems.terminal {
             self.channels
                 .get::<TerminalSubsystem>()
                 .send_mut(terminal::Init)
                 .await??;
         }
 
+        if let Some(config_source) = &self.config_source {
+            info!("Config: Loaded {}", config_source.display());
+        }
+
         self.channels
             .get::<ResourceSubsystem>()
             .send(assets::Init)
             .await??;
-        
-        self.channels
-            .get::<GraphicsSubsystem>()
-            .send(graphics::Init)
-            .await??;
+
+        #[cfg(feature = "graphics")]
+        if self.subsystems.graphics {
+            self.channels
+                .get::<GraphicsSubsystem>()
+                .send(graphics::Init)
+                .await??;
+        }
              
         Ok(())
     }
@@ -43,76 +125,462 @@ impl EngineSubsystem {
     #[titan_core::task]
     pub async fn run(&self) -> Result<()> {
                       
-        let benchmark_name = "engine::Fps";
         let frame_start = Instant::now();
 
+        self.channels
+            .get::<TimeSubsystem>()
+            .send_mut(clock::Tick)
+            .await?;
+
         self.channels
             .get::<TasksSubsystem>()
             .send(tasks::StartBenchmark {
-                name: benchmark_name,
+                name: FPS_BENCHMARK,
+                group: Self::name(),
             })
             .await?;
-        
-        #[cfg(not(feature = "tracing"))] {
-            self.channels
-                .get::<TerminalSubsystem>()
-                .send_mut(terminal::Render)
-                .await??;
-        }
-                                
+
         self.channels
-            .get::<GraphicsSubsystem>()
-            .send(graphics::Render)
-            .await??;
+            .get::<TasksSubsystem>()
+            .send(tasks::StartBenchmark {
+                name: WORK_BENCHMARK,
+                group: Self::name(),
+            })
+            .await?;
         
-        // future::try_join_all(vec![frame_render])
-        //     .await?;
+        // Terminal and graphics are separate subsystems, so their renders don't
+        // contend for the same lock and can overlap.
+        let terminal_render = async {
+            #[cfg(all(feature = "terminal", not(feature = "tracing")))]
+            if self.subsystems.terminal {
+                let render_start = self.start_benchmark(TERMINAL_RENDER_BENCHMARK)
+                    .await?;
+
+                self.channels
+                    .get::<TerminalSubsystem>()
+                    .send_mut(terminal::Render)
+                    .await??;
+
+                self.end_benchmark(TERMINAL_RENDER_BENCHMARK, render_start)
+                    .await?;
+            }
+
+            Result::<()>::Ok(())
+        };
+
+        let graphics_render = async {
+            #[cfg(feature = "graphics")]
+            if self.subsystems.graphics {
+                let render_start = self.start_benchmark(GRAPHICS_RENDER_BENCHMARK)
+                    .await?;
+
+                self.channels
+                    .get::<GraphicsSubsystem>()
+                    .send(graphics::Render)
+                    .await??;
+
+                self.end_benchmark(GRAPHICS_RENDER_BENCHMARK, render_start)
+                    .await?;
+            }
+
+            Result::<()>::Ok(())
+        };
+
+        futures::try_join!(terminal_render, graphics_render)?;
+
+        *self.renders.lock().await += 1;
+
+        let work_time = frame_start.elapsed();
+
+        self.channels
+            .get::<TasksSubsystem>()
+            .send(tasks::EndBenchmark {
+                name: WORK_BENCHMARK,
+                end: work_time.as_secs_f64(),
+            })
+            .await?;
+
+        if let Some(frame_limit) = self.frame_limit {
+            time::sleep(frame_limit.saturating_sub(work_time))
+                .await;
+        }
         
         self.channels
             .get::<TasksSubsystem>()
             .send(tasks::EndBenchmark {
-                name: benchmark_name,
+                name: FPS_BENCHMARK,
                 end: frame_start.elapsed().as_secs_f64(),
-                display: |bench| {
-                    format!(
-                        "{:>4.0} [{}] ~ {:>4.0} [{}] <=> [{:.0} - {:.0}]",
-                        1.0 / bench.duration,
-                        &chrono::format_duration(&bench.duration),
-                        (bench.runs as f64) * 1.0 / bench.run_time,
-                        &chrono::format_duration(&bench.average),
-                        1.0 / bench.max,
-                        1.0 / bench.min,
-                    )
-                },
+            })
+            .await?;
+
+        Ok(())
+    }
+
+    /// Starts one of the frame's child benchmarks, returning when it started.
+    /// Unused when neither render is built in.
+    #[cfg_attr(not(any(feature = "graphics", all(feature = "terminal", not(feature = "tracing")))), allow(dead_code))]
+    async fn start_benchmark(&self, name: &'static str) -> Result<Instant> {
+        self.channels
+            .get::<TasksSubsystem>()
+            .send(tasks::StartBenchmark {
+                name,
+                group: Self::name(),
+            })
+            .await?;
+
+        Ok(Instant::now())
+    }
+
+    #[cfg_attr(not(any(feature = "graphics", all(feature = "terminal", not(feature = "tracing")))), allow(dead_code))]
+    async fn end_benchmark(&self, name: &'static str, start: Instant) -> Result<()> {
+        self.channels
+            .get::<TasksSubsystem>()
+            .send(tasks::EndBenchmark {
+                name,
+                end: start.elapsed().as_secs_f64(),
             })
             .await?;
 
         Ok(())
     }
 
+    #[titan_core::task]
+    pub async fn stats(&self) -> Result<EngineStats> {
+        let fps_bench = self.channels
+            .get::<TasksSubsystem>()
+            .send(tasks::GetBenchmarks)
+            .await?
+            .into_iter()
+            .find(|bench| bench.name == FPS_BENCHMARK);
+
+        let delta = self.channels
+            .get::<TimeSubsystem>()
+            .send(clock::Delta)
+            .await?;
+
+        let mut stats = EngineStats {
+            renders: *self.renders.read().await,
+            uptime: self.started.elapsed(),
+            delta,
+            ..Default::default()
+        };
+
+        if let Some(bench) = fps_bench.filter(|bench| bench.runs > 0) {
+            stats.fps = 1.0 / bench.duration;
+            stats.frame_time = bench.duration;
+            stats.average_frame_time = bench.average;
+        }
+
+        Ok(stats)
+    }
+
+    /// Frame timings over the whole session so far. FPS are zero until a frame finished.
+    #[titan_core::task]
+    pub async fn report(&self) -> Result<FrameReport> {
+        let fps_bench = self.channels
+            .get::<TasksSubsystem>()
+            .send(tasks::GetBenchmarks)
+            .await?
+            .into_iter()
+            .find(|bench| bench.name == FPS_BENCHMARK);
+
+        let mut report = FrameReport {
+            frames: *self.renders.read().await,
+            runtime: self.started.elapsed(),
+            ..Default::default()
+        };
+
+        if let Some(bench) = fps_bench.filter(|bench| bench.runs > 0) {
+            report.average_fps = bench.runs as f64 / bench.run_time;
+            report.min_fps = 1.0 / bench.max;
+            report.max_fps = 1.0 / bench.min;
+        }
+
+        Ok(report)
+    }
+
     #[titan_core::task]
     pub fn request_quit(&mut self) {
         info!("Quit requested...");
         self.quit = true;
     }
 
-    #[titan_core::task]
+    #[titan_core::task(display_output)]
     pub fn should_quit(&self) -> bool {
         self.quit
     }
 
+    /// Each shutdown is awaited, so by the time this returns the watcher has stopped,
+    /// the GPU device is released and the terminal restored, ahead of the runtime drop.
     #[titan_core::task]
     pub async fn shutdown(&self) -> Result<()> {
+        // Taken before anything stops, logged once the terminal is restored.
+        let report = self.report()
+            .await;
+
         self.channels
-            .get::<GraphicsSubsystem>()
-            .send(graphics::Shutdown)
+            .get::<ResourceSubsystem>()
+            .send(assets::Shutdown)
             .await?;
 
-        self.channels
-            .get::<TerminalSubsystem>()
-            .send(terminal::Shutdown)
-            .await??;
+        #[cfg(feature = "graphics")]
+        if self.subsystems.graphics {
+            self.channels
+                .get::<GraphicsSubsystem>()
+                .send(graphics::Shutdown)
+                .await??;
+        }
+
+        #[cfg(feature = "terminal")]
+        if self.subsystems.terminal {
+            self.channels
+                .get::<TerminalSubsystem>()
+                .send(terminal::Shutdown)
+                .await??;
+        }
+
+        match report {
+            Ok(report) => info!("Engine: {}", report),
+            Err(err) => warn!("Engine: Failed to build the frame report: {}", err),
+        }
 
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{tests::start_headless, TitanConfig};
+
+    async fn run_frames(channels: &Channels, frames: u32) {
+        for _ in 0..frames {
+            channels
+                .get::<EngineSubsystem>()
+                .send(Run)
+                .await
+                .unwrap()
+                .unwrap();
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn stats_reflect_recent_frames() {
+        let channels = start_headless(TitanConfig::default())
+            .await;
+
+        let before = channels
+            .get::<EngineSubsystem>()
+            .send(Stats)
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(before.renders, 0);
+        assert_eq!(before.fps, 0.0);
+
+        run_frames(&channels, 3)
+            .await;
+
+        let stats = channels
+            .get::<EngineSubsystem>()
+            .send(Stats)
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(stats.renders, 3);
+        assert!(stats.frame_time > 0.0);
+        assert!(stats.average_frame_time > 0.0);
+        assert_eq!(stats.fps, 1.0 / stats.frame_time);
+        assert!(stats.uptime >= before.uptime);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn work_benchmark_leaves_out_the_frame_cap() {
+        let channels = start_headless(TitanConfig {
+            frame_limit: Some(Duration::from_millis(50)),
+            ..Default::default()
+        })
+        .await;
+
+        run_frames(&channels, 5)
+            .await;
+
+        let benchmarks = channels
+            .get::<TasksSubsystem>()
+            .send(tasks::GetBenchmarks)
+            .await
+            .unwrap();
+
+        let find = |name| benchmarks
+            .iter()
+            .find(|bench| bench.name == name)
+            .unwrap();
+
+        let work = find(WORK_BENCHMARK);
+        let fps = find(FPS_BENCHMARK);
+
+        assert_eq!(work.runs, 5);
+        assert!(work.max < 0.01, "{}", work.max);
+        assert!((fps.average - 0.05).abs() < 0.005, "{}", fps.average);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn report_sums_up_the_session() {
+        let channels = start_headless(TitanConfig {
+            frame_limit: Some(Duration::from_millis(50)),
+            ..Default::default()
+        })
+        .await;
+
+        let empty = channels
+            .get::<EngineSubsystem>()
+            .send(Report)
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(empty.frames, 0);
+        assert_eq!(empty.average_fps, 0.0);
+
+        run_frames(&channels, 4)
+            .await;
+
+        let report = channels
+            .get::<EngineSubsystem>()
+            .send(Report)
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(report.frames, 4);
+        assert!(report.runtime >= Duration::from_millis(200), "{:?}", report.runtime);
+
+        // The paused clock makes every frame exactly the 50ms cap.
+        for fps in [report.average_fps, report.min_fps, report.max_fps] {
+            assert!((fps - 20.0).abs() < 0.5, "{}", report);
+        }
+
+        assert!(report.to_string().starts_with("4 frames in "), "{}", report);
+        assert!(report.to_string().contains("FPS avg 20.0"), "{}", report);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn headless_frames_have_no_render_benchmarks() {
+        let channels = start_headless(TitanConfig::default())
+            .await;
+
+        run_frames(&channels, 2)
+            .await;
+
+        let benchmarks = channels
+            .get::<TasksSubsystem>()
+            .send(tasks::GetBenchmarks)
+            .await
+            .unwrap();
+
+        let mut engine = benchmarks
+            .iter()
+            .filter(|bench| bench.group == EngineSubsystem::name())
+            .map(|bench| bench.name)
+            .collect::<Vec<_>>();
+
+        engine.sort();
+
+        assert_eq!(engine, [FPS_BENCHMARK, WORK_BENCHMARK]);
+    }
+
+    /// Both renders, which only happen with both subsystems built in.
+    #[cfg(all(feature = "graphics", feature = "terminal", not(feature = "tracing")))]
+    mod renders {
+        use super::*;
+        use crate::{start_subsystems, tests::TestApp, EngineChannels};
+        use titan_core::{logs::LogHistory, SubsystemRef};
+
+        /// Every built-in subsystem, which without `Init` means no terminal or GPU is set up.
+        async fn start_all() -> EngineChannels {
+            start_subsystems(TestApp, TitanConfig::default(), LogHistory::default())
+                .await
+                .unwrap()
+        }
+
+        /// Runs one frame with `S` registered but no longer running.
+        async fn frame_without<S: Subsystem>() -> Result<()> {
+            let mut channels = start_all()
+                .await;
+
+            let (dead_ref, dead_receiver) = SubsystemRef::<S>::new();
+            drop(dead_receiver);
+
+            channels.add(dead_ref)
+                .await;
+
+            channels
+                .get::<EngineSubsystem>()
+                .send(Run)
+                .await
+                .unwrap()
+        }
+
+        #[tokio::test(flavor = "multi_thread")]
+        async fn one_run_renders_terminal_and_graphics() {
+            let channels = start_all()
+                .await;
+
+            run_frames(&channels, 1)
+                .await;
+
+            let benchmarks = channels
+                .get::<TasksSubsystem>()
+                .send(tasks::GetBenchmarks)
+                .await
+                .unwrap();
+
+            for name in [TERMINAL_RENDER_BENCHMARK, GRAPHICS_RENDER_BENCHMARK] {
+                let bench = benchmarks
+                    .iter()
+                    .find(|bench| bench.name == name)
+                    .unwrap();
+
+                assert_eq!(bench.runs, 1, "{}", name);
+            }
+        }
+
+        #[tokio::test(flavor = "multi_thread")]
+        async fn each_render_is_timed_within_the_frame_work() {
+            let channels = start_all()
+                .await;
+
+            run_frames(&channels, 3)
+                .await;
+
+            let benchmarks = channels
+                .get::<TasksSubsystem>()
+                .send(tasks::GetBenchmarks)
+                .await
+                .unwrap();
+
+            let find = |name| benchmarks
+                .iter()
+                .find(|bench| bench.name == name)
+                .unwrap();
+
+            let work = find(WORK_BENCHMARK);
+
+            for name in [TERMINAL_RENDER_BENCHMARK, GRAPHICS_RENDER_BENCHMARK] {
+                let render = find(name);
+
+                assert_eq!(render.group, EngineSubsystem::name(), "{}", name);
+                assert_eq!(render.runs, 3, "{}", name);
+                assert!(render.duration <= work.duration, "{}: {} > {}", name, render.duration, work.duration);
+            }
+        }
+
+        #[tokio::test(flavor = "multi_thread")]
+        async fn either_render_failing_fails_the_run() {
+            assert!(frame_without::<TerminalSubsystem>().await.is_err());
+            assert!(frame_without::<GraphicsSubsystem>().await.is_err());
+        }
+    }
+}
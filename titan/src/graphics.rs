@@ -1,31 +1,142 @@
-use titan_core::{Result, anyhow, Channels, ArcLock};
+use std::{
+    collections::HashMap,
+    future::Future,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+use titan_core::{runtime::{sync::oneshot, time}, error, info, trace, warn, Result, anyhow, Channels, ArcLock};
+use crate::world::{self, WorldSubsystem};
+use wgpu::util::DeviceExt;
+
+#[derive(Clone)]
+pub struct GraphicsConfig {
+    pub color_format: wgpu::TextureFormat,
+    /// Requested MSAA sample count (1, 2, 4 or 8).
+    pub sample_count: u32,
+    /// Requested surface present mode, `Fifo` being vsync.
+    pub present_mode: wgpu::PresentMode,
+    /// Backends tried first when requesting an adapter, before falling back to all of them.
+    pub backends: wgpu::Backends,
+    /// Width and height of the window surface and of the frame `screenshot` captures.
+    pub frame_size: (u32, u32),
+}
+
+/// Adapter requests per backend set before moving on to the next one.
+const ADAPTER_ATTEMPTS: u32 = 3;
+const ADAPTER_RETRY_DELAY: Duration = Duration::from_millis(250);
+
+/// Clear color frames start with until `set_clear_color` changes it.
+pub const DEFAULT_CLEAR_COLOR: wgpu::Color = wgpu::Color::BLACK;
+
+impl GraphicsConfig {
+    /// Present mode to configure the surface with, falling back to `Fifo` which every
+    /// surface supports.
+    pub fn present_mode_for(&self, capabilities: &wgpu::SurfaceCapabilities) -> wgpu::PresentMode {
+        if capabilities.present_modes.contains(&self.present_mode) {
+            self.present_mode
+        } else {
+            warn!("Graphics: Present mode {:?} unsupported, falling back to Fifo", self.present_mode);
+            wgpu::PresentMode::Fifo
+        }
+    }
+}
+
+impl Default for GraphicsConfig {
+    fn default() -> Self {
+        Self {
+            color_format: wgpu::TextureFormat::Bgra8UnormSrgb,
+            sample_count: 1,
+            present_mode: wgpu::PresentMode::Fifo,
+            backends: wgpu::Backends::PRIMARY,
+            frame_size: (1280, 720),
+        }
+    }
+}
+
+/// Window surface attached with `attach_window`, with the configuration it was last
+/// configured with on the current device.
+pub struct GraphicsSurface {
+    pub surface: wgpu::Surface<'static>,
+    pub config: wgpu::SurfaceConfiguration,
+}
+
+/// Handle to a buffer created with `create_buffer`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct BufferId(u64);
+
+/// A buffer owned by the graphics subsystem, with the data and usage it was created from
+/// so it can be re-uploaded when the device is re-acquired. The buffer itself is padded to
+/// `COPY_BUFFER_ALIGNMENT`, so `data.len()` is the length readback truncates to.
+pub struct GpuBuffer {
+    pub buffer: wgpu::Buffer,
+    pub data: Vec<u8>,
+    pub usage: wgpu::BufferUsages,
+}
+
+impl GpuBuffer {
+    fn upload(device: &wgpu::Device, data: Vec<u8>, usage: wgpu::BufferUsages) -> Self {
+        let buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Titan Buffer"),
+            contents: &data,
+            usage,
+        });
+
+        Self { buffer, data, usage }
+    }
+}
 
 pub struct GraphicsSubsystem {
     pub channels: Channels,
+    pub config: GraphicsConfig,
+    /// Instance and adapter the device came from; surfaces are created and checked against them.
+    pub instance: ArcLock<Option<wgpu::Instance>>,
+    pub adapter: ArcLock<Option<wgpu::Adapter>>,
     pub device: ArcLock<Option<wgpu::Device>>,
     pub queue: ArcLock<Option<wgpu::Queue>>,
+    /// Window frames are presented to, set with `attach_window`. The surface is re-created
+    /// from it whenever the device is re-acquired.
+    pub window: Option<Arc<dyn wgpu::WindowHandle>>,
+    pub surface: ArcLock<Option<GraphicsSurface>>,
+    /// Sample count validated against the device, used for color targets and pipelines.
+    pub sample_count: ArcLock<u32>,
+    /// Multisampled color target at `frame_size` that frames render into before resolving
+    /// to the surface or screenshot texture. `None` when the sample count is 1.
+    pub msaa_target: ArcLock<Option<wgpu::TextureView>>,
+    /// Set from the device lost callback, checked at the start of each render.
+    pub device_lost: Arc<AtomicBool>,
+    /// Color render passes clear their target to.
+    pub clear_color: ArcLock<wgpu::Color>,
+    /// Buffers made with `create_buffer`, re-uploaded under the same ids whenever the
+    /// device is re-acquired.
+    pub buffers: ArcLock<HashMap<BufferId, GpuBuffer>>,
+    pub next_buffer_id: Arc<AtomicU64>,
+    /// Index into `list_adapters` chosen with `select_adapter`, also used when the
+    /// device is re-acquired. `None` lets `request_adapter` pick.
+    pub adapter_index: Option<usize>,
 }
 
 #[titan_core::subsystem]
 impl GraphicsSubsystem {
     
+    /// Acquires the device. An explicit task rather than `on_start` so `engine::Init`
+    /// fails when there's no device instead of running without one.
     #[titan_core::task]
     async fn init(&self) -> Result<()> {
-        let instance = wgpu::Instance::default();
-
-        let adapter = instance.request_adapter(
-            &wgpu::RequestAdapterOptions {
-                power_preference: wgpu::PowerPreference::HighPerformance,
-                force_fallback_adapter: false,
-                compatible_surface: None,
-            })
+        self.acquire_device()
             .await
-            .ok_or(anyhow!("Graphics: Failed to request adapter"))?;
+    }
+
+    async fn acquire_device(&self) -> Result<()> {
+        let (instance, adapter) = self.request_adapter()
+            .await?;
 
         let (device, queue) = adapter.request_device(
                 &wgpu::DeviceDescriptor {
                     label: Some("Titan Device"),
-                    required_features: wgpu::Features::default(),
+                    required_features: adapter.features() & wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES,
                     required_limits: wgpu::Limits::default(),
                     memory_hints: wgpu::MemoryHints::MemoryUsage,
                 },
@@ -33,21 +144,1192 @@ impl GraphicsSubsystem {
             )
             .await?;
 
+        let sample_count = Self::supported_sample_count(&adapter, &device, &self.config);
+
+        self.sample_count.write(sample_count)
+            .await;
+
+        self.device_lost.store(false, Ordering::SeqCst);
+
+        let device_lost = self.device_lost.clone();
+
+        // Dropped/replaced devices are expected during recovery and shutdown.
+        device.set_device_lost_callback(move |reason, message| {
+            if matches!(reason, wgpu::DeviceLostReason::Unknown | wgpu::DeviceLostReason::Destroyed) {
+                error!("Graphics: Device lost ({:?}): {}", reason, message);
+                device_lost.store(true, Ordering::SeqCst);
+            }
+        });
+
+        // Existing ids stay valid, though anything written to them on the GPU since is lost.
+        self.buffers.lock()
+            .await
+            .values_mut()
+            .for_each(|gpu_buffer| {
+                let data = std::mem::take(&mut gpu_buffer.data);
+                *gpu_buffer = GpuBuffer::upload(&device, data, gpu_buffer.usage);
+            });
+
         self.device.write(Some(device))
             .await;
         
         self.queue.write(Some(queue))
             .await;
 
+        self.instance.write(Some(instance))
+            .await;
+
+        self.adapter.write(Some(adapter))
+            .await;
+
+        self.create_msaa_target()
+            .await?;
+
+        self.create_surface()
+            .await
+    }
+
+    /// (Re-)creates `msaa_target` for the current device, sample count and `frame_size`.
+    async fn create_msaa_target(&self) -> Result<()> {
+        let sample_count = *self.sample_count.read()
+            .await;
+
+        let msaa_target = match sample_count > 1 {
+            true => {
+                let device_lock = self.device.read()
+                    .await;
+
+                let device = device_lock
+                    .as_ref()
+                    .ok_or(anyhow!("Graphics: Device not initialized"))?;
+
+                let (width, height) = self.config.frame_size;
+
+                let texture = device.create_texture(&wgpu::TextureDescriptor {
+                    label: Some("Titan MSAA Target"),
+                    size: wgpu::Extent3d {
+                        width,
+                        height,
+                        depth_or_array_layers: 1,
+                    },
+                    mip_level_count: 1,
+                    sample_count,
+                    dimension: wgpu::TextureDimension::D2,
+                    format: self.config.color_format,
+                    usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+                    view_formats: &[],
+                });
+
+                Some(texture.create_view(&wgpu::TextureViewDescriptor::default()))
+            }
+            false => None,
+        };
+
+        self.msaa_target.write(msaa_target)
+            .await;
+
+        Ok(())
+    }
+
+    /// Multisample state for render pipelines drawing into the frame targets.
+    pub async fn multisample_state(&self) -> wgpu::MultisampleState {
+        wgpu::MultisampleState {
+            count: *self.sample_count.read()
+                .await,
+            ..Default::default()
+        }
+    }
+
+    /// Color attachment clearing `target`, rendering into `msaa_target` and resolving into
+    /// `target` when multisampling.
+    fn color_attachment<'a>(
+        target: &'a wgpu::TextureView,
+        msaa_target: Option<&'a wgpu::TextureView>,
+        clear_color: wgpu::Color,
+    ) -> wgpu::RenderPassColorAttachment<'a> {
+        match msaa_target {
+            Some(msaa_view) => wgpu::RenderPassColorAttachment {
+                view: msaa_view,
+                resolve_target: Some(target),
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(clear_color),
+                    store: wgpu::StoreOp::Discard,
+                },
+            },
+            None => wgpu::RenderPassColorAttachment {
+                view: target,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(clear_color),
+                    store: wgpu::StoreOp::Store,
+                },
+            },
+        }
+    }
+
+    /// Creates the surface for `window` on the current instance and configures it. Surfaces
+    /// belong to the instance, so this runs again whenever the device is re-acquired.
+    async fn create_surface(&self) -> Result<()> {
+        let Some(window) = self.window.clone() else {
+            return Ok(());
+        };
+
+        let surface = {
+            let instance_lock = self.instance.read()
+                .await;
+
+            let instance = instance_lock
+                .as_ref()
+                .ok_or(anyhow!("Graphics: Instance not initialized"))?;
+
+            instance.create_surface(window)
+                .map_err(|err| anyhow!("Graphics: Failed to create surface: {}", err))?
+        };
+
+        let config = self.surface_config(&surface)
+            .await?;
+
+        *self.surface.lock()
+            .await = Some(GraphicsSurface { surface, config });
+
+        self.configure_surface()
+            .await
+    }
+
+    /// Surface configuration for the current adapter, `frame_size` and present mode, the
+    /// latter passed through `present_mode_for` so an unsupported mode falls back to `Fifo`.
+    async fn surface_config(&self, surface: &wgpu::Surface<'static>) -> Result<wgpu::SurfaceConfiguration> {
+        let adapter_lock = self.adapter.read()
+            .await;
+
+        let adapter = adapter_lock
+            .as_ref()
+            .ok_or(anyhow!("Graphics: Adapter not initialized"))?;
+
+        if !adapter.is_surface_supported(surface) {
+            return Err(anyhow!("Graphics: Adapter {:?} can't present to the window", adapter.get_info().name));
+        }
+
+        let capabilities = surface.get_capabilities(adapter);
+
+        if !capabilities.formats.contains(&self.config.color_format) {
+            return Err(anyhow!("Graphics: Surface doesn't support {:?}", self.config.color_format));
+        }
+
+        let (width, height) = self.config.frame_size;
+
+        Ok(wgpu::SurfaceConfiguration {
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            format: self.config.color_format,
+            width,
+            height,
+            present_mode: self.config.present_mode_for(&capabilities),
+            desired_maximum_frame_latency: 2,
+            alpha_mode: capabilities.alpha_modes[0],
+            view_formats: vec![],
+        })
+    }
+
+    /// Re-configures the attached surface, if any, after its size or present mode changed.
+    async fn configure_surface(&self) -> Result<()> {
+        let mut surface_lock = self.surface.lock()
+            .await;
+
+        let Some(graphics_surface) = surface_lock.as_mut() else {
+            return Ok(());
+        };
+
+        graphics_surface.config = self.surface_config(&graphics_surface.surface)
+            .await?;
+
+        let device_lock = self.device.read()
+            .await;
+
+        let device = device_lock
+            .as_ref()
+            .ok_or(anyhow!("Graphics: Device not initialized"))?;
+
+        graphics_surface.surface.configure(device, &graphics_surface.config);
+
+        info!("Graphics: Surface configured at {}x{} with {:?}",
+            graphics_surface.config.width,
+            graphics_surface.config.height,
+            graphics_surface.config.present_mode
+        );
+
+        Ok(())
+    }
+
+    /// Requests an adapter from the configured backends, retrying briefly since some
+    /// become available only after startup, then falls back to every backend.
+    async fn request_adapter(&self) -> Result<(wgpu::Instance, wgpu::Adapter)> {
+        if let Some(index) = self.adapter_index {
+            return Self::enumerate_adapter(index);
+        }
+
+        let (instance, adapter) = Self::with_backend_fallback(self.config.backends, |backends| async move {
+            let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
+                backends,
+                ..Default::default()
+            });
+
+            let adapter = instance.request_adapter(
+                &wgpu::RequestAdapterOptions {
+                    power_preference: wgpu::PowerPreference::HighPerformance,
+                    force_fallback_adapter: false,
+                    compatible_surface: None,
+                })
+                .await?;
+
+            Some((instance, adapter))
+        })
+        .await?;
+
+        info!("Graphics: Using adapter {:?}", adapter.get_info().name);
+
+        Ok((instance, adapter))
+    }
+
+    /// Calls `request` with `preferred` up to `ADAPTER_ATTEMPTS` times, then likewise with
+    /// every backend, until it returns something.
+    async fn with_backend_fallback<T, F, Fut>(preferred: wgpu::Backends, mut request: F) -> Result<T>
+    where
+        F: FnMut(wgpu::Backends) -> Fut,
+        Fut: Future<Output = Option<T>>,
+    {
+        let mut backend_sets = vec![preferred];
+
+        if preferred != wgpu::Backends::all() {
+            backend_sets.push(wgpu::Backends::all());
+        }
+
+        for backends in backend_sets.iter() {
+            for attempt in 1..=ADAPTER_ATTEMPTS {
+                info!("Graphics: Requesting adapter from {:?} (attempt {}/{})", backends, attempt, ADAPTER_ATTEMPTS);
+
+                if let Some(found) = request(*backends).await {
+                    return Ok(found);
+                }
+
+                warn!("Graphics: No adapter available from {:?}", backends);
+
+                if attempt < ADAPTER_ATTEMPTS {
+                    time::sleep(ADAPTER_RETRY_DELAY)
+                        .await;
+                }
+            }
+        }
+
+        Err(anyhow!("Graphics: Failed to request adapter, tried backends {:?} {} times each",
+            backend_sets,
+            ADAPTER_ATTEMPTS
+        ))
+    }
+
+    fn all_backends_instance() -> wgpu::Instance {
+        wgpu::Instance::new(wgpu::InstanceDescriptor {
+            backends: wgpu::Backends::all(),
+            ..Default::default()
+        })
+    }
+
+    /// Every adapter on every backend, in the order `list_adapters` shows them.
+    fn enumerate_adapters(instance: &wgpu::Instance) -> Vec<wgpu::Adapter> {
+        instance.enumerate_adapters(wgpu::Backends::all())
+    }
+
+    fn enumerate_adapter(index: usize) -> Result<(wgpu::Instance, wgpu::Adapter)> {
+        let instance = Self::all_backends_instance();
+        let mut adapters = Self::enumerate_adapters(&instance);
+        let adapter_count = adapters.len();
+
+        match index < adapter_count {
+            true => {
+                let adapter = adapters.swap_remove(index);
+                info!("Graphics: Using selected adapter {:?}", adapter.get_info().name);
+                Ok((instance, adapter))
+            }
+            false => Err(anyhow!("Graphics: No adapter at index {}, {} available", index, adapter_count)),
+        }
+    }
+
+    fn supported_sample_count(adapter: &wgpu::Adapter, device: &wgpu::Device, config: &GraphicsConfig) -> u32 {
+        let requested = config.sample_count;
+
+        // Without adapter specific format features only the WebGPU guaranteed counts are valid.
+        let supported = match device.features().contains(wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES) {
+            true => adapter
+                .get_texture_format_features(config.color_format)
+                .flags
+                .sample_count_supported(requested),
+            false => matches!(requested, 1 | 4),
+        };
+
+        if matches!(requested, 1 | 2 | 4 | 8) && supported {
+            requested
+        } else {
+            warn!("Graphics: Sample count {} unsupported for {:?}, falling back to 1", requested, config.color_format);
+            1
+        }
+    }
+
+    async fn recover_device(&self) -> Result<()> {
+        if !self.device_lost.load(Ordering::SeqCst) {
+            return Ok(());
+        }
+
+        warn!("Graphics: Re-acquiring lost device...");
+
+        self.acquire_device()
+            .await?;
+
+        info!("Graphics: Device recovered");
+
         Ok(())
     }
 
+    /// Adapters available on every backend, formatted as `index: name (type, backend)`.
+    /// The index is what `select_adapter` takes.
+    #[titan_core::task(io)]
+    async fn list_adapters(&self) -> Vec<String> {
+        Self::enumerate_adapters(&Self::all_backends_instance())
+            .iter()
+            .enumerate()
+            .map(|(index, adapter)| {
+                let info = adapter.get_info();
+                format!("{}: {} ({:?}, {:?})", index, info.name, info.device_type, info.backend)
+            })
+            .collect()
+    }
+
+    /// Re-acquires the device on the adapter at `index` in `list_adapters`, re-uploading
+    /// existing buffers. An out of range index leaves the device as is.
+    #[titan_core::task]
+    async fn select_adapter(&mut self, index: usize) -> Result<()> {
+        Self::enumerate_adapter(index)?;
+
+        let previous = self.adapter_index.replace(index);
+
+        if let Err(err) = self.acquire_device().await {
+            self.adapter_index = previous;
+            return Err(err);
+        }
+
+        Ok(())
+    }
+
+    /// Presents frames to `window` from now on, replacing any window attached before. The
+    /// surface is configured at `frame_size`; call `resize` when the window's size changes.
+    #[titan_core::task]
+    async fn attach_window(&mut self, window: Arc<dyn wgpu::WindowHandle>) -> Result<()> {
+        let previous = self.window.replace(window);
+
+        if let Err(err) = self.create_surface().await {
+            self.window = previous;
+            return Err(err);
+        }
+
+        Ok(())
+    }
+
+    /// Resizes the window surface, the MSAA target and the frame `screenshot` captures.
+    #[titan_core::task]
+    async fn resize(&mut self, frame_size: (u32, u32)) -> Result<()> {
+        let (width, height) = frame_size;
+
+        if width == 0 || height == 0 {
+            return Err(anyhow!("Graphics: Invalid frame size {}x{}", width, height));
+        }
+
+        self.config.frame_size = frame_size;
+
+        self.create_msaa_target()
+            .await?;
+
+        self.configure_surface()
+            .await
+    }
+
+    /// Switches the present mode, re-configuring the window surface if one is attached.
+    /// Modes the surface doesn't support fall back to `Fifo`.
+    #[titan_core::task]
+    async fn set_present_mode(&mut self, present_mode: wgpu::PresentMode) -> Result<()> {
+        info!("Graphics: Present mode set to {:?}", present_mode);
+        self.config.present_mode = present_mode;
+
+        self.configure_surface()
+            .await
+    }
+
+    /// Sets the render pass clear color from RGBA components, each clamped to `[0, 1]`.
+    #[titan_core::task]
+    async fn set_clear_color(&self, color: (f64, f64, f64, f64)) {
+        let (r, g, b, a) = color;
+
+        let clear_color = wgpu::Color {
+            r: r.clamp(0.0, 1.0),
+            g: g.clamp(0.0, 1.0),
+            b: b.clamp(0.0, 1.0),
+            a: a.clamp(0.0, 1.0),
+        };
+
+        info!("Graphics: Clear color set to {:?}", clear_color);
+
+        self.clear_color.write(clear_color)
+            .await;
+    }
+
+    #[titan_core::task]
+    async fn clear_color(&self) -> wgpu::Color {
+        *self.clear_color.read()
+            .await
+    }
+
     #[titan_core::task(benchmark)]
     async fn render(&self) -> Result<()> {
+        self.recover_device()
+            .await?;
+
+        // Nothing is drawn yet; this is where renderable entities will be picked up.
+        let renderables = self.channels
+            .get::<WorldSubsystem>()
+            .send(world::Query {
+                tag: world::RENDERABLE_TAG.to_string(),
+            })
+            .await?;
+
+        trace!("Graphics: {} renderable entities", renderables.len());
+
+        self.present()
+            .await
+    }
+
+    /// Clears the attached surface's next frame and presents it. Does nothing without a
+    /// window, and skips the frame when the surface needs re-configuring first.
+    async fn present(&self) -> Result<()> {
+        let surface_lock = self.surface.read()
+            .await;
+
+        let Some(graphics_surface) = surface_lock.as_ref() else {
+            return Ok(());
+        };
+
+        let frame = match graphics_surface.surface.get_current_texture() {
+            Ok(frame) => frame,
+            Err(wgpu::SurfaceError::Outdated | wgpu::SurfaceError::Lost) => {
+                warn!("Graphics: Surface outdated, re-configuring");
+                drop(surface_lock);
+
+                return self.configure_surface()
+                    .await;
+            }
+            Err(wgpu::SurfaceError::Timeout) => {
+                warn!("Graphics: Timed out acquiring the next frame");
+                return Ok(());
+            }
+            Err(err) => return Err(anyhow!("Graphics: Failed to acquire the next frame: {}", err)),
+        };
+
+        let frame_view = frame.texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        let device_lock = self.device.read()
+            .await;
+
+        let device = device_lock
+            .as_ref()
+            .ok_or(anyhow!("Graphics: Device not initialized"))?;
+
+        let queue_lock = self.queue.read()
+            .await;
+
+        let queue = queue_lock
+            .as_ref()
+            .ok_or(anyhow!("Graphics: Queue not initialized"))?;
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Titan Frame Encoder"),
+        });
+
+        let clear_color = *self.clear_color.read()
+            .await;
+
+        let msaa_target = self.msaa_target.read()
+            .await;
+
+        // Nothing is drawn yet, so the frame is just the clear pass.
+        encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Titan Frame Pass"),
+            color_attachments: &[Some(Self::color_attachment(&frame_view, msaa_target.as_ref(), clear_color))],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+
+        queue.submit(Some(encoder.finish()));
+        frame.present();
+
         Ok(())
     }
 
+    /// Runs the `main` entry point of a WGSL compute shader over `input`, which is bound
+    /// read-write at `@group(0) @binding(0)`, and returns the buffer's contents afterwards.
+    #[titan_core::task(io, cost = 8)]
+    async fn dispatch_compute(&self, shader: String, workgroups: (u32, u32, u32), input: Vec<u8>) -> Result<Vec<u8>> {
+        let device_lock = self.device.read()
+            .await;
+
+        let device = device_lock
+            .as_ref()
+            .ok_or(anyhow!("Graphics: Device not initialized"))?;
+
+        let queue_lock = self.queue.read()
+            .await;
+
+        let queue = queue_lock
+            .as_ref()
+            .ok_or(anyhow!("Graphics: Queue not initialized"))?;
+
+        let limits = device.limits();
+        let buffer_size = input.len() as u64;
+        let (x, y, z) = workgroups;
+
+        if buffer_size == 0 || !buffer_size.is_multiple_of(wgpu::COPY_BUFFER_ALIGNMENT) {
+            return Err(anyhow!("Graphics: Compute input must be a non-empty multiple of {} bytes", wgpu::COPY_BUFFER_ALIGNMENT));
+        }
+
+        if buffer_size > limits.max_storage_buffer_binding_size as u64 {
+            return Err(anyhow!("Graphics: Compute input of {} bytes exceeds the storage binding limit", buffer_size));
+        }
+
+        if [x, y, z].iter().any(|count| *count > limits.max_compute_workgroups_per_dimension) {
+            return Err(anyhow!("Graphics: Compute workgroups {:?} exceed the per-dimension limit", workgroups));
+        }
+
+        device.push_error_scope(wgpu::ErrorFilter::Validation);
+
+        let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Titan Compute Shader"),
+            source: wgpu::ShaderSource::Wgsl(shader.into()),
+        });
+
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("Titan Compute Pipeline"),
+            layout: None,
+            module: &module,
+            entry_point: Some("main"),
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+            cache: None,
+        });
+
+        let storage_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Titan Compute Storage"),
+            contents: &input,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+        });
+
+        let staging_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Titan Compute Staging"),
+            size: buffer_size,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Titan Compute Bind Group"),
+            layout: &pipeline.get_bind_group_layout(0),
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: storage_buffer.as_entire_binding(),
+            }],
+        });
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Titan Compute Encoder"),
+        });
+
+        {
+            let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("Titan Compute Pass"),
+                timestamp_writes: None,
+            });
+
+            compute_pass.set_pipeline(&pipeline);
+            compute_pass.set_bind_group(0, &bind_group, &[]);
+            compute_pass.dispatch_workgroups(x, y, z);
+        }
+
+        encoder.copy_buffer_to_buffer(&storage_buffer, 0, &staging_buffer, 0, buffer_size);
+
+        queue.submit(Some(encoder.finish()));
+
+        // Kept open through binding and submit so a shader whose bindings don't match the
+        // storage buffer comes back as an error instead of hitting the uncaptured handler.
+        if let Some(err) = device.pop_error_scope().await {
+            return Err(anyhow!("Graphics: Invalid compute shader: {}", err));
+        }
+
+        Self::read_staging_buffer(device, &staging_buffer)
+            .await
+    }
+
+    /// Uploads `data` into a new buffer with `usage`, plus `COPY_SRC` so it can be read back.
+    #[titan_core::task]
+    async fn create_buffer(&self, data: Vec<u8>, usage: wgpu::BufferUsages) -> Result<BufferId> {
+        let device_lock = self.device.read()
+            .await;
+
+        let device = device_lock
+            .as_ref()
+            .ok_or(anyhow!("Graphics: Device not initialized"))?;
+
+        if data.is_empty() {
+            return Err(anyhow!("Graphics: Buffer data is empty"));
+        }
+
+        // Mappable buffers can't also be `COPY_SRC`, and readback maps a staging copy anyway.
+        if usage.intersects(wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::MAP_WRITE) {
+            return Err(anyhow!("Graphics: Buffer usage {:?} can't include map flags", usage));
+        }
+
+        // Padded up to `COPY_BUFFER_ALIGNMENT` by wgpu.
+        let gpu_buffer = GpuBuffer::upload(device, data, usage | wgpu::BufferUsages::COPY_SRC);
+
+        let id = BufferId(self.next_buffer_id.fetch_add(1, Ordering::SeqCst));
+
+        self.buffers.lock()
+            .await
+            .insert(id, gpu_buffer);
+
+        Ok(id)
+    }
+
+    /// Copies a buffer made with `create_buffer` back from the GPU.
+    #[titan_core::task(io)]
+    async fn read_buffer(&self, id: BufferId) -> Result<Vec<u8>> {
+        let device_lock = self.device.read()
+            .await;
+
+        let device = device_lock
+            .as_ref()
+            .ok_or(anyhow!("Graphics: Device not initialized"))?;
+
+        let queue_lock = self.queue.read()
+            .await;
+
+        let queue = queue_lock
+            .as_ref()
+            .ok_or(anyhow!("Graphics: Queue not initialized"))?;
+
+        let buffers = self.buffers.read()
+            .await;
+
+        let gpu_buffer = buffers
+            .get(&id)
+            .ok_or(anyhow!("Graphics: Unknown buffer {:?}", id))?;
+
+        let buffer_size = gpu_buffer.buffer.size();
+
+        let staging_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Titan Buffer Staging"),
+            size: buffer_size,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Titan Buffer Readback Encoder"),
+        });
+
+        encoder.copy_buffer_to_buffer(&gpu_buffer.buffer, 0, &staging_buffer, 0, buffer_size);
+
+        queue.submit(Some(encoder.finish()));
+
+        let mut data = Self::read_staging_buffer(device, &staging_buffer)
+            .await?;
+
+        data.truncate(gpu_buffer.data.len());
+
+        Ok(data)
+    }
+
+    /// Drops a buffer made with `create_buffer`, returning whether it existed.
     #[titan_core::task]
-    async fn shutdown(&self) {
+    async fn destroy_buffer(&self, id: BufferId) -> bool {
+        self.buffers.lock()
+            .await
+            .remove(&id)
+            .is_some()
+    }
+
+    /// Renders the current frame offscreen at `GraphicsConfig::frame_size`, resolving the
+    /// MSAA target if there is one, and saves it to `path` as an RGBA8 PNG.
+    #[titan_core::task(io)]
+    async fn screenshot(&self, path: String) -> Result<()> {
+        let device_lock = self.device.read()
+            .await;
+
+        let device = device_lock
+            .as_ref()
+            .ok_or(anyhow!("Graphics: Device not initialized"))?;
+
+        let queue_lock = self.queue.read()
+            .await;
+
+        let queue = queue_lock
+            .as_ref()
+            .ok_or(anyhow!("Graphics: Queue not initialized"))?;
+
+        let (width, height) = self.config.frame_size;
+        let max_dimension = device.limits().max_texture_dimension_2d;
+
+        if width == 0 || height == 0 || width > max_dimension || height > max_dimension {
+            return Err(anyhow!("Graphics: Invalid screenshot size {}x{}", width, height));
+        }
+
+        // The frame is rendered in the color format so it can share the MSAA target.
+        let bgra = match self.config.color_format {
+            wgpu::TextureFormat::Rgba8Unorm | wgpu::TextureFormat::Rgba8UnormSrgb => false,
+            wgpu::TextureFormat::Bgra8Unorm | wgpu::TextureFormat::Bgra8UnormSrgb => true,
+            format => return Err(anyhow!("Graphics: Can't screenshot {:?} frames", format)),
+        };
+
+        let frame_extent = wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        };
+
+        let frame_texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Titan Screenshot Target"),
+            size: frame_extent,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: self.config.color_format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+
+        let frame_view = frame_texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        // Texture to buffer copies need every row padded to `COPY_BYTES_PER_ROW_ALIGNMENT`.
+        let row_bytes = width * 4;
+        let padded_row_bytes = row_bytes.div_ceil(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT) * wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+
+        let staging_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Titan Screenshot Staging"),
+            size: padded_row_bytes as u64 * height as u64,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Titan Screenshot Encoder"),
+        });
+
+        let clear_color = *self.clear_color.read()
+            .await;
+
+        let msaa_target = self.msaa_target.read()
+            .await;
+
+        // Rendering doesn't draw anything yet, so the frame is just the clear pass.
+        encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Titan Screenshot Pass"),
+            color_attachments: &[Some(Self::color_attachment(&frame_view, msaa_target.as_ref(), clear_color))],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+
+        encoder.copy_texture_to_buffer(
+            wgpu::ImageCopyTexture {
+                texture: &frame_texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All,
+            },
+            wgpu::ImageCopyBuffer {
+                buffer: &staging_buffer,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_row_bytes),
+                    rows_per_image: Some(height),
+                },
+            },
+            frame_extent,
+        );
+
+        queue.submit(Some(encoder.finish()));
+
+        let padded_pixels = Self::read_staging_buffer(device, &staging_buffer)
+            .await?;
+
+        let mut pixels = padded_pixels
+            .chunks_exact(padded_row_bytes as usize)
+            .flat_map(|row| &row[..row_bytes as usize])
+            .copied()
+            .collect::<Vec<u8>>();
+
+        if bgra {
+            pixels
+                .chunks_exact_mut(4)
+                .for_each(|pixel| pixel.swap(0, 2));
+        }
+
+        image::save_buffer(&path, &pixels, width, height, image::ExtendedColorType::Rgba8)
+            .map_err(|err| anyhow!("Graphics: Failed to save screenshot to {}: {}", path, err))?;
+
+        info!("Graphics: Saved screenshot to {}", path);
+
+        Ok(())
+    }
+
+    /// Maps a `MAP_READ` buffer once submitted work has finished and copies out its contents.
+    async fn read_staging_buffer(device: &wgpu::Device, staging_buffer: &wgpu::Buffer) -> Result<Vec<u8>> {
+        let (sender, receiver) = oneshot::channel();
+        let staging_slice = staging_buffer.slice(..);
+
+        staging_slice.map_async(wgpu::MapMode::Read, move |result| {
+            let _ = sender.send(result);
+        });
+
+        device.poll(wgpu::Maintain::Wait);
+
+        receiver
+            .await?
+            .map_err(|err| anyhow!("Graphics: Failed to map staging buffer: {}", err))?;
+
+        let output = staging_slice
+            .get_mapped_range()
+            .to_vec();
+
+        staging_buffer.unmap();
+
+        Ok(output)
+    }
+
+    /// Waits for submitted GPU work, then drops the queue and device so wgpu tears
+    /// down before the runtime does. An io task since waiting on the GPU blocks.
+    #[titan_core::task(io)]
+    async fn shutdown(&self) -> Result<()> {
+        if let Some(device) = self.device.read().await.as_ref() {
+            device.poll(wgpu::Maintain::Wait);
+        }
+
+        self.buffers.lock()
+            .await
+            .clear();
+
+        self.surface.write(None)
+            .await;
+
+        self.queue.write(None)
+            .await;
+
+        self.device.write(None)
+            .await;
+
+        info!("Graphics: Device released");
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn graphics_subsystem(config: GraphicsConfig) -> GraphicsSubsystem {
+        GraphicsSubsystem {
+            channels: Channels::default(),
+            config,
+            instance: ArcLock::new(None),
+            adapter: ArcLock::new(None),
+            device: ArcLock::new(None),
+            queue: ArcLock::new(None),
+            window: None,
+            surface: ArcLock::new(None),
+            sample_count: ArcLock::new(1),
+            msaa_target: ArcLock::new(None),
+            device_lost: Arc::new(AtomicBool::new(false)),
+            clear_color: ArcLock::new(DEFAULT_CLEAR_COLOR),
+            buffers: ArcLock::new(HashMap::new()),
+            next_buffer_id: Arc::new(AtomicU64::new(0)),
+            adapter_index: None,
+        }
+    }
+
+    /// A subsystem with a device on any backend, or `None` where there's no adapter,
+    /// e.g. in CI, so GPU tests pass vacuously there.
+    async fn with_device(config: GraphicsConfig) -> Option<GraphicsSubsystem> {
+        let graphics = graphics_subsystem(GraphicsConfig {
+            backends: wgpu::Backends::all(),
+            ..config
+        });
+
+        match graphics.acquire_device().await {
+            Ok(()) => Some(graphics),
+            Err(err) => {
+                eprintln!("Skipping GPU test: {}", err);
+                None
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn compute_shader_doubles_its_input() {
+        let Some(graphics) = with_device(GraphicsConfig::default()).await else {
+            return;
+        };
+
+        let shader = "
+            @group(0) @binding(0) var<storage, read_write> values: array<u32>;
+
+            @compute @workgroup_size(1)
+            fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+                values[id.x] = values[id.x] * 2u;
+            }
+        ";
+
+        let input = [1u32, 2, 3, 4]
+            .iter()
+            .flat_map(|value| value.to_ne_bytes())
+            .collect::<Vec<u8>>();
+
+        let output = graphics.dispatch_compute(shader.to_string(), (4, 1, 1), input)
+            .await
+            .unwrap()
+            .chunks_exact(4)
+            .map(|bytes| u32::from_ne_bytes(bytes.try_into().unwrap()))
+            .collect::<Vec<u32>>();
+
+        assert_eq!(output, [2, 4, 6, 8]);
+    }
+
+    #[tokio::test]
+    async fn screenshot_decodes_back_at_the_frame_size() {
+        // 70 pixel rows are 280 bytes, so the copy has to pad them to 512.
+        let Some(graphics) = with_device(GraphicsConfig { frame_size: (70, 30), ..Default::default() }).await else {
+            return;
+        };
+
+        graphics.clear_color.write(wgpu::Color::RED)
+            .await;
+
+        let dir = tempfile::tempdir()
+            .unwrap();
+        let path = dir.path().join("frame.png");
+
+        graphics.screenshot(path.display().to_string())
+            .await
+            .unwrap();
+
+        let image = image::open(&path)
+            .unwrap()
+            .into_rgba8();
+
+        assert_eq!(image.dimensions(), (70, 30));
+        assert!(image.pixels().all(|pixel| pixel.0 == [255, 0, 0, 255]));
+    }
+
+    #[tokio::test]
+    async fn compute_without_a_device_errors() {
+        let graphics = graphics_subsystem(GraphicsConfig::default());
+
+        let result = graphics.dispatch_compute(String::new(), (1, 1, 1), vec![0; 4])
+            .await;
+
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn unsupported_sample_count_falls_back_to_1() {
+        for sample_count in [3, 16] {
+            let Some(graphics) = with_device(GraphicsConfig { sample_count, ..Default::default() }).await else {
+                return;
+            };
+
+            assert_eq!(graphics.multisample_state().await.count, 1);
+            assert!(graphics.msaa_target.read().await.is_none());
+        }
+
+        // Every adapter supports 4x, so it's kept and gets a target to resolve from.
+        let Some(graphics) = with_device(GraphicsConfig { sample_count: 4, ..Default::default() }).await else {
+            return;
+        };
+
+        assert_eq!(graphics.multisample_state().await.count, 4);
+        assert!(graphics.msaa_target.read().await.is_some());
+    }
+
+    #[tokio::test]
+    async fn lost_device_is_reacquired_with_its_buffers() {
+        let Some(graphics) = with_device(GraphicsConfig::default()).await else {
+            return;
+        };
+
+        let id = graphics.create_buffer(vec![1, 2, 3, 4, 5], wgpu::BufferUsages::STORAGE)
+            .await
+            .unwrap();
+
+        // Destroying the device goes through the same lost callback a driver reset does.
+        if let Some(device) = graphics.device.read().await.as_ref() {
+            device.destroy();
+            device.poll(wgpu::Maintain::Wait);
+        }
+
+        assert!(graphics.device_lost.load(Ordering::SeqCst));
+
+        graphics.recover_device()
+            .await
+            .unwrap();
+
+        assert!(!graphics.device_lost.load(Ordering::SeqCst));
+        assert_eq!(graphics.read_buffer(id).await.unwrap(), [1, 2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn unsupported_present_mode_falls_back_to_fifo() {
+        let capabilities = wgpu::SurfaceCapabilities {
+            present_modes: vec![wgpu::PresentMode::Fifo, wgpu::PresentMode::Immediate],
+            ..Default::default()
+        };
+
+        let mailbox = GraphicsConfig {
+            present_mode: wgpu::PresentMode::Mailbox,
+            ..Default::default()
+        };
+
+        let immediate = GraphicsConfig {
+            present_mode: wgpu::PresentMode::Immediate,
+            ..Default::default()
+        };
+
+        assert_eq!(mailbox.present_mode_for(&capabilities), wgpu::PresentMode::Fifo);
+        assert_eq!(immediate.present_mode_for(&capabilities), wgpu::PresentMode::Immediate);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn adapter_request_falls_back_to_every_backend() {
+        let mut requested = Vec::new();
+
+        let found = GraphicsSubsystem::with_backend_fallback(wgpu::Backends::VULKAN, |backends| {
+            requested.push(backends);
+            async move {
+                match backends == wgpu::Backends::all() {
+                    true => Some(backends),
+                    false => None,
+                }
+            }
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(found, wgpu::Backends::all());
+        assert_eq!(requested, [
+            wgpu::Backends::VULKAN,
+            wgpu::Backends::VULKAN,
+            wgpu::Backends::VULKAN,
+            wgpu::Backends::all(),
+        ]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn adapter_request_error_lists_the_backends_tried() {
+        let err = GraphicsSubsystem::with_backend_fallback(wgpu::Backends::GL, |_| async { None::<()> })
+            .await
+            .unwrap_err();
+
+        assert!(err.to_string().contains(&format!("{:?}", [wgpu::Backends::GL, wgpu::Backends::all()])));
+    }
+
+    #[tokio::test]
+    async fn shutdown_releases_the_device() {
+        let Some(graphics) = with_device(GraphicsConfig::default()).await else {
+            return;
+        };
+
+        graphics.shutdown()
+            .await
+            .unwrap();
+
+        assert!(graphics.device.read().await.is_none());
+        assert!(graphics.queue.read().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn clear_color_is_clamped() {
+        let graphics = graphics_subsystem(GraphicsConfig::default());
+
+        assert_eq!(graphics.clear_color().await, DEFAULT_CLEAR_COLOR);
+
+        graphics.set_clear_color((2.0, -1.0, 0.5, 1.5))
+            .await;
+
+        assert_eq!(graphics.clear_color().await, wgpu::Color { r: 1.0, g: 0.0, b: 0.5, a: 1.0 });
+    }
+
+    #[tokio::test]
+    async fn buffer_round_trips_its_unaligned_bytes() {
+        let Some(graphics) = with_device(GraphicsConfig::default()).await else {
+            return;
+        };
+
+        let data = vec![1u8, 2, 3, 4, 5];
+
+        let id = graphics.create_buffer(data.clone(), wgpu::BufferUsages::STORAGE)
+            .await
+            .unwrap();
+
+        assert_eq!(graphics.read_buffer(id).await.unwrap(), data);
+
+        assert!(graphics.destroy_buffer(id).await);
+        assert!(graphics.read_buffer(id).await.is_err());
+        assert!(!graphics.destroy_buffer(id).await);
+
+        assert!(graphics.create_buffer(data, wgpu::BufferUsages::MAP_READ).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn selected_adapter_is_the_listed_one() {
+        let Some(mut graphics) = with_device(GraphicsConfig::default()).await else {
+            return;
+        };
+
+        let adapters = graphics.list_adapters()
+            .await;
+
+        assert!(!adapters.is_empty());
+
+        for (index, adapter) in adapters.iter().enumerate() {
+            assert!(adapter.starts_with(&format!("{}: ", index)), "{}", adapter);
+        }
+
+        let out_of_range = graphics.select_adapter(adapters.len())
+            .await
+            .unwrap_err();
+
+        assert!(out_of_range.to_string().contains(&format!("No adapter at index {}", adapters.len())), "{}", out_of_range);
+        assert_eq!(graphics.adapter_index, None);
+        assert!(graphics.device.read().await.is_some());
+
+        let last = adapters.len() - 1;
+
+        graphics.select_adapter(last)
+            .await
+            .unwrap();
+
+        let name = graphics.adapter
+            .read()
+            .await
+            .as_ref()
+            .unwrap()
+            .get_info()
+            .name;
+
+        assert_eq!(graphics.adapter_index, Some(last));
+        assert!(adapters[last].starts_with(&format!("{}: {} (", last, name)), "{} vs {}", adapters[last], name);
+        assert!(graphics.device.read().await.is_some());
     }
 }
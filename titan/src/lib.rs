@@ -1,31 +1,85 @@
-mod engine;
-mod graphics;
+pub mod config;
+pub mod engine;
+#[cfg(feature = "graphics")]
+pub mod graphics;
+#[cfg(feature = "metrics")]
+mod metrics;
+pub mod random;
+#[cfg(feature = "stats")]
+mod stats;
+#[cfg(feature = "terminal")]
 mod terminal;
+pub mod time;
+pub mod world;
 
 use engine::EngineSubsystem;
+use random::RandomSubsystem;
+use time::TimeSubsystem;
+use world::WorldSubsystem;
+#[cfg(feature = "graphics")]
 use graphics::GraphicsSubsystem;
-use std::path::PathBuf;
+#[cfg(feature = "terminal")]
+use ratatui::widgets::TableState;
+#[cfg(feature = "graphics")]
+use std::sync::{atomic::{AtomicBool, AtomicU64}, Arc};
+#[cfg(feature = "json-logs")]
+use titan_core::tracing_subscriber::fmt;
+use std::{any::Any, collections::{HashMap, VecDeque}, future::Future, io, ops::{Deref, DerefMut}};
 use tasks::TasksSubsystem;
+#[cfg(feature = "terminal")]
 use terminal::{TermView, TerminalSubsystem};
 use titan_assets::ResourceSubsystem;
 use titan_core::{
-    runtime::runtime::Builder, tasks, tracing_subscriber::{self, layer::SubscriberExt, util::SubscriberInitExt, EnvFilter},
-    ArcLock, Channels, IndexMap, Subsystem, SubsystemRef
+    logs::LogHistory, runtime::{self, runtime::{Builder, Runtime}, signal, time::Instant}, tasks, tracing_subscriber::{self, layer::SubscriberExt, util::SubscriberInitExt, EnvFilter},
+    ArcLock, IndexMap
 };
 
-pub use titan_core::{async_trait, Result, info, error, warn};
+pub use titan_core::{async_trait, subsystem, task, Channels, Result, Subsystem, SubsystemRef, info, error, warn};
+pub use titan_core;
+pub use config::{EngineSubsystems, RunConfig, TitanConfig};
+#[cfg(feature = "terminal")]
+pub use terminal::Theme;
 
 #[async_trait]
 pub trait App: Send + Sync + 'static {
     async fn init(&self) -> Result<()>;
     async fn shutdown(&self) -> Result<()>;
+
+    /// Starts app subsystems and adds their refs to `channels`, so they share the
+    /// engine's task, log and benchmark infrastructure.
+    async fn register_subsystems(&self, _channels: &mut Channels) -> Result<()> {
+        Ok(())
+    }
+
+    /// Engine configuration `run` starts the runtime and subsystems with. `config` is
+    /// loaded from `titan.toml` when present; override fields here to take precedence.
+    fn config(&self, config: TitanConfig) -> TitanConfig {
+        config
+    }
 }
 
 pub fn run(app: impl App) -> Result<()> {
+    let config = app.config(TitanConfig::load()?);
+
+    run_with_config(app, config)
+}
+
+/// `run` without the terminal and graphics subsystems, for CI, benchmarks and servers
+/// that have neither a TTY nor a GPU. The engine loop runs until `RequestQuit`.
+pub fn run_headless(app: impl App) -> Result<()> {
+    let mut config = app.config(TitanConfig::load()?);
+    config.subsystems = EngineSubsystems::HEADLESS;
+
+    run_with_config(app, config)
+}
+
+/// `run` with an explicit config in place of `App::config`.
+pub fn run_with_config(app: impl App, config: TitanConfig) -> Result<()> {
+    let log_history = LogHistory::new(config.log_history);
 
     #[cfg(feature = "tracing")] {
         let filter = EnvFilter::try_from_default_env()
-            .unwrap_or_else(|_| EnvFilter::new("info"));
+            .unwrap_or_else(|_| EnvFilter::new(&config.log_level));
         
         let subscriber = tracing_subscriber::fmt::layer()
             .with_ansi(true)
@@ -36,125 +90,370 @@ pub fn run(app: impl App) -> Result<()> {
             .with_thread_names(false)
             .with_target(false);
 
-        tracing_subscriber::registry()
+        #[cfg(feature = "json-logs")]
+        let (subscriber, json_subscriber) = match config.runtime.json_logs {
+            true => {
+                let json_subscriber = json_logs(std::io::stdout);
+
+                (None, Some(json_subscriber))
+            },
+            false => (Some(subscriber), None),
+        };
+
+        let registry = tracing_subscriber::registry()
             .with(filter)
             .with(subscriber)
-            .init();
+            .with(log_history.clone());
+
+        #[cfg(feature = "json-logs")]
+        let registry = registry.with(json_subscriber);
+
+        registry.init();
+    }
+
+    // Without tracing the terminal installs logging, so headless apps need their own.
+    #[cfg(not(feature = "tracing"))]
+    if !cfg!(feature = "terminal") || !config.subsystems.terminal {
+        init_plain_logs(&log_history);
     }
             
-    let runtime = Builder::new_multi_thread()
-        .thread_name("titan")
-        .enable_all()
-        .build()?;
+    let runtime = build_runtime(&config.runtime)?;
 
     let run_result: Result<()> = runtime.block_on(async move {        
-        let channels = start_subsystems(app)?;
+        let channels = start_subsystems(app, config, log_history)
+            .await?;
 
-        channels
-            .get::<EngineSubsystem>()
-            .send(engine::Init)
-            .await??;
+        run_until_quit(&channels, signal::ctrl_c())
+            .await
+    });
+
+    run_result
+}
 
-        // Note: Don't lock subsystem tasks in an
-        // infinite loop as this can potentiallly
-        // interfere with concurrency.
-        // Better to have our main loop in the main thread
-        // unbounded from any subsystem.
+/// Runs the engine loop until quit is requested, by the app or by `interrupt`
+/// resolving, then shuts the engine down.
+async fn run_until_quit(channels: &Channels, interrupt: impl Future<Output = io::Result<()>> + Send + 'static) -> Result<()> {
+    let signal_channels = channels.clone();
 
-        let mut engine_quit = false;
-        
-        while !engine_quit {
-            channels
-                .get::<EngineSubsystem>()
-                .send(engine::Run)
-                .await??;
+    runtime::spawn(async move {
+        if interrupt.await.is_ok() {
+            info!("Interrupt received...");
 
-            engine_quit = channels
+            signal_channels
                 .get::<EngineSubsystem>()
-                .send(engine::ShouldQuit)
-                .await?;
+                .send_mut(engine::RequestQuit);
         }
+    });
+
+    let run_result = run_loop(channels)
+        .await;
+
+    info!("Shutting down...");
+
+    // Shutdown runs regardless of how the loop ended so the
+    // terminal is always restored before the error propagates.
+    let shutdown_result = channels
+        .get::<EngineSubsystem>()
+        .send(engine::Shutdown)
+        .await;
+
+    run_result?;
+    shutdown_result??;
+
+    Ok(())
+}
+
+fn build_runtime(run_config: &RunConfig) -> Result<Runtime> {
+    let mut builder = Builder::new_multi_thread();
 
-        info!("Shutting down...");
+    builder
+        .thread_name("titan")
+        .enable_all();
+
+    if let Some(worker_threads) = run_config.worker_threads {
+        builder.worker_threads(worker_threads);
+    }
+
+    if let Some(max_blocking_threads) = run_config.max_blocking_threads {
+        builder.max_blocking_threads(max_blocking_threads);
+    }
+
+    Ok(builder.build()?)
+}
+
+async fn run_loop(channels: &Channels) -> Result<()> {
+    channels
+        .get::<EngineSubsystem>()
+        .send(engine::Init)
+        .await??;
+
+    // Note: Don't lock subsystem tasks in an
+    // infinite loop as this can potentiallly
+    // interfere with concurrency.
+    // Better to have our main loop in the main thread
+    // unbounded from any subsystem.
 
+    let mut engine_quit = false;
+    
+    while !engine_quit {
         channels
             .get::<EngineSubsystem>()
-            .send(engine::Shutdown)
+            .send(engine::Run)
             .await??;
 
-        Ok(())
-    });
+        engine_quit = channels
+            .get::<EngineSubsystem>()
+            .send(engine::ShouldQuit)
+            .await?;
+    }
 
-    run_result
+    Ok(())
+}
+
+/// Channels of the engine's subsystems, holding the strong refs they're registered
+/// with weakly. Once it's dropped the subsystem loops stop as their queues drain, as
+/// `Channels` no longer keeps them alive on its own.
+pub struct EngineChannels {
+    channels: Channels,
+    _subsystems: Vec<Box<dyn Any + Send + Sync>>,
+}
+
+impl Deref for EngineChannels {
+    type Target = Channels;
+
+    fn deref(&self) -> &Channels {
+        &self.channels
+    }
+}
+
+impl DerefMut for EngineChannels {
+    fn deref_mut(&mut self) -> &mut Channels {
+        &mut self.channels
+    }
 }
 
-pub fn start_subsystems(app: impl App) -> Result<Channels> {
+pub async fn start_subsystems(app: impl App, config: TitanConfig, log_history: LogHistory) -> Result<EngineChannels> {
     let (engine_ref, engine_receiver) = SubsystemRef::<EngineSubsystem>::new();
+    #[cfg(feature = "graphics")]
     let (graphics_ref, graphics_receiver) = SubsystemRef::<GraphicsSubsystem>::new();
+    #[cfg(feature = "terminal")]
     let (terminal_ref, terminal_receiver) = SubsystemRef::<TerminalSubsystem>::new();
     let (tasks_ref, tasks_receiver) = SubsystemRef::<TasksSubsystem>::new();
     let (resources_ref, resources_receiver) = SubsystemRef::<ResourceSubsystem>::new();
+    let (random_ref, random_receiver) = SubsystemRef::<RandomSubsystem>::new();
+    let (time_ref, time_receiver) = SubsystemRef::<TimeSubsystem>::new();
+    let (world_ref, world_receiver) = SubsystemRef::<WorldSubsystem>::new();
+    #[cfg(feature = "metrics")]
+    let (metrics_ref, metrics_receiver) = SubsystemRef::<metrics::MetricsSubsystem>::new();
+    #[cfg(feature = "stats")]
+    let (stats_ref, stats_receiver) = SubsystemRef::<stats::StatsSubsystem>::new();
 
     let mut channels = Channels::default();
+    let mut subsystems: Vec<Box<dyn Any + Send + Sync>> = Vec::new();
     
-    channels.add(engine_ref);
-    channels.add(graphics_ref);
-    channels.add(terminal_ref);
-    channels.add(tasks_ref);
-    channels.add(resources_ref);
+    channels.add_weak(&engine_ref).await;
+    subsystems.push(Box::new(engine_ref));
+    #[cfg(feature = "graphics")]
+    if config.subsystems.graphics {
+        channels.add_weak(&graphics_ref).await;
+        subsystems.push(Box::new(graphics_ref));
+    }
+    #[cfg(feature = "terminal")]
+    if config.subsystems.terminal {
+        channels.add_weak(&terminal_ref).await;
+        subsystems.push(Box::new(terminal_ref));
+    }
+    channels.add_weak(&tasks_ref).await;
+    subsystems.push(Box::new(tasks_ref));
+    channels.add_weak(&resources_ref).await;
+    subsystems.push(Box::new(resources_ref));
+    channels.add_weak(&random_ref).await;
+    subsystems.push(Box::new(random_ref));
+    channels.add_weak(&time_ref).await;
+    subsystems.push(Box::new(time_ref));
+    channels.add_weak(&world_ref).await;
+    subsystems.push(Box::new(world_ref));
+    #[cfg(feature = "metrics")] {
+        channels.add_weak(&metrics_ref).await;
+        subsystems.push(Box::new(metrics_ref));
+    }
+    #[cfg(feature = "stats")] {
+        channels.add_weak(&stats_ref).await;
+        subsystems.push(Box::new(stats_ref));
+    }
 
     TasksSubsystem::start_quiet(
         TasksSubsystem {
             channels: channels.clone(),
             tasks: ArcLock::new(IndexMap::new()),
             benchmarks: ArcLock::new(IndexMap::new()),
+            formatters: ArcLock::new(HashMap::new()),
+            log_history: log_history.clone(),
+            audit: ArcLock::new(VecDeque::new()),
+            audit_capacity: config.audit_capacity,
+            max_task_depth: config.max_task_depth,
         },
         tasks_receiver,
     );
 
-    TerminalSubsystem::start(
-        TerminalSubsystem {
+    #[cfg(feature = "terminal")]
+    if config.subsystems.terminal {
+        TerminalSubsystem::start(
+            TerminalSubsystem {
+                channels: channels.clone(),
+                terminal: None,
+                view: TermView::Tasks,
+                task_displays: Vec::new(),
+                refresh_interval: config.refresh_interval,
+                last_draw: None,
+                redraw: true,
+                task_state: TableState::default(),
+                bench_state: TableState::default(),
+                table_areas: Vec::new(),
+                filter: String::new(),
+                searching: false,
+                color: config.color && std::env::var_os("NO_COLOR").is_none(),
+                detail: false,
+                last_hash: None,
+                log_history,
+                last_status: None,
+                flash: None,
+                theme: config.theme.clone(),
+            },
+            terminal_receiver,
+            channels.get::<TasksSubsystem>(),
+        );
+    }
+
+    // Metrics runs quiet so its own subscriptions don't feed back into task logs.
+    #[cfg(feature = "metrics")]
+    metrics::MetricsSubsystem::start_quiet(
+        metrics::MetricsSubsystem {
+            channels: channels.clone(),
+            address: config.metrics_address,
+            completions: ArcLock::new(IndexMap::new()),
+        },
+        metrics_receiver,
+    );
+
+    #[cfg(feature = "stats")]
+    stats::StatsSubsystem::start(
+        stats::StatsSubsystem {
             channels: channels.clone(),
-            terminal: None,
-            view: TermView::Tasks,
-            task_displays: Vec::new(),
+            system: sysinfo::System::new(),
+            pid: None,
         },
-        terminal_receiver,
+        stats_receiver,
         channels.get::<TasksSubsystem>(),
     );
 
     ResourceSubsystem::start(
         ResourceSubsystem {
             channels: channels.clone(),
-            assets_dir: PathBuf::from("/resources"),
+            assets_dir: config.assets_dir,
             watcher: ArcLock::new(None),
+            resources: ArcLock::new(HashMap::new()),
         },
         resources_receiver,
         channels.get::<TasksSubsystem>(),
     );
 
-    GraphicsSubsystem::start(
-        GraphicsSubsystem {
-            channels: channels.clone(),
-            device: ArcLock::new(None),
-            queue: ArcLock::new(None),
-        },
-        graphics_receiver,
+    RandomSubsystem::start(
+        RandomSubsystem::new(channels.clone(), config.random_seed),
+        random_receiver,
         channels.get::<TasksSubsystem>(),
     );
 
+    TimeSubsystem::start(
+        TimeSubsystem::new(channels.clone()),
+        time_receiver,
+        channels.get::<TasksSubsystem>(),
+    );
+
+    WorldSubsystem::start(
+        WorldSubsystem::new(channels.clone()),
+        world_receiver,
+        channels.get::<TasksSubsystem>(),
+    );
+
+    #[cfg(feature = "graphics")]
+    if config.subsystems.graphics {
+        GraphicsSubsystem::start(
+            GraphicsSubsystem {
+                channels: channels.clone(),
+                config: config.graphics,
+                instance: ArcLock::new(None),
+                adapter: ArcLock::new(None),
+                device: ArcLock::new(None),
+                queue: ArcLock::new(None),
+                window: None,
+                surface: ArcLock::new(None),
+                sample_count: ArcLock::new(1),
+                msaa_target: ArcLock::new(None),
+                device_lost: Arc::new(AtomicBool::new(false)),
+                clear_color: ArcLock::new(graphics::DEFAULT_CLEAR_COLOR),
+                buffers: ArcLock::new(HashMap::new()),
+                next_buffer_id: Arc::new(AtomicU64::new(0)),
+                adapter_index: None,
+            },
+            graphics_receiver,
+            channels.get::<TasksSubsystem>(),
+        );
+    }
+
+    app.register_subsystems(&mut channels)
+        .await?;
+
     EngineSubsystem::start(
         EngineSubsystem {
             channels: channels.clone(),
             quit: false,
             app: Box::new(app),
-            renders: 0,
+            renders: ArcLock::new(0),
+            started: Instant::now(),
+            frame_limit: config.frame_limit,
+            subsystems: config.subsystems,
+            config_source: config.source,
         },
         engine_receiver,
         channels.get::<TasksSubsystem>(),
     );
 
-    Ok(channels)
+    Ok(EngineChannels {
+        channels,
+        _subsystems: subsystems,
+    })
+}
+
+/// Newline-delimited JSON logs written to `make_writer`, with the fields of the
+/// task span they were logged in.
+#[cfg(feature = "json-logs")]
+fn json_logs<S, W>(make_writer: W) -> fmt::Layer<S, fmt::format::JsonFields, fmt::format::Format<fmt::format::Json>, W>
+where
+    S: titan_core::tracing::Subscriber + for<'a> tracing_subscriber::registry::LookupSpan<'a>,
+    W: for<'a> fmt::MakeWriter<'a> + 'static,
+{
+    fmt::layer()
+        .json()
+        .with_current_span(true)
+        .with_span_list(false)
+        .with_writer(make_writer)
+}
+
+/// Plain stderr logging for when there's no TUI to host the logs. With tracing, `run`
+/// has already installed its own subscriber.
+#[cfg(not(feature = "tracing"))]
+pub(crate) fn init_plain_logs(log_history: &LogHistory) {
+    let plain_logs = tracing_subscriber::fmt::layer()
+        .with_writer(std::io::stderr)
+        .with_ansi(false)
+        .without_time();
+
+    let _ = tracing_subscriber::registry()
+        .with(tracing_subscriber::filter::LevelFilter::INFO)
+        .with(plain_logs)
+        .with(log_history.clone())
+        .try_init();
 }
 
 pub async fn stop_subsystems(stops: Vec<ArcLock<bool>>) {
@@ -163,3 +462,323 @@ pub async fn stop_subsystems(stops: Vec<ArcLock<bool>>) {
             .await;
     }
 }
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    pub struct TestApp;
+
+    #[async_trait]
+    impl App for TestApp {
+        async fn init(&self) -> Result<()> {
+            Ok(())
+        }
+
+        async fn shutdown(&self) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    pub struct EchoSubsystem {
+        channels: Channels,
+    }
+
+    #[titan_core::subsystem]
+    impl EchoSubsystem {
+
+        #[titan_core::task]
+        fn echo(&self, message: String) -> String {
+            message
+        }
+    }
+
+    /// Registers `EchoSubsystem` alongside the engine's own.
+    struct EchoApp;
+
+    #[async_trait]
+    impl App for EchoApp {
+        async fn init(&self) -> Result<()> {
+            Ok(())
+        }
+
+        async fn shutdown(&self) -> Result<()> {
+            Ok(())
+        }
+
+        async fn register_subsystems(&self, channels: &mut Channels) -> Result<()> {
+            let (echo_ref, echo_receiver) = SubsystemRef::<EchoSubsystem>::new();
+
+            channels.add(echo_ref)
+                .await;
+
+            EchoSubsystem::start(
+                EchoSubsystem { channels: channels.clone() },
+                echo_receiver,
+                channels.get::<TasksSubsystem>(),
+            );
+
+            Ok(())
+        }
+    }
+
+    /// Overrides the seed in `App::config`.
+    struct SeededApp;
+
+    #[async_trait]
+    impl App for SeededApp {
+        async fn init(&self) -> Result<()> {
+            Ok(())
+        }
+
+        async fn shutdown(&self) -> Result<()> {
+            Ok(())
+        }
+
+        fn config(&self, config: TitanConfig) -> TitanConfig {
+            TitanConfig {
+                random_seed: Some(7),
+                ..config
+            }
+        }
+    }
+
+    /// Requests quit as soon as its subsystems are registered.
+    #[cfg(not(feature = "tracing"))]
+    struct QuittingApp;
+
+    #[cfg(not(feature = "tracing"))]
+    #[async_trait]
+    impl App for QuittingApp {
+        async fn init(&self) -> Result<()> {
+            Ok(())
+        }
+
+        async fn shutdown(&self) -> Result<()> {
+            Ok(())
+        }
+
+        async fn register_subsystems(&self, channels: &mut Channels) -> Result<()> {
+            channels
+                .get::<EngineSubsystem>()
+                .send_mut(engine::RequestQuit);
+
+            Ok(())
+        }
+    }
+
+    /// `start_subsystems` for `TestApp` without a terminal or GPU.
+    pub async fn start_headless(config: TitanConfig) -> EngineChannels {
+        let config = TitanConfig {
+            subsystems: EngineSubsystems::HEADLESS,
+            ..config
+        };
+
+        start_subsystems(TestApp, config, LogHistory::default())
+            .await
+            .unwrap()
+    }
+
+    #[test]
+    fn runtime_gets_the_configured_worker_threads() {
+        let run_config = RunConfig {
+            worker_threads: Some(3),
+            ..Default::default()
+        };
+
+        let runtime = build_runtime(&run_config)
+            .unwrap();
+
+        assert_eq!(runtime.metrics().num_workers(), 3);
+    }
+
+    /// Whether a task called `name` shows up in the audit. Finished tasks reach the
+    /// audit after their caller has been answered, so this waits a little for it.
+    async fn audited(channels: &Channels, name: &str) -> bool {
+        for _ in 0..100 {
+            let audit = channels
+                .get::<TasksSubsystem>()
+                .send(tasks::GetAudit { n: 16 })
+                .await
+                .unwrap();
+
+            if audit.iter().any(|entry| entry.name == name) {
+                return true;
+            }
+
+            runtime::time::sleep(std::time::Duration::from_millis(10))
+                .await;
+        }
+
+        false
+    }
+
+    /// Keeps everything written to it, for reading log output back.
+    #[cfg(feature = "json-logs")]
+    #[derive(Clone, Default)]
+    struct CapturedLogs(std::sync::Arc<std::sync::Mutex<Vec<u8>>>);
+
+    #[cfg(feature = "json-logs")]
+    impl io::Write for CapturedLogs {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[cfg(feature = "json-logs")]
+    #[test]
+    fn json_logs_carry_the_task_fields() {
+        let captured = CapturedLogs::default();
+        let writer = captured.clone();
+
+        let subscriber = tracing_subscriber::registry()
+            .with(json_logs(move || writer.clone()));
+
+        // A current thread runtime keeps the subsystem loops on this thread's subscriber.
+        titan_core::tracing::subscriber::with_default(subscriber, || {
+            Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .unwrap()
+                .block_on(async {
+                    start_headless(TitanConfig::default())
+                        .await
+                        .get::<EngineSubsystem>()
+                        .send_mut(engine::RequestQuit)
+                        .await
+                        .unwrap();
+                });
+        });
+
+        let logs = String::from_utf8(captured.0.lock().unwrap().clone())
+            .unwrap();
+
+        let quit = logs
+            .lines()
+            .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+            .find(|line| line["fields"]["message"] == "Quit requested...")
+            .expect("RequestQuit's log line wasn't captured");
+
+        assert_eq!(quit["level"], "INFO");
+        assert_eq!(quit["span"]["task"], "EngineSubsystem::RequestQuit");
+        assert_eq!(quit["span"]["subsystem"], "EngineSubsystem");
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn app_subsystems_share_the_engine_channels() {
+        let config = TitanConfig {
+            subsystems: EngineSubsystems::HEADLESS,
+            ..Default::default()
+        };
+
+        let channels = start_subsystems(EchoApp, config, LogHistory::default())
+            .await
+            .unwrap();
+
+        let echoed = channels
+            .get::<EchoSubsystem>()
+            .send(Echo { message: String::from("hello") })
+            .await
+            .unwrap();
+
+        assert_eq!(echoed, "hello");
+        assert!(audited(&channels, "EchoSubsystem::Echo").await);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn interrupt_runs_the_normal_shutdown() {
+        let channels = start_headless(TitanConfig::default())
+            .await;
+
+        let run = run_until_quit(&channels, async { Ok(()) });
+
+        runtime::time::timeout(std::time::Duration::from_secs(5), run)
+            .await
+            .expect("The interrupt didn't end the engine loop")
+            .unwrap();
+
+        assert!(audited(&channels, "EngineSubsystem::Shutdown").await);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn failed_frame_still_shuts_down() {
+        let mut channels = start_headless(TitanConfig::default())
+            .await;
+
+        // With the time subsystem's receiver gone the first `Tick` fails, and the frame with it.
+        let (time_ref, time_receiver) = SubsystemRef::<TimeSubsystem>::new();
+        drop(time_receiver);
+
+        channels.add(time_ref)
+            .await;
+
+        let run = run_until_quit(&channels, std::future::pending());
+
+        let run_result = runtime::time::timeout(std::time::Duration::from_secs(5), run)
+            .await
+            .expect("The failed frame didn't end the engine loop");
+
+        assert!(run_result.is_err());
+        assert!(audited(&channels, "EngineSubsystem::Shutdown").await);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn dropping_the_engine_channels_stops_the_subsystems() {
+        let engine = start_headless(TitanConfig::default())
+            .await;
+
+        let channels = engine.clone();
+
+        assert!(channels.try_get::<WorldSubsystem>().is_ok());
+
+        drop(engine);
+
+        // Every subsystem loop holds a tasks ref until it ends, so tasks goes last.
+        let stopped = async {
+            while channels.try_get::<TasksSubsystem>().is_ok() {
+                runtime::time::sleep(std::time::Duration::from_millis(10))
+                    .await;
+            }
+        };
+
+        runtime::time::timeout(std::time::Duration::from_secs(5), stopped)
+            .await
+            .expect("The subsystems outlived their strong refs");
+
+        assert!(channels.try_get::<EngineSubsystem>().is_err());
+        assert!(channels.try_get::<WorldSubsystem>().is_err());
+    }
+
+    #[tokio::test]
+    async fn app_config_reaches_the_subsystems() {
+        let config = SeededApp.config(TitanConfig {
+            subsystems: EngineSubsystems::HEADLESS,
+            ..Default::default()
+        });
+
+        let channels = start_subsystems(SeededApp, config, LogHistory::default())
+            .await
+            .unwrap();
+
+        let seed = channels
+            .get::<RandomSubsystem>()
+            .send(random::Seed)
+            .await
+            .unwrap();
+
+        assert_eq!(seed, 7);
+    }
+
+    // With tracing, `run` installs the global subscriber, which other tests may already have set.
+    #[cfg(not(feature = "tracing"))]
+    #[test]
+    fn headless_run_ends_on_quit() {
+        run_headless(QuittingApp)
+            .unwrap();
+    }
+}
@@ -0,0 +1,190 @@
+use std::{fmt::Write, net::SocketAddr};
+use titan_core::{
+    error, info,
+    runtime::{
+        self,
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::{TcpListener, TcpStream},
+    },
+    tasks::{self, TaskEnd, TasksSubsystem},
+    ArcLock, Channels, IndexMap, Result,
+};
+
+pub struct MetricsSubsystem {
+    pub channels: Channels,
+    pub address: SocketAddr,
+    pub completions: ArcLock<IndexMap<&'static str, u64>>,
+}
+
+#[titan_core::subsystem]
+impl MetricsSubsystem {
+
+    async fn on_start(&mut self) -> Result<()> {
+        self.channels
+            .subscribe::<tasks::EndTask, RecordTask>()
+            .await?;
+
+        let listener = TcpListener::bind(self.address)
+            .await?;
+
+        info!("Metrics: Serving on http://{}/metrics", self.address);
+
+        runtime::spawn(Self::serve(listener, self.channels.clone()));
+
+        Ok(())
+    }
+
+    #[titan_core::task]
+    async fn record_task(&self, task_end: TaskEnd) {
+        *self.completions
+            .lock()
+            .await
+            .entry(task_end.name)
+            .or_insert(0) += 1;
+    }
+
+    #[titan_core::task]
+    async fn export(&self) -> Result<String> {
+        let benchmarks = self.channels
+            .get::<TasksSubsystem>()
+            .send(tasks::GetBenchmarks)
+            .await?;
+
+        let mut metrics = String::new();
+
+        writeln!(metrics, "# HELP titan_task_completions_total Completed task executions.")?;
+        writeln!(metrics, "# TYPE titan_task_completions_total counter")?;
+
+        for (name, count) in self.completions.read().await.iter() {
+            writeln!(metrics, "titan_task_completions_total{{task=\"{}\"}} {}", name, count)?;
+        }
+
+        writeln!(metrics, "# HELP titan_benchmark_average_seconds Average benchmark duration.")?;
+        writeln!(metrics, "# TYPE titan_benchmark_average_seconds gauge")?;
+
+        for bench in benchmarks.iter() {
+            writeln!(metrics, "titan_benchmark_average_seconds{{benchmark=\"{}\"}} {}", bench.name, bench.average)?;
+        }
+
+        writeln!(metrics, "# HELP titan_benchmark_runs_total Completed benchmark runs.")?;
+        writeln!(metrics, "# TYPE titan_benchmark_runs_total counter")?;
+
+        for bench in benchmarks.iter() {
+            writeln!(metrics, "titan_benchmark_runs_total{{benchmark=\"{}\"}} {}", bench.name, bench.runs)?;
+        }
+
+        Ok(metrics)
+    }
+
+    async fn serve(listener: TcpListener, channels: Channels) {
+        loop {
+            let stream = match listener.accept().await {
+                Ok((stream, _)) => stream,
+                Err(err) => {
+                    error!("Metrics: Failed to accept connection: {}", err);
+                    continue;
+                }
+            };
+
+            let channels = channels.clone();
+
+            runtime::spawn(async move {
+                if let Err(err) = Self::respond(stream, channels).await {
+                    error!("Metrics: Failed to respond: {}", err);
+                }
+            });
+        }
+    }
+
+    async fn respond(mut stream: TcpStream, channels: Channels) -> Result<()> {
+        let mut buffer = [0; 1024];
+
+        let read = stream.read(&mut buffer)
+            .await?;
+
+        let request = String::from_utf8_lossy(&buffer[..read]);
+
+        let response = match request.starts_with("GET /metrics") {
+            true => {
+                let body = channels
+                    .get::<MetricsSubsystem>()
+                    .send(Export)
+                    .await??;
+
+                format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\n\r\n{}",
+                    body.len(),
+                    body
+                )
+            },
+            false => "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\n\r\n".to_string(),
+        };
+
+        stream.write_all(response.as_bytes())
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{engine::{self, EngineSubsystem}, TitanConfig};
+    use std::time::Duration;
+
+    async fn scrape(address: SocketAddr) -> Result<String> {
+        let mut stream = TcpStream::connect(address)
+            .await?;
+
+        stream.write_all(b"GET /metrics HTTP/1.1\r\n\r\n")
+            .await?;
+
+        let mut response = String::new();
+
+        stream.read_to_string(&mut response)
+            .await?;
+
+        Ok(response)
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn metrics_endpoint_serves_prometheus_text() {
+        let address = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap();
+
+        let channels = crate::tests::start_headless(TitanConfig { metrics_address: address, ..Default::default() })
+            .await;
+
+        channels
+            .get::<EngineSubsystem>()
+            .send(engine::ShouldQuit)
+            .await
+            .unwrap();
+
+        // Binding and recording the completion both happen off the caller's path.
+        let completion = "titan_task_completions_total{task=\"EngineSubsystem::ShouldQuit\"} 1";
+        let mut response = String::new();
+
+        for _ in 0..100 {
+            response = scrape(address)
+                .await
+                .unwrap_or_default();
+
+            if response.contains(completion) {
+                break;
+            }
+
+            runtime::time::sleep(Duration::from_millis(10))
+                .await;
+        }
+
+        assert!(response.starts_with("HTTP/1.1 200 OK"), "{}", response);
+        assert!(response.contains("Content-Type: text/plain; version=0.0.4"));
+        assert!(response.contains("# TYPE titan_task_completions_total counter"));
+        assert!(response.contains("# TYPE titan_benchmark_average_seconds gauge"));
+        assert!(response.contains(completion), "{}", response);
+    }
+}
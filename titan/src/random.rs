@@ -0,0 +1,108 @@
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use titan_core::{info, Channels, Result, anyhow};
+
+/// Seedable randomness. Its tasks are mutable, so they run one at a time in send order
+/// and a given seed always yields the same sequence.
+pub struct RandomSubsystem {
+    pub channels: Channels,
+    pub rng: ChaCha8Rng,
+    /// Seed `rng` was last seeded with, logged so a run can be replayed.
+    pub seed: u64,
+}
+
+impl RandomSubsystem {
+    /// Seeded with `seed`, or a random seed when `None`.
+    pub fn new(channels: Channels, seed: Option<u64>) -> Self {
+        let seed = seed.unwrap_or_else(rand::random);
+
+        Self {
+            channels,
+            rng: ChaCha8Rng::seed_from_u64(seed),
+            seed,
+        }
+    }
+}
+
+#[titan_core::subsystem]
+impl RandomSubsystem {
+
+    fn on_start(&mut self) -> Result<()> {
+        info!("Random: Seeded with {}", self.seed);
+        Ok(())
+    }
+
+    /// Uniform in `[0, 1)`.
+    #[titan_core::task]
+    fn next_f32(&mut self) -> f32 {
+        self.rng.gen()
+    }
+
+    /// Uniform in `[min, max)`.
+    #[titan_core::task]
+    fn range(&mut self, min: f32, max: f32) -> Result<f32> {
+        if min.is_nan() || max.is_nan() || min >= max {
+            return Err(anyhow!("Random: Empty range {}..{}", min, max));
+        }
+
+        Ok(self.rng.gen_range(min..max))
+    }
+
+    /// Restarts the sequence from `seed`.
+    #[titan_core::task]
+    fn reseed(&mut self, seed: u64) {
+        info!("Random: Reseeded with {}", seed);
+
+        self.rng = ChaCha8Rng::seed_from_u64(seed);
+        self.seed = seed;
+    }
+
+    #[titan_core::task]
+    fn seed(&self) -> u64 {
+        self.seed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sequence(random: &mut RandomSubsystem) -> Vec<f32> {
+        (0..8)
+            .map(|_| random.next_f32())
+            .collect()
+    }
+
+    #[test]
+    fn same_seed_gives_the_same_sequence() {
+        let mut first = RandomSubsystem::new(Channels::default(), Some(42));
+        let mut second = RandomSubsystem::new(Channels::default(), Some(42));
+        let mut other = RandomSubsystem::new(Channels::default(), Some(43));
+
+        let expected = sequence(&mut first);
+
+        assert_eq!(sequence(&mut second), expected);
+        assert_ne!(sequence(&mut other), expected);
+        assert!(expected.iter().all(|value| (0.0..1.0).contains(value)));
+
+        other.reseed(42);
+
+        assert_eq!(other.seed(), 42);
+        assert_eq!(sequence(&mut other), expected);
+    }
+
+    #[test]
+    fn range_stays_in_bounds_and_rejects_empty_ranges() {
+        let mut random = RandomSubsystem::new(Channels::default(), Some(7));
+
+        for _ in 0..100 {
+            let value = random.range(-2.0, 3.0)
+                .unwrap();
+
+            assert!((-2.0..3.0).contains(&value));
+        }
+
+        assert!(random.range(1.0, 1.0).is_err());
+        assert!(random.range(f32::NAN, 1.0).is_err());
+    }
+}
@@ -0,0 +1,70 @@
+use sysinfo::{Pid, ProcessesToUpdate, System};
+use titan_core::{Channels, Result, anyhow};
+
+#[derive(Clone, Default)]
+pub struct ProcessUsage {
+    pub cpu: f32,
+    pub memory: u64,
+}
+
+pub struct StatsSubsystem {
+    pub channels: Channels,
+    pub system: System,
+    pub pid: Option<Pid>,
+}
+
+#[titan_core::subsystem]
+impl StatsSubsystem {
+
+    fn on_start(&mut self) -> Result<()> {
+        let pid = sysinfo::get_current_pid()
+            .map_err(|err| anyhow!("Stats: Failed to get current pid: {}", err))?;
+
+        self.pid = Some(pid);
+        
+        Ok(())
+    }
+
+    #[titan_core::task]
+    fn process_stats(&mut self) -> ProcessUsage {
+        let Some(pid) = self.pid else {
+            return ProcessUsage::default();
+        };
+
+        // CPU usage is measured between refreshes, so the first sample reads 0%.
+        self.system.refresh_processes(ProcessesToUpdate::Some(&[pid]), true);
+
+        self.system
+            .process(pid)
+            .map(|process| ProcessUsage {
+                cpu: process.cpu_usage(),
+                memory: process.memory(),
+            })
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn process_stats_are_plausible() {
+        let mut stats = StatsSubsystem {
+            channels: Channels::default(),
+            system: System::new(),
+            pid: None,
+        };
+
+        stats.on_start()
+            .unwrap();
+
+        // The first sample only primes CPU usage.
+        stats.process_stats();
+
+        let usage = stats.process_stats();
+
+        assert!(usage.cpu >= 0.0);
+        assert!(usage.memory > 0);
+    }
+}
@@ -1,22 +1,142 @@
 use crate::engine;
+#[cfg(feature = "stats")]
+use crate::stats;
 
 use ratatui::{
-    backend::CrosstermBackend,
-    crossterm::event::{self, Event},
-    layout::{Constraint, Direction, Layout},
+    backend::{Backend, CrosstermBackend},
+    crossterm::{
+        event::{self, DisableMouseCapture, EnableMouseCapture, Event, MouseButton, MouseEvent, MouseEventKind},
+        execute,
+    },
+    layout::{Constraint, Direction, Flex, Layout, Position, Rect},
     style::{palette::tailwind, Color, Style},
-    widgets::{Block, Borders, Cell, Row, Table},
+    widgets::{Block, Borders, Cell, Clear, Paragraph, Row, Table, TableState},
     Frame, Terminal,
 };
-use std::io::Stdout;
-use titan_core::{info, tasks::{self, GetBenchmarkDisplays}, Channels, Result};
+use std::{hash::{DefaultHasher, Hash, Hasher}, io::{self, IsTerminal, Stdout}, time::{Duration, SystemTime, UNIX_EPOCH}};
+use titan_core::{chrono, debug, error, info, runtime::time::Instant, tasks::{self, BenchmarkGroup, BenchmarkLog}, warn, Channels, Result, SubscriptionInfo};
+use titan_core::logs::LogHistory;
 use tui_logger::TuiLoggerWidget;
 
 type TitanTerminal = Terminal<CrosstermBackend<Stdout>>;
 
+#[derive(Default)]
+struct TermDisplays {
+    tasks: Vec<tasks::Display>,
+    benches: Vec<BenchmarkGroup>,
+    queues: Vec<tasks::Display>,
+    stats: Option<String>,
+    detail: Option<BenchmarkLog>,
+    subscriptions: Vec<SubscriptionInfo>,
+    status: Option<String>,
+}
+
+/// Recent samples listed in the benchmark detail popup.
+const DETAIL_SAMPLES: usize = 10;
+
+/// Time between the plain status lines written while drawing fails.
+const STATUS_LINE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How long `flash` messages stay in the status box.
+const FLASH_DURATION: Duration = Duration::from_secs(3);
+
+/// Colors used throughout the terminal UI.
+#[derive(Clone, Debug)]
+pub struct Theme {
+    pub name: &'static str,
+    pub header_fg: Color,
+    pub header_bg: Color,
+    pub row_fg: Color,
+    pub highlight_bg: Color,
+    pub title: Color,
+    pub error: Color,
+    pub warn: Color,
+    pub info: Color,
+    pub trace: Color,
+    pub debug: Color,
+}
+
+impl Theme {
+    pub fn dark() -> Self {
+        Self {
+            name: "dark",
+            header_fg: tailwind::SLATE.c200,
+            header_bg: tailwind::SLATE.c900,
+            row_fg: tailwind::SLATE.c200,
+            highlight_bg: tailwind::SLATE.c700,
+            title: Color::LightCyan,
+            error: Color::Red,
+            warn: Color::Yellow,
+            info: Color::Green,
+            trace: Color::Blue,
+            debug: Color::Magenta,
+        }
+    }
+
+    pub fn light() -> Self {
+        Self {
+            name: "light",
+            header_fg: tailwind::SLATE.c900,
+            header_bg: tailwind::SLATE.c200,
+            row_fg: tailwind::SLATE.c800,
+            highlight_bg: tailwind::SLATE.c300,
+            title: tailwind::CYAN.c700,
+            error: tailwind::RED.c700,
+            warn: tailwind::AMBER.c700,
+            info: tailwind::GREEN.c700,
+            trace: tailwind::BLUE.c700,
+            debug: tailwind::FUCHSIA.c700,
+        }
+    }
+
+    /// Themes cycled through with 't'.
+    pub fn presets() -> Vec<Theme> {
+        vec![Self::dark(), Self::light()]
+    }
+
+    /// The preset called `name`, as set by `theme` in `titan.toml`.
+    pub fn from_name(name: &str) -> Option<Theme> {
+        Self::presets()
+            .into_iter()
+            .find(|theme| theme.name.eq_ignore_ascii_case(name))
+    }
+
+    /// The preset after this one, wrapping around.
+    pub fn next(&self) -> Theme {
+        let presets = Self::presets();
+
+        let index = presets
+            .iter()
+            .position(|theme| theme.name == self.name)
+            .map_or(0, |index| (index + 1) % presets.len());
+
+        presets[index].clone()
+    }
+
+    /// This theme with every log level in the default style.
+    pub fn without_log_colors(&self) -> Theme {
+        Theme {
+            error: Color::Reset,
+            warn: Color::Reset,
+            info: Color::Reset,
+            trace: Color::Reset,
+            debug: Color::Reset,
+            ..self.clone()
+        }
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::dark()
+    }
+}
+
 pub enum TermView {
     Tasks,
     Log,
+    /// Registered subsystems and the subscriptions between their tasks.
+    Graph,
 }
 
 pub struct TerminalSubsystem {
@@ -24,106 +144,425 @@ pub struct TerminalSubsystem {
     pub terminal: Option<TitanTerminal>,
     pub view: TermView,
     pub task_displays: Vec<String>,
+    pub refresh_interval: Duration,
+    pub last_draw: Option<Instant>,
+    pub redraw: bool,
+    pub task_state: TableState,
+    pub bench_state: TableState,
+    pub table_areas: Vec<Rect>,
+    pub filter: String,
+    pub searching: bool,
+    /// Per-level log colors, toggled with 'c'.
+    pub color: bool,
+    /// Cycled through `Theme::presets` with 't'.
+    pub theme: Theme,
+    /// Shows the detail popup for the selected benchmark, opened with Enter.
+    pub detail: bool,
+    /// Hash of everything the last drawn frame showed, see `frame_hash`.
+    pub last_hash: Option<u64>,
+    /// Filled by the plain logging fallback; the TUI keeps its own history.
+    pub log_history: LogHistory,
+    /// Set while drawing fails, to when the last plain status line was written.
+    pub last_status: Option<Instant>,
+    /// Result of the last benchmark export with 's', shown for `FLASH_DURATION`.
+    pub flash: Option<(String, Instant)>,
 }
 
 #[titan_core::subsystem]
 impl TerminalSubsystem {
 
     #[titan_core::task]
-    async fn init(&mut self) -> Result<()> {        
+    async fn init(&mut self) -> Result<()> {
+        self.init_for(io::stdout().is_terminal())
+            .await
+    }
+
+    /// `init` for a stdout that is or isn't a TTY.
+    async fn init_for(&mut self, interactive: bool) -> Result<()> {
+        // Piped output or CI can't host the TUI, so log plainly to stderr instead.
+        if !interactive {
+            #[cfg(not(feature = "tracing"))]
+            crate::init_plain_logs(&self.log_history);
+
+            warn!("Terminal: stdout is not a TTY, falling back to plain logging");
+            return Ok(());
+        }
+
         tui_logger::init_logger(titan_core::log::LevelFilter::Trace)?;
                         
         self.terminal = Some(ratatui::init());
 
+        execute!(io::stdout(), EnableMouseCapture)?;
+
         self.channels
             .subscribe_mut::<tasks::StartTask, AddTaskDisplay>()
-            .await;
+            .await?;
                 
         Ok(())
     }
 
     #[titan_core::task]
-    async fn add_task_display(&mut self, id: String, name: &'static str, depth: usize) {
+    async fn add_task_display(&mut self, id: String, name: &'static str, depth: usize, _parent: Option<String>, _correlation: String, _queue_latency: f64) {
         info!("Hello from subscription!");
     }
     
     #[titan_core::task(benchmark)]
     async fn render(&mut self) -> Result<()> {
+        if self.terminal.is_none() {
+            return Ok(());
+        }
+
+        if self.draw_due() {
+            match self.draw().await {
+                Ok(()) => self.draw_recovered(),
+                Err(err) => self.draw_failed(err)?,
+            }
+        }
+        
+        self.events()
+            .await?;
+
+        Ok(())
+    }
+
+    /// Whether a redraw was requested or `refresh_interval` passed since the last draw.
+    fn draw_due(&self) -> bool {
+        self.redraw || self.last_draw
+            .is_none_or(|last_draw| last_draw.elapsed() >= self.refresh_interval)
+    }
+
+    // A failed draw shouldn't take the engine loop down with it, so the TUI is retried
+    // every tick and a plain status line stands in for it on stderr meanwhile.
+    fn draw_failed(&mut self, err: impl std::fmt::Display) -> Result<()> {
+        match self.last_status {
+            None => error!("Terminal: Draw failed, falling back to status lines: {}", err),
+            Some(_) => debug!("Terminal: Draw failed: {}", err),
+        }
+
+        self.redraw = true;
+
+        let status_due = self.last_status
+            .is_none_or(|last_status| last_status.elapsed() >= STATUS_LINE_INTERVAL);
+
+        if !status_due {
+            return Ok(());
+        }
+
+        let queues = self.channels
+            .pending_lens()?
+            .into_iter()
+            .map(|(name, pending)| format!("{}={}", name, pending))
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        eprintln!("titan: queued {}", queues);
+
+        self.last_status = Some(Instant::now());
+
+        Ok(())
+    }
+
+    fn draw_recovered(&mut self) {
+        if self.last_status.take().is_some() {
+            info!("Terminal: Drawing recovered");
+        }
+    }
+
+    async fn draw(&mut self) -> Result<()> {
         // let task_displays = self
         //     .channels
         //     .get::<tasks::TasksSubsystem>()
         //     .send(tasks::GetTaskDisplays)
         //     .await;
 
-        let benchmark_displays = self
+        let benchmark_groups = self
             .channels
             .get::<tasks::TasksSubsystem>()
-            .send(tasks::GetBenchmarkDisplays)
-            .await?;
-       
-        self.terminal
-            .as_mut()
-            .expect("Terminal not initialized!")
-            .draw(|f| Self::ui(f, &self.view, vec![], benchmark_displays))?;
-        
-        self.events()
+            .send(tasks::GetBenchmarkGroups)
             .await?;
 
+        let queue_displays = self.channels
+            .pending_lens()?
+            .into_iter()
+            .map(|(name, pending)| tasks::Display {
+                name: name.to_string(),
+                display: pending.to_string(),
+            })
+            .collect();
+
+        let detail = match self.detail {
+            true => self.selected_benchmark(&benchmark_groups).await?,
+            false => None,
+        };
+
+        let subscriptions = match self.view {
+            TermView::Graph => self.channels.subscriptions().await,
+            _ => Vec::new(),
+        };
+
+        let displays = TermDisplays {
+            benches: benchmark_groups,
+            queues: queue_displays,
+            detail,
+            stats: self.process_stats().await?,
+            subscriptions,
+            status: self.flash
+                .as_ref()
+                .filter(|(_, flashed)| flashed.elapsed() < FLASH_DURATION)
+                .map(|(message, _)| message.clone()),
+            ..Default::default()
+        };
+
+        let mut terminal = self.terminal
+            .take()
+            .expect("Terminal not initialized!");
+
+        let draw_result = self.draw_frame(&mut terminal, displays);
+
+        self.terminal = Some(terminal);
+
+        draw_result
+    }
+
+    /// Draws `displays` to `terminal`, unless they'd make the same frame as last time.
+    fn draw_frame<B: Backend>(&mut self, terminal: &mut Terminal<B>, displays: TermDisplays) -> Result<()> {
+        let search = match self.searching || !self.filter.is_empty() {
+            true => Some(self.filter.as_str()),
+            false => None,
+        };
+
+        let theme = self.active_theme();
+
+        // The log view follows the logger rather than `displays`, so it can't be hashed.
+        let frame_hash = match self.view {
+            TermView::Tasks | TermView::Graph => Some(self.frame_hash(search, &theme, &displays)),
+            TermView::Log => None,
+        };
+
+        if !self.redraw && frame_hash.is_some() && frame_hash == self.last_hash {
+            self.last_draw = Some(Instant::now());
+            return Ok(());
+        }
+
+        let mut table_areas = Vec::new();
+
+        terminal
+            .draw(|f| {
+                table_areas = Self::ui(
+                    f,
+                    &self.view,
+                    &mut self.task_state,
+                    &mut self.bench_state,
+                    search,
+                    &theme,
+                    displays,
+                );
+            })?;
+
+        self.table_areas = table_areas;
+
+        self.last_hash = frame_hash;
+        self.last_draw = Some(Instant::now());
+        self.redraw = false;
+
         Ok(())
     }
 
+    /// Hashes the inputs to `ui` for the tasks view, so unchanged frames can skip drawing.
+    fn frame_hash(&self, search: Option<&str>, theme: &Theme, displays: &TermDisplays) -> u64 {
+        let mut hasher = DefaultHasher::new();
+
+        std::mem::discriminant(&self.view).hash(&mut hasher);
+        self.task_state.selected().hash(&mut hasher);
+        self.task_state.offset().hash(&mut hasher);
+        self.bench_state.selected().hash(&mut hasher);
+        self.bench_state.offset().hash(&mut hasher);
+        search.hash(&mut hasher);
+        theme.name.hash(&mut hasher);
+        self.color.hash(&mut hasher);
+
+        displays.tasks.hash(&mut hasher);
+        displays.benches.hash(&mut hasher);
+        displays.queues.hash(&mut hasher);
+        displays.stats.hash(&mut hasher);
+        displays.subscriptions.hash(&mut hasher);
+        displays.status.hash(&mut hasher);
+
+        if let Some(detail) = &displays.detail {
+            detail.name.hash(&mut hasher);
+            detail.runs.hash(&mut hasher);
+            detail.display.hash(&mut hasher);
+
+            detail.samples
+                .iter()
+                .for_each(|sample| sample.to_bits().hash(&mut hasher));
+        }
+
+        hasher.finish()
+    }
+
+    /// The selected benchmark row, looked up through the same rows the table shows.
+    /// Group headers have no benchmark.
+    async fn selected_benchmark(&self, groups: &[BenchmarkGroup]) -> Result<Option<BenchmarkLog>> {
+        let Some(selected) = self.bench_state.selected() else {
+            return Ok(None);
+        };
+
+        let filter = self.filter.to_lowercase();
+
+        let Some(Some(selected)) = Self::benchmark_rows(groups, &filter).get(selected).map(|(_, bench)| *bench) else {
+            return Ok(None);
+        };
+
+        let benchmark = self
+            .channels
+            .get::<tasks::TasksSubsystem>()
+            .send(tasks::GetBenchmarks)
+            .await?
+            .into_iter()
+            .find(|bench| bench.name == selected.name);
+
+        Ok(benchmark)
+    }
+
+    /// Benchmark table rows: each group's header, with no display, followed by its
+    /// benchmarks matching `filter`. Groups with no matches are left out.
+    fn benchmark_rows<'a>(groups: &'a [BenchmarkGroup], filter: &str) -> Vec<(&'a str, Option<&'a tasks::Display>)> {
+        let mut rows = Vec::new();
+
+        for group in groups {
+            let mut benches = group.benches
+                .iter()
+                .filter(|bench| bench.name.to_lowercase().contains(filter))
+                .peekable();
+
+            if benches.peek().is_none() {
+                continue;
+            }
+
+            rows.push((group.name.as_str(), None));
+            rows.extend(benches.map(|bench| (group.name.as_str(), Some(bench))));
+        }
+
+        rows
+    }
+
+    #[cfg(feature = "stats")]
+    async fn process_stats(&self) -> Result<Option<String>> {
+        let usage = self
+            .channels
+            .get::<stats::StatsSubsystem>()
+            .send_mut(stats::ProcessStats)
+            .await?;
+
+        Ok(Some(format!(
+            "CPU {:>6.2}% | RSS {:>8.2} MiB",
+            usage.cpu,
+            usage.memory as f64 / (1024.0 * 1024.0),
+        )))
+    }
+
+    #[cfg(not(feature = "stats"))]
+    async fn process_stats(&self) -> Result<Option<String>> {
+        Ok(None)
+    }
+
     fn ui(
         frame: &mut Frame,
         view: &TermView,
-        tasks: Vec<tasks::Display>,
-        benches: Vec<tasks::Display>,
-    ) {
+        task_state: &mut TableState,
+        bench_state: &mut TableState,
+        search: Option<&str>,
+        theme: &Theme,
+        displays: TermDisplays,
+    ) -> Vec<Rect> {
         let headers = ["Name", "Display"]
             .into_iter()
             .map(Cell::from)
             .collect::<Row>()
             .style(
                 Style::new()
-                    .fg(tailwind::SLATE.c200)
-                    .bg(tailwind::SLATE.c900),
+                    .fg(theme.header_fg)
+                    .bg(theme.header_bg),
             )
             .height(1);
 
-        let task_rows = tasks.into_iter().map(|task| {
-            Row::new(vec![Cell::new(task.name), Cell::new(task.display)])
-                .style(Style::new().fg(tailwind::SLATE.c200))
-                .height(1)
-        });
+        let filter = search
+            .unwrap_or_default()
+            .to_lowercase();
+
+        let task_rows = displays.tasks.into_iter()
+            .filter(|task| task.name.to_lowercase().contains(&filter))
+            .map(|task| {
+                Row::new(vec![Cell::new(task.name), Cell::new(task.display)])
+                    .style(Style::new().fg(theme.row_fg))
+                    .height(1)
+            });
 
         let task_table = Table::new(task_rows, [Constraint::Fill(1), Constraint::Fill(3)])
             .block(
                 Block::default()
                     .borders(Borders::ALL)
                     .title("Task Stack")
-                    .title_style(Style::default().fg(Color::LightCyan)),
+                    .title_style(Style::default().fg(theme.title)),
             )
-            .header(headers.clone());
+            .header(headers.clone())
+            .row_highlight_style(Style::new().bg(theme.highlight_bg));
 
-        let bench_rows = benches.into_iter().map(|bench| {
-            Row::new(vec![Cell::new(bench.name), Cell::new(bench.display)])
-                .style(Style::new().fg(tailwind::SLATE.c200))
-                .height(1)
-        });
+        let bench_rows = Self::benchmark_rows(&displays.benches, &filter)
+            .into_iter()
+            .map(|(group, bench)| match bench {
+                Some(bench) => Row::new(vec![Cell::new(bench.name.as_str()), Cell::new(bench.display.as_str())])
+                    .style(Style::new().fg(theme.row_fg))
+                    .height(1),
+                None => Row::new(vec![Cell::new(group), Cell::new("")])
+                    .style(Style::new().fg(theme.title))
+                    .height(1),
+            })
+            .collect::<Vec<_>>();
 
         let benchmark_table = Table::new(bench_rows, [Constraint::Fill(1), Constraint::Fill(3)])
             .block(
                 Block::default()
                     .borders(Borders::ALL)
                     .title("Benchmarks")
-                    .title_style(Style::default().fg(Color::LightCyan)),
+                    .title_style(Style::default().fg(theme.title)),
+            )
+            .header(headers)
+            .row_highlight_style(Style::new().bg(theme.highlight_bg));
+
+        let queue_headers = ["Subsystem", "Pending"]
+            .into_iter()
+            .map(Cell::from)
+            .collect::<Row>()
+            .style(
+                Style::new()
+                    .fg(theme.header_fg)
+                    .bg(theme.header_bg),
             )
-            .header(headers);
+            .height(1);
+
+        let queue_rows = displays.queues.iter()
+            .map(|queue| {
+                Row::new(vec![Cell::new(queue.name.as_str()), Cell::new(queue.display.as_str())])
+                    .style(Style::new().fg(theme.row_fg))
+                    .height(1)
+            });
+
+        let queue_table = Table::new(queue_rows, [Constraint::Fill(3), Constraint::Fill(1)])
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title("Queues")
+                    .title_style(Style::default().fg(theme.title)),
+            )
+            .header(queue_headers);
 
         let logger = TuiLoggerWidget::default()
             .block(
                 Block::bordered()
                     .title("Log")
-                    .title_style(Style::default().fg(Color::LightCyan)),
+                    .title_style(Style::default().fg(theme.title)),
             )
             .output_separator('|')
             .output_timestamp(None)
@@ -131,59 +570,875 @@ impl TerminalSubsystem {
             .output_target(false)
             .output_file(false)
             .output_line(false)
-            .style_error(Style::default().fg(Color::Red))
-            .style_warn(Style::default().fg(Color::Yellow))
-            .style_info(Style::default().fg(Color::Green))
-            .style_trace(Style::default().fg(Color::Blue))
-            .style_debug(Style::default().fg(Color::Magenta));
+            .style_error(Style::default().fg(theme.error))
+            .style_warn(Style::default().fg(theme.warn))
+            .style_info(Style::default().fg(theme.info))
+            .style_trace(Style::default().fg(theme.trace))
+            .style_debug(Style::default().fg(theme.debug));
 
         match view {
             TermView::Tasks => {
+                let search_height = match search {
+                    Some(_) => 3,
+                    None => 0,
+                };
+
+                let stats_height = match displays.stats {
+                    Some(_) => 3,
+                    None => 0,
+                };
+
+                let status_height = match displays.status {
+                    Some(_) => 3,
+                    None => 0,
+                };
+
                 let layout = Layout::default()
                     .direction(Direction::Vertical)
-                    .constraints(vec![Constraint::Percentage(50), Constraint::Percentage(50)])
+                    .constraints(vec![
+                        Constraint::Percentage(50),
+                        Constraint::Percentage(50),
+                        Constraint::Length(search_height),
+                        Constraint::Length(stats_height),
+                        Constraint::Length(status_height),
+                    ])
                     .split(frame.area());
 
-                frame.render_widget(task_table, layout[0]);
-                frame.render_widget(benchmark_table, layout[1]);
+                let bench_layout = Layout::default()
+                    .direction(Direction::Horizontal)
+                    .constraints(vec![Constraint::Fill(3), Constraint::Fill(1)])
+                    .split(layout[1]);
+
+                frame.render_stateful_widget(task_table, layout[0], task_state);
+                frame.render_stateful_widget(benchmark_table, bench_layout[0], bench_state);
+                frame.render_widget(queue_table, bench_layout[1]);
+
+                if let Some(search) = search {
+                    let search_box = Paragraph::new(format!("/{}", search))
+                        .style(Style::new().fg(theme.row_fg))
+                        .block(
+                            Block::bordered()
+                                .title("Search")
+                                .title_style(Style::default().fg(theme.title)),
+                        );
+
+                    frame.render_widget(search_box, layout[2]);
+                }
+
+                if let Some(stats) = displays.stats {
+                    let stats_box = Paragraph::new(stats)
+                        .style(Style::new().fg(theme.row_fg))
+                        .block(
+                            Block::bordered()
+                                .title("Process")
+                                .title_style(Style::default().fg(theme.title)),
+                        );
+
+                    frame.render_widget(stats_box, layout[3]);
+                }
+
+                if let Some(status) = displays.status {
+                    let status_box = Paragraph::new(status)
+                        .style(Style::new().fg(theme.row_fg))
+                        .block(
+                            Block::bordered()
+                                .title("Status")
+                                .title_style(Style::default().fg(theme.title)),
+                        );
+
+                    frame.render_widget(status_box, layout[4]);
+                }
+
+                if let Some(bench) = displays.detail {
+                    Self::detail_popup(frame, theme, &bench);
+                }
+
+                vec![layout[0], bench_layout[0]]
             }
             TermView::Log => {
                 frame.render_widget(logger, frame.area());
+
+                Vec::new()
+            }
+            TermView::Graph => {
+                Self::graph_view(frame, theme, &displays.queues, &displays.subscriptions);
+
+                Vec::new()
             }
         }
     }
 
+    /// Subsystems with their queue length beside a source -> target list of subscriptions.
+    fn graph_view(frame: &mut Frame, theme: &Theme, subsystems: &[tasks::Display], subscriptions: &[SubscriptionInfo]) {
+        let header_style = Style::new()
+            .fg(theme.header_fg)
+            .bg(theme.header_bg);
+
+        let subsystem_rows = subsystems
+            .iter()
+            .map(|subsystem| {
+                Row::new(vec![Cell::new(subsystem.name.as_str()), Cell::new(subsystem.display.as_str())])
+                    .style(Style::new().fg(theme.row_fg))
+            });
+
+        let subsystem_table = Table::new(subsystem_rows, [Constraint::Fill(3), Constraint::Fill(1)])
+            .block(
+                Block::bordered()
+                    .title("Subsystems")
+                    .title_style(Style::default().fg(theme.title)),
+            )
+            .header(Row::new(["Subsystem", "Pending"]).style(header_style));
+
+        let subscription_rows = subscriptions
+            .iter()
+            .map(|subscription| {
+                Row::new(vec![
+                    Cell::new(subscription.source),
+                    Cell::new("->"),
+                    Cell::new(subscription.target),
+                ])
+                .style(Style::new().fg(theme.row_fg))
+            });
+
+        let subscription_table = Table::new(
+                subscription_rows,
+                [Constraint::Fill(1), Constraint::Length(2), Constraint::Fill(1)],
+            )
+            .block(
+                Block::bordered()
+                    .title("Subscriptions")
+                    .title_style(Style::default().fg(theme.title)),
+            )
+            .header(Row::new(["Source", "", "Target"]).style(header_style));
+
+        let [subsystem_area, subscription_area] = Layout::horizontal([Constraint::Fill(1), Constraint::Fill(2)])
+            .areas(frame.area());
+
+        frame.render_widget(subsystem_table, subsystem_area);
+        frame.render_widget(subscription_table, subscription_area);
+    }
+
+    fn detail_popup(frame: &mut Frame, theme: &Theme, bench: &BenchmarkLog) {
+        let samples = bench.samples
+            .iter()
+            .rev()
+            .take(DETAIL_SAMPLES)
+            .map(chrono::format_duration_compact)
+            .collect::<Vec<_>>();
+
+        let lines = vec![
+            format!("Runs     {}", bench.runs),
+            format!("Last     {}", chrono::format_duration_compact(&bench.duration)),
+            format!("Average  {}", chrono::format_duration_compact(&bench.average)),
+            format!("Min      {}", chrono::format_duration_compact(&bench.min)),
+            format!("Max      {}", chrono::format_duration_compact(&bench.max)),
+            format!("p50      {}", chrono::format_duration_compact(&bench.percentile(0.50))),
+            format!("p95      {}", chrono::format_duration_compact(&bench.percentile(0.95))),
+            format!("p99      {}", chrono::format_duration_compact(&bench.percentile(0.99))),
+            String::new(),
+            format!("Last {} samples", samples.len()),
+        ];
+
+        let text = lines
+            .into_iter()
+            .chain(samples)
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        let [area] = Layout::vertical([Constraint::Percentage(60)])
+            .flex(Flex::Center)
+            .areas(frame.area());
+
+        let [area] = Layout::horizontal([Constraint::Percentage(50)])
+            .flex(Flex::Center)
+            .areas(area);
+
+        let detail_box = Paragraph::new(text)
+            .style(Style::new().fg(theme.row_fg).bg(theme.header_bg))
+            .block(
+                Block::bordered()
+                    .title(bench.name)
+                    .title_style(Style::default().fg(theme.title)),
+            );
+
+        frame.render_widget(Clear, area);
+        frame.render_widget(detail_box, area);
+    }
+
+    /// `theme`, less the log colors while they're toggled off.
+    fn active_theme(&self) -> Theme {
+        match self.color {
+            true => self.theme.clone(),
+            false => self.theme.without_log_colors(),
+        }
+    }
+
     async fn events(&mut self) -> Result<()> {
         if event::poll(std::time::Duration::from_secs(0))? {
             info!("Checking events...");
-            if let Event::Key(key) = event::read()? {
-                if key.kind == event::KeyEventKind::Press && key.code == event::KeyCode::Char('q') {
-                    self.channels
-                        .get::<engine::EngineSubsystem>()
-                        .send_mut(engine::RequestQuit);
-                    
-                    info!("Here");
-                };
+            let event = event::read()?;
 
-                if key.kind == event::KeyEventKind::Press && key.code == event::KeyCode::Char('1') {
-                    self.view = TermView::Tasks;
-                }
+            // Skipped frames would leave a resized terminal stale.
+            if let Event::Resize(..) = event {
+                self.redraw = true;
+            }
 
-                if key.kind == event::KeyEventKind::Press && key.code == event::KeyCode::Char('2') {
-                    self.view = TermView::Log;
-                }
-                
-                if key.kind == event::KeyEventKind::Press && key.code == event::KeyCode::Up {
-                    
-                }
+            if let Event::Mouse(mouse) = event {
+                self.mouse_event(mouse);
+            }
+
+            if let Event::Key(key) = event {
+                self.key_event(key)
+                    .await;
             }
         }
         Ok(())
     }
 
+    async fn key_event(&mut self, key: event::KeyEvent) {
+        if self.searching {
+            self.search_event(key);
+            return;
+        }
+
+        if key.kind == event::KeyEventKind::Press && key.code == event::KeyCode::Char('/') {
+            self.searching = true;
+            self.redraw = true;
+        }
+
+        let interrupt = key.code == event::KeyCode::Char('c')
+            && key.modifiers.contains(event::KeyModifiers::CONTROL);
+
+        if key.kind == event::KeyEventKind::Press && (key.code == event::KeyCode::Char('q') || interrupt) {
+            self.channels
+                .get::<engine::EngineSubsystem>()
+                .send_mut(engine::RequestQuit);
+            
+            info!("Here");
+        };
+
+        if key.kind == event::KeyEventKind::Press && key.code == event::KeyCode::Enter {
+            self.detail = self.bench_state.selected().is_some();
+            self.redraw = true;
+        }
+
+        if key.kind == event::KeyEventKind::Press && key.code == event::KeyCode::Esc {
+            self.detail = false;
+            self.redraw = true;
+        }
+
+        if key.kind == event::KeyEventKind::Press && key.code == event::KeyCode::Char('c') && !interrupt {
+            self.color = !self.color;
+            self.redraw = true;
+        }
+
+        if key.kind == event::KeyEventKind::Press && key.code == event::KeyCode::Char('t') {
+            self.theme = self.theme.next();
+            self.redraw = true;
+        }
+
+        if key.kind == event::KeyEventKind::Press && key.code == event::KeyCode::Char('s') {
+            self.export_benchmarks()
+                .await;
+        }
+
+        if key.kind == event::KeyEventKind::Press && key.code == event::KeyCode::Char('1') {
+            self.view = TermView::Tasks;
+            self.redraw = true;
+        }
+
+        if key.kind == event::KeyEventKind::Press && key.code == event::KeyCode::Char('2') {
+            self.view = TermView::Log;
+            self.redraw = true;
+        }
+
+        if key.kind == event::KeyEventKind::Press && key.code == event::KeyCode::Char('3') {
+            self.view = TermView::Graph;
+            self.redraw = true;
+        }
+        
+        if key.kind == event::KeyEventKind::Press && key.code == event::KeyCode::Up {
+            
+        }
+    }
+
+    /// Snapshots the benchmarks to a timestamped CSV in the working directory and
+    /// flashes where it went, or why it failed, in the status box.
+    async fn export_benchmarks(&mut self) {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|since_epoch| since_epoch.as_secs())
+            .unwrap_or_default();
+
+        self.export_benchmarks_to(format!("benchmarks-{}.csv", timestamp))
+            .await;
+    }
+
+    async fn export_benchmarks_to(&mut self, path: String) {
+        let export_result = self.channels
+            .get::<tasks::TasksSubsystem>()
+            .send(tasks::ExportBenchmarksCsv { path: path.clone() })
+            .await;
+
+        let message = match export_result {
+            Ok(Ok(())) => format!("Benchmarks exported to {}", path),
+            Ok(Err(err)) => format!("Benchmark export failed: {}", err),
+            Err(err) => format!("Benchmark export failed: {}", err),
+        };
+
+        info!("Terminal: {}", message);
+
+        self.flash = Some((message, Instant::now()));
+        self.redraw = true;
+    }
+
+    fn search_event(&mut self, key: event::KeyEvent) {
+        if key.kind != event::KeyEventKind::Press {
+            return;
+        }
+
+        match key.code {
+            event::KeyCode::Esc => {
+                self.filter.clear();
+                self.searching = false;
+            },
+            event::KeyCode::Enter => {
+                self.searching = false;
+            },
+            event::KeyCode::Backspace => {
+                self.filter.pop();
+            },
+            event::KeyCode::Char(c) => {
+                self.filter.push(c);
+            },
+            _ => return,
+        }
+
+        self.task_state.select(None);
+        self.bench_state.select(None);
+        self.redraw = true;
+    }
+
+    fn mouse_event(&mut self, mouse: MouseEvent) {
+        let position = Position::new(mouse.column, mouse.row);
+
+        let Some(table_index) = self.table_areas
+            .iter()
+            .position(|area| area.contains(position))
+        else {
+            return;
+        };
+
+        let table_area = self.table_areas[table_index];
+
+        let table_state = match table_index {
+            0 => &mut self.task_state,
+            _ => &mut self.bench_state,
+        };
+
+        match mouse.kind {
+            MouseEventKind::ScrollDown => table_state.scroll_down_by(1),
+            MouseEventKind::ScrollUp => table_state.scroll_up_by(1),
+            MouseEventKind::Down(MouseButton::Left) => {
+                // Rows start below the top border and the header row.
+                let first_row = table_area.y + 2;
+                let last_row = table_area.bottom().saturating_sub(1);
+
+                if (first_row..last_row).contains(&mouse.row) {
+                    let row = table_state.offset() + (mouse.row - first_row) as usize;
+                    table_state.select(Some(row));
+                }
+            },
+            _ => return,
+        }
+
+        self.redraw = true;
+    }
+
     #[titan_core::task]
     fn shutdown(&self) -> Result<()> {
+        if self.terminal.is_none() {
+            return Ok(());
+        }
+
+        execute!(io::stdout(), DisableMouseCapture)?;
         ratatui::restore();
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{tests::start_headless, TitanConfig};
+    use ratatui::{backend::TestBackend, buffer::Buffer, crossterm::event::KeyModifiers};
+    use tokio::time;
+
+    fn terminal_subsystem() -> TerminalSubsystem {
+        TerminalSubsystem {
+            channels: Channels::default(),
+            terminal: None,
+            view: TermView::Tasks,
+            task_displays: Vec::new(),
+            refresh_interval: Duration::from_millis(100),
+            last_draw: None,
+            redraw: false,
+            task_state: TableState::default(),
+            bench_state: TableState::default(),
+            table_areas: Vec::new(),
+            filter: String::new(),
+            searching: false,
+            color: true,
+            theme: Theme::default(),
+            detail: false,
+            last_hash: None,
+            log_history: LogHistory::default(),
+            last_status: None,
+            flash: None,
+        }
+    }
+
+    /// Lays out `displays` on a 60x20 test terminal the way `draw` would, keeping the table areas.
+    fn draw(terminal: &mut TerminalSubsystem, displays: TermDisplays) -> Buffer {
+        let mut backend = Terminal::new(TestBackend::new(60, 20))
+            .unwrap();
+
+        let search = match terminal.searching || !terminal.filter.is_empty() {
+            true => Some(terminal.filter.as_str()),
+            false => None,
+        };
+
+        backend
+            .draw(|f| {
+                terminal.table_areas = TerminalSubsystem::ui(
+                    f,
+                    &terminal.view,
+                    &mut terminal.task_state,
+                    &mut terminal.bench_state,
+                    search,
+                    &terminal.theme,
+                    displays,
+                );
+            })
+            .unwrap();
+
+        backend
+            .backend()
+            .buffer()
+            .clone()
+    }
+
+    fn text(buffer: &Buffer) -> String {
+        buffer.content()
+            .chunks(buffer.area.width as usize)
+            .map(|line| line.iter().map(|cell| cell.symbol()).collect::<String>())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    fn key(code: event::KeyCode) -> event::KeyEvent {
+        event::KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    fn task_displays(count: usize) -> Vec<tasks::Display> {
+        (0..count)
+            .map(|index| tasks::Display {
+                name: format!("Task{}", index),
+                display: String::new(),
+            })
+            .collect()
+    }
+
+    fn mouse(kind: MouseEventKind, column: u16, row: u16) -> MouseEvent {
+        MouseEvent {
+            kind,
+            column,
+            row,
+            modifiers: KeyModifiers::NONE,
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn renders_are_throttled_to_the_refresh_interval() {
+        let mut terminal = terminal_subsystem();
+        let mut draws = 0;
+
+        // One simulated second of 10ms engine frames against a 100ms refresh interval.
+        for _ in 0..100 {
+            if terminal.draw_due() {
+                draws += 1;
+                terminal.last_draw = Some(Instant::now());
+            }
+
+            time::advance(Duration::from_millis(10))
+                .await;
+        }
+
+        assert_eq!(draws, 10);
+
+        terminal.redraw = true;
+        assert!(terminal.draw_due());
+    }
+
+    #[test]
+    fn scrolling_the_task_table_moves_its_offset() {
+        let mut terminal = terminal_subsystem();
+
+        draw(&mut terminal, TermDisplays { tasks: task_displays(20), ..Default::default() });
+
+        let task_area = terminal.table_areas[0];
+
+        for _ in 0..10 {
+            terminal.mouse_event(mouse(MouseEventKind::ScrollDown, task_area.x + 1, task_area.y + 3));
+        }
+
+        assert_eq!(terminal.task_state.selected(), Some(10));
+        assert_eq!(terminal.bench_state.selected(), None);
+
+        draw(&mut terminal, TermDisplays { tasks: task_displays(20), ..Default::default() });
+
+        assert!(terminal.task_state.offset() > 0);
+    }
+
+    #[test]
+    fn clicking_a_row_selects_it() {
+        let mut terminal = terminal_subsystem();
+
+        draw(&mut terminal, TermDisplays { tasks: task_displays(20), ..Default::default() });
+
+        let task_area = terminal.table_areas[0];
+
+        // Below the top border and the header, so the third row.
+        terminal.mouse_event(mouse(MouseEventKind::Down(MouseButton::Left), task_area.x + 1, task_area.y + 4));
+
+        assert_eq!(terminal.task_state.selected(), Some(2));
+        assert!(terminal.redraw);
+    }
+
+    #[test]
+    fn search_filter_narrows_the_visible_rows() {
+        let mut terminal = terminal_subsystem();
+
+        let displays = || TermDisplays {
+            tasks: ["Render", "Update", "PreRender"]
+                .into_iter()
+                .map(|name| tasks::Display { name: name.to_string(), display: String::new() })
+                .collect(),
+            ..Default::default()
+        };
+
+        terminal.searching = true;
+        "REN".chars().for_each(|c| terminal.search_event(key(event::KeyCode::Char(c))));
+
+        assert_eq!(terminal.filter, "REN");
+
+        let filtered = text(&draw(&mut terminal, displays()));
+
+        assert!(filtered.contains("Render"));
+        assert!(filtered.contains("PreRender"));
+        assert!(!filtered.contains("Update"));
+
+        terminal.search_event(key(event::KeyCode::Esc));
+
+        assert!(terminal.filter.is_empty());
+        assert!(!terminal.searching);
+        assert!(text(&draw(&mut terminal, displays())).contains("Update"));
+    }
+
+    #[tokio::test]
+    async fn color_toggle_drops_the_log_colors() {
+        let mut terminal = terminal_subsystem();
+
+        assert_eq!(terminal.active_theme().error, Color::Red);
+
+        terminal.key_event(key(event::KeyCode::Char('c')))
+            .await;
+
+        let theme = terminal.active_theme();
+        let log_colors = [theme.error, theme.warn, theme.info, theme.trace, theme.debug];
+
+        assert!(log_colors.iter().all(|color| *color == Color::Reset));
+        assert_eq!(theme.title, terminal.theme.title);
+        assert!(terminal.redraw);
+
+        terminal.key_event(key(event::KeyCode::Char('c')))
+            .await;
+
+        assert_eq!(terminal.active_theme().error, Color::Red);
+    }
+
+    #[tokio::test]
+    async fn non_tty_init_falls_back_to_plain_logging() {
+        let mut terminal = terminal_subsystem();
+
+        terminal.init_for(false)
+            .await
+            .unwrap();
+
+        assert!(terminal.terminal.is_none());
+        assert!(terminal.render().await.is_ok());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn enter_opens_the_selected_benchmark_detail() {
+        let mut terminal = terminal_subsystem();
+
+        let engine = start_headless(TitanConfig::default())
+            .await;
+
+        terminal.channels = engine.clone();
+
+        let tasks_ref = terminal.channels.get::<tasks::TasksSubsystem>();
+
+        tasks_ref.send(tasks::StartBenchmark { name: "test::Frame", group: "test" })
+            .await
+            .unwrap();
+
+        for end in [0.01, 0.02, 0.03] {
+            tasks_ref.send(tasks::EndBenchmark { name: "test::Frame", end })
+                .await
+                .unwrap();
+        }
+
+        let groups = tasks_ref.send(tasks::GetBenchmarkGroups)
+            .await
+            .unwrap();
+
+        let row = TerminalSubsystem::benchmark_rows(&groups, "")
+            .iter()
+            .position(|(_, bench)| bench.is_some_and(|bench| bench.name == "test::Frame"))
+            .unwrap();
+
+        terminal.bench_state.select(Some(row));
+
+        terminal.key_event(key(event::KeyCode::Enter))
+            .await;
+
+        assert!(terminal.detail);
+
+        let detail = terminal.selected_benchmark(&groups)
+            .await
+            .unwrap();
+
+        let buffer = draw(&mut terminal, TermDisplays {
+            benches: groups,
+            detail,
+            ..Default::default()
+        });
+
+        let text = text(&buffer);
+
+        assert!(text.contains("test::Frame"));
+        assert!(text.contains("Runs     3"));
+        assert!(text.contains(&format!("p50      {}", chrono::format_duration_compact(&0.02))));
+    }
+
+    #[tokio::test]
+    async fn switching_themes_changes_the_styles() {
+        let mut terminal = terminal_subsystem();
+
+        let uses_header_bg = |buffer: &Buffer, theme: &Theme| buffer.content()
+            .iter()
+            .any(|cell| cell.bg == theme.header_bg);
+
+        let displays = || TermDisplays {
+            tasks: task_displays(3),
+            ..Default::default()
+        };
+
+        let dark = draw(&mut terminal, displays());
+
+        assert!(uses_header_bg(&dark, &Theme::dark()));
+
+        terminal.key_event(key(event::KeyCode::Char('t')))
+            .await;
+
+        assert_eq!(terminal.theme.name, "light");
+
+        let light = draw(&mut terminal, displays());
+
+        assert!(uses_header_bg(&light, &Theme::light()));
+        assert!(!uses_header_bg(&light, &Theme::dark()));
+    }
+
+    #[test]
+    fn unchanged_frames_skip_the_draw() {
+        let mut terminal = terminal_subsystem();
+        let mut backend = Terminal::new(TestBackend::new(60, 20))
+            .unwrap();
+
+        let displays = || TermDisplays {
+            tasks: task_displays(3),
+            ..Default::default()
+        };
+
+        for _ in 0..3 {
+            terminal.draw_frame(&mut backend, displays())
+                .unwrap();
+        }
+
+        assert_eq!(backend.get_frame().count(), 1);
+
+        terminal.task_state.select(Some(1));
+
+        terminal.draw_frame(&mut backend, displays())
+            .unwrap();
+
+        assert_eq!(backend.get_frame().count(), 2);
+
+        terminal.redraw = true;
+
+        terminal.draw_frame(&mut backend, displays())
+            .unwrap();
+
+        assert_eq!(backend.get_frame().count(), 3);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn graph_view_lists_subsystems_and_subscriptions() {
+        let mut terminal = terminal_subsystem();
+
+        let engine = start_headless(TitanConfig::default())
+            .await;
+
+        terminal.channels = engine.clone();
+
+        terminal.channels.subscribe_fn::<crate::random::Seed>(|_| {})
+            .await;
+
+        terminal.key_event(key(event::KeyCode::Char('3')))
+            .await;
+
+        assert!(matches!(terminal.view, TermView::Graph));
+
+        let queues = terminal.channels
+            .pending_lens()
+            .unwrap()
+            .into_iter()
+            .map(|(name, pending)| tasks::Display {
+                name: name.to_string(),
+                display: pending.to_string(),
+            })
+            .collect();
+
+        let displays = TermDisplays {
+            queues,
+            subscriptions: terminal.channels.subscriptions().await,
+            ..Default::default()
+        };
+
+        // Wide enough that the task names aren't cut off.
+        let mut backend = Terminal::new(TestBackend::new(120, 20))
+            .unwrap();
+
+        terminal.draw_frame(&mut backend, displays)
+            .unwrap();
+
+        let text = text(backend.backend().buffer());
+
+        assert!(text.contains("RandomSubsystem"));
+        assert!(text.contains("EngineSubsystem"));
+
+        let subscription = text
+            .lines()
+            .find(|line| line.contains("RandomSubsystem::Seed"))
+            .expect("The subscription wasn't listed");
+
+        assert!(subscription.contains("->"));
+        assert!(subscription.contains(" fn "));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn failed_draws_fall_back_to_throttled_status_lines() {
+        let mut terminal = terminal_subsystem();
+
+        terminal.draw_failed("No TTY")
+            .unwrap();
+
+        let first_status = terminal.last_status
+            .expect("No status line after the first failure");
+
+        assert!(terminal.redraw);
+
+        time::advance(Duration::from_millis(100))
+            .await;
+
+        terminal.draw_failed("No TTY")
+            .unwrap();
+
+        assert_eq!(terminal.last_status, Some(first_status));
+
+        time::advance(STATUS_LINE_INTERVAL)
+            .await;
+
+        terminal.draw_failed("No TTY")
+            .unwrap();
+
+        assert!(terminal.last_status > Some(first_status));
+
+        terminal.draw_recovered();
+
+        assert!(terminal.last_status.is_none());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn benchmark_export_flashes_where_it_went() {
+        let mut terminal = terminal_subsystem();
+
+        let engine = start_headless(TitanConfig::default())
+            .await;
+
+        terminal.channels = engine.clone();
+
+        let export_dir = tempfile::tempdir()
+            .unwrap();
+        let path = export_dir.path()
+            .join("benchmarks.csv")
+            .display()
+            .to_string();
+
+        terminal.export_benchmarks_to(path.clone())
+            .await;
+
+        assert!(std::path::Path::new(&path).is_file());
+        assert!(terminal.redraw);
+
+        let displays = TermDisplays {
+            status: terminal.flash.as_ref().map(|(message, _)| message.clone()),
+            ..Default::default()
+        };
+
+        let mut backend = Terminal::new(TestBackend::new(120, 20))
+            .unwrap();
+
+        terminal.draw_frame(&mut backend, displays)
+            .unwrap();
+
+        let text = text(backend.backend().buffer());
+
+        assert!(text.contains("Status"), "{}", text);
+        assert!(text.contains(&format!("Benchmarks exported to {}", path)), "{}", text);
+    }
+
+    #[tokio::test]
+    async fn failed_export_on_s_flashes_the_error() {
+        let mut terminal = terminal_subsystem();
+
+        // Registered but stopped, so the export can't reach it.
+        let (tasks_ref, tasks_receiver) = titan_core::SubsystemRef::<tasks::TasksSubsystem>::new();
+        drop(tasks_receiver);
+
+        terminal.channels.add(tasks_ref)
+            .await;
+
+        terminal.key_event(key(event::KeyCode::Char('s')))
+            .await;
+
+        let (message, _) = terminal.flash
+            .as_ref()
+            .unwrap();
+
+        assert!(message.starts_with("Benchmark export failed"), "{}", message);
+        assert!(terminal.redraw);
+    }
+}
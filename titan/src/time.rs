@@ -0,0 +1,147 @@
+use std::time::Duration;
+use titan_core::{info, runtime::time::Instant, Channels, Result, anyhow};
+
+/// Engine clock, ticked once per frame by the engine. Time can be scaled for slow motion
+/// or fast forward, or driven by hand with `advance` to make frame deltas deterministic.
+pub struct TimeSubsystem {
+    pub channels: Channels,
+    /// Multiplier from wall time to engine time.
+    pub scale: f64,
+    /// Scaled time of the last tick.
+    pub delta: Duration,
+    /// Scaled time across all ticks.
+    pub now: Duration,
+    /// Unscaled time across all ticks.
+    pub total_elapsed: Duration,
+    pub last_tick: Option<Instant>,
+    /// Set by `advance`, after which ticks only move by the time advanced.
+    pub manual: bool,
+    /// Time advanced since the last tick in manual mode.
+    pub pending: Duration,
+}
+
+impl TimeSubsystem {
+    pub fn new(channels: Channels) -> Self {
+        Self {
+            channels,
+            scale: 1.0,
+            delta: Duration::ZERO,
+            now: Duration::ZERO,
+            total_elapsed: Duration::ZERO,
+            last_tick: None,
+            manual: false,
+            pending: Duration::ZERO,
+        }
+    }
+}
+
+#[titan_core::subsystem]
+impl TimeSubsystem {
+
+    /// Moves the clock on by the time since the last tick, or by what was `advance`d in
+    /// manual mode, and returns the scaled delta.
+    #[titan_core::task]
+    fn tick(&mut self) -> Duration {
+        let tick = Instant::now();
+
+        let elapsed = match self.manual {
+            true => std::mem::take(&mut self.pending),
+            false => self.last_tick.map_or(Duration::ZERO, |last_tick| tick - last_tick),
+        };
+
+        self.last_tick = Some(tick);
+        self.delta = elapsed.mul_f64(self.scale);
+        self.now += self.delta;
+        self.total_elapsed += elapsed;
+
+        self.delta
+    }
+
+    /// Scaled time since the clock started, as of the last tick.
+    #[titan_core::task]
+    fn now(&self) -> Duration {
+        self.now
+    }
+
+    /// Scaled time of the last tick.
+    #[titan_core::task]
+    fn delta(&self) -> Duration {
+        self.delta
+    }
+
+    /// Unscaled time since the clock started, as of the last tick.
+    #[titan_core::task]
+    fn total_elapsed(&self) -> Duration {
+        self.total_elapsed
+    }
+
+    /// 0.5 runs at half speed, 2.0 at double; 0.0 pauses.
+    #[titan_core::task]
+    fn set_scale(&mut self, scale: f64) -> Result<()> {
+        if !scale.is_finite() || scale < 0.0 {
+            return Err(anyhow!("Time: Invalid scale {}", scale));
+        }
+
+        info!("Time: Scale set to {}", scale);
+        self.scale = scale;
+
+        Ok(())
+    }
+
+    /// Switches the clock to manual mode, where the wall clock is ignored and the next
+    /// tick moves by `duration` (scaled). Meant for tests and replays.
+    #[titan_core::task]
+    fn advance(&mut self, duration: Duration) {
+        self.manual = true;
+        self.pending += duration;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use titan_core::runtime::time;
+
+    const FRAME: Duration = Duration::from_millis(16);
+
+    #[tokio::test(start_paused = true)]
+    async fn advance_makes_ticks_deterministic() {
+        let mut clock = TimeSubsystem::new(Channels::default());
+
+        clock.advance(FRAME);
+
+        // Wall time passing in between doesn't count once the clock is manual.
+        time::advance(Duration::from_secs(1))
+            .await;
+
+        assert_eq!(clock.tick(), FRAME);
+        assert_eq!(clock.tick(), Duration::ZERO);
+
+        clock.set_scale(0.5)
+            .unwrap();
+        clock.advance(FRAME);
+        clock.advance(FRAME);
+
+        assert_eq!(clock.tick(), FRAME);
+        assert_eq!(clock.now(), FRAME * 2);
+        assert_eq!(clock.total_elapsed(), FRAME * 3);
+        assert_eq!(clock.delta(), FRAME);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn ticks_follow_the_scaled_wall_clock() {
+        let mut clock = TimeSubsystem::new(Channels::default());
+
+        assert_eq!(clock.tick(), Duration::ZERO);
+
+        clock.set_scale(2.0)
+            .unwrap();
+
+        time::advance(FRAME)
+            .await;
+
+        assert_eq!(clock.tick(), FRAME * 2);
+        assert!(clock.set_scale(-1.0).is_err());
+        assert!(clock.set_scale(f64::INFINITY).is_err());
+    }
+}
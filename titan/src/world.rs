@@ -0,0 +1,140 @@
+use std::{
+    collections::HashSet,
+    sync::{atomic::{AtomicU64, Ordering}, Arc},
+};
+use titan_core::{Channels, DashMap};
+
+/// Tag graphics looks for when gathering entities to draw.
+pub const RENDERABLE_TAG: &str = "renderable";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct EntityId(u64);
+
+/// A game object. Deliberately small: a name, a position, and tags that stand in
+/// for components until there's a real ECS.
+#[derive(Clone, Debug, Default)]
+pub struct Entity {
+    pub name: String,
+    pub position: [f32; 3],
+    pub tags: HashSet<String>,
+}
+
+impl Entity {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            ..Default::default()
+        }
+    }
+
+    pub fn with_position(mut self, position: [f32; 3]) -> Self {
+        self.position = position;
+        self
+    }
+
+    pub fn with_tag(mut self, tag: impl Into<String>) -> Self {
+        self.tags.insert(tag.into());
+        self
+    }
+}
+
+/// Holds the game objects. Entities live in a `DashMap`, so every task takes the
+/// subsystem read lock and they run concurrently.
+pub struct WorldSubsystem {
+    pub channels: Channels,
+    pub entities: DashMap<EntityId, Entity>,
+    pub next_entity_id: Arc<AtomicU64>,
+}
+
+impl WorldSubsystem {
+    pub fn new(channels: Channels) -> Self {
+        Self {
+            channels,
+            entities: DashMap::new(),
+            next_entity_id: Arc::new(AtomicU64::new(0)),
+        }
+    }
+}
+
+#[titan_core::subsystem]
+impl WorldSubsystem {
+
+    #[titan_core::task]
+    fn spawn_entity(&self, entity: Entity) -> EntityId {
+        let id = EntityId(self.next_entity_id.fetch_add(1, Ordering::SeqCst));
+
+        self.entities.insert(id, entity);
+
+        id
+    }
+
+    /// Removes the entity, returning it if it existed.
+    #[titan_core::task]
+    fn despawn_entity(&self, id: EntityId) -> Option<Entity> {
+        self.entities
+            .remove(&id)
+            .map(|(_, entity)| entity)
+    }
+
+    #[titan_core::task]
+    fn get_entity(&self, id: EntityId) -> Option<Entity> {
+        self.entities
+            .get(&id)
+            .map(|entity| entity.clone())
+    }
+
+    /// Every entity carrying `tag`, ordered by id.
+    #[titan_core::task]
+    fn query(&self, tag: String) -> Vec<(EntityId, Entity)> {
+        let mut entities = self.entities
+            .iter()
+            .filter(|entry| entry.tags.contains(&tag))
+            .map(|entry| (*entry.key(), entry.value().clone()))
+            .collect::<Vec<_>>();
+
+        entities.sort_by_key(|(id, _)| *id);
+
+        entities
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn entities_round_trip_and_query_in_id_order() {
+        let world = WorldSubsystem::new(Channels::default());
+
+        let crate_id = world.spawn_entity(
+            Entity::new("crate")
+                .with_position([1.0, 2.0, 3.0])
+                .with_tag(RENDERABLE_TAG),
+        );
+        let trigger_id = world.spawn_entity(Entity::new("trigger"));
+        let lamp_id = world.spawn_entity(
+            Entity::new("lamp")
+                .with_tag(RENDERABLE_TAG),
+        );
+
+        let fetched = world.get_entity(crate_id)
+            .unwrap();
+
+        assert_eq!(fetched.name, "crate");
+        assert_eq!(fetched.position, [1.0, 2.0, 3.0]);
+
+        let renderable = world.query(RENDERABLE_TAG.to_string())
+            .into_iter()
+            .map(|(id, entity)| (id, entity.name))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            renderable,
+            vec![(crate_id, "crate".to_string()), (lamp_id, "lamp".to_string())],
+        );
+
+        assert_eq!(world.despawn_entity(trigger_id).map(|entity| entity.name), Some("trigger".to_string()));
+        assert!(world.get_entity(trigger_id).is_none());
+        assert!(world.despawn_entity(trigger_id).is_none());
+    }
+}